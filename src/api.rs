@@ -1,46 +1,663 @@
-use crate::browser::{connect_or_start_browser, find_outlook_page};
-use anyhow::Result;
+use crate::bail;
+pub use crate::browser::AuthStatus;
+use crate::browser::{
+    Folder, connect_or_start_browser, ensure_signed_in, evaluate, evaluate_json, find_outlook_page,
+};
+use crate::config::Config;
+use crate::error::{Error, Result};
+pub use crate::list::InboxView;
+use crate::settings::Settings;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub id: String,
+    /// Per-item ID that survives new mail arriving in the conversation
+    #[serde(rename = "immutableId", default)]
+    pub immutable_id: Option<String>,
     pub subject: Option<String>,
     pub from: Option<String>,
+    /// Recipients on the To line (reading pane only; empty in list views)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub to: Vec<String>,
+    /// Recipients on the Cc line (reading pane only; empty in list views)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cc: Vec<String>,
+    pub date: Option<String>,
     pub body: Option<String>,
+    #[serde(rename = "bodyHtml", default)]
+    pub body_html: Option<String>,
     pub preview: Option<String>,
     #[serde(default)]
     pub labels: Vec<String>,
+    /// Swatch color per label (CSS color), where the page shows one
+    #[serde(
+        rename = "labelColors",
+        default,
+        skip_serializing_if = "std::collections::BTreeMap::is_empty"
+    )]
+    pub label_colors: std::collections::BTreeMap<String, String>,
     #[serde(rename = "isUnread", default)]
     pub is_unread: bool,
+    #[serde(rename = "isPinned", default)]
+    pub is_pinned: bool,
+    #[serde(rename = "isFlagged", default)]
+    pub is_flagged: bool,
+    /// Messages in the conversation, from the row's thread-count badge (None for single messages)
+    #[serde(
+        rename = "messageCount",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub message_count: Option<u32>,
+    /// Folder the message was listed from, e.g. "Inbox"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+}
+
+/// Local filter over an already-listed set of messages (not an Outlook search)
+#[derive(Debug, Clone, Default)]
+pub struct ListFilter {
+    /// Substring the sender must contain, case-insensitively
+    pub from: Option<String>,
+    /// Substring the subject must contain, case-insensitively
+    pub subject: Option<String>,
+}
+
+impl Message {
+    /// Whether this message passes every criterion set in `filter`
+    pub fn matches(&self, filter: &ListFilter) -> bool {
+        let contains = |field: &Option<String>, needle: &Option<String>| match needle {
+            Some(needle) => field
+                .as_deref()
+                .is_some_and(|f| f.to_lowercase().contains(&needle.to_lowercase())),
+            None => true,
+        };
+        contains(&self.from, &filter.from) && contains(&self.subject, &filter.subject)
+    }
+}
+
+/// Cache file holding the last scraped label list, kept in the config directory
+const LABELS_CACHE: &str = "labels";
+
+/// How long `download_eml` waits for the downloaded file to appear
+const DOWNLOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LabelsCache {
+    /// Unix timestamp (seconds) of the scrape
+    fetched_at: u64,
+    labels: Vec<String>,
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// When a snoozed message should return to the inbox
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnoozeTime {
+    LaterToday,
+    Tomorrow,
+    ThisWeekend,
+    NextWeek,
+    /// A specific date as YYYY-MM-DD
+    Date(String),
+}
+
+impl SnoozeTime {
+    /// Quick-pick label shown in the Snooze submenu
+    fn menu_label(&self) -> &'static str {
+        match self {
+            SnoozeTime::LaterToday => "later today",
+            SnoozeTime::Tomorrow => "tomorrow",
+            SnoozeTime::ThisWeekend => "this weekend",
+            SnoozeTime::NextWeek => "next week",
+            SnoozeTime::Date(_) => "choose a date",
+        }
+    }
+}
+
+impl std::str::FromStr for SnoozeTime {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "later-today" | "later" => Ok(SnoozeTime::LaterToday),
+            "tomorrow" => Ok(SnoozeTime::Tomorrow),
+            "this-weekend" | "weekend" => Ok(SnoozeTime::ThisWeekend),
+            "next-week" => Ok(SnoozeTime::NextWeek),
+            date => {
+                let parts: Vec<&str> = date.split('-').collect();
+                let valid = parts.len() == 3
+                    && [4, 2, 2]
+                        .iter()
+                        .zip(&parts)
+                        .all(|(len, p)| p.len() == *len && p.chars().all(|c| c.is_ascii_digit()));
+                if !valid {
+                    anyhow::bail!(
+                        "Invalid snooze time '{}': use later-today, tomorrow, this-weekend, next-week, or YYYY-MM-DD",
+                        s
+                    );
+                }
+                Ok(SnoozeTime::Date(date.to_string()))
+            }
+        }
+    }
+}
+
+/// How to unsubscribe from a mailing list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Unsubscribe {
+    /// Unsubscribe URL, if one was found
+    pub url: Option<String>,
+    /// The URL expects an RFC 8058 one-click POST instead of being opened
+    pub one_click: bool,
+    /// Outlook's unsubscribe banner already sent the request
+    pub handled_by_outlook: bool,
+    /// Outlook shows its own unsubscribe banner, which `Client::unsubscribe` can click
+    #[serde(default)]
+    pub outlook_banner: bool,
+}
+
+/// JavaScript function finding Outlook's "Unsubscribe" banner link or button outside the body
+const FIND_UNSUBSCRIBE_BANNER_JS: &str = r#"
+    function findUnsubscribeBanner(bodySelector) {
+        const bodyEl = document.querySelector(bodySelector);
+        const candidates = document.querySelectorAll('a, button, [role="link"], [role="button"]');
+        for (const el of candidates) {
+            if (bodyEl && bodyEl.contains(el)) continue;
+            const text = el.textContent?.trim().toLowerCase() || '';
+            const label = el.getAttribute('aria-label')?.toLowerCase() || '';
+            if (text === 'unsubscribe' || label === 'unsubscribe') return el;
+        }
+        return null;
+    }
+"#;
+
+/// Read the List-Unsubscribe and List-Unsubscribe-Post headers out of raw message headers
+/// Only an https URL can be one-click (RFC 8058); a mailto-only header yields its address.
+pub fn parse_list_unsubscribe(headers: &str) -> Option<Unsubscribe> {
+    // Unfold continuation lines so each header is on one line
+    let mut fields: Vec<String> = Vec::new();
+    for line in headers.lines() {
+        if line.starts_with([' ', '\t'])
+            && let Some(last) = fields.last_mut()
+        {
+            last.push(' ');
+            last.push_str(line.trim());
+        } else if line.is_empty() {
+            break;
+        } else {
+            fields.push(line.to_string());
+        }
+    }
+    let header = |name: &str| {
+        fields.iter().find_map(|field| {
+            let (key, value) = field.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_string())
+        })
+    };
+
+    let value = header("List-Unsubscribe")?;
+    let urls: Vec<&str> = value
+        .split(',')
+        .filter_map(|part| {
+            part.trim()
+                .strip_prefix('<')
+                .and_then(|p| p.strip_suffix('>'))
+        })
+        .collect();
+    let https = urls
+        .iter()
+        .find(|u| u.to_ascii_lowercase().starts_with("https:"));
+    let url = https.or(urls.first())?.to_string();
+    let one_click = https.is_some()
+        && header("List-Unsubscribe-Post")
+            .is_some_and(|v| v.eq_ignore_ascii_case("List-Unsubscribe=One-Click"));
+    Some(Unsubscribe {
+        url: Some(url),
+        one_click,
+        handled_by_outlook: false,
+        outlook_banner: false,
+    })
+}
+
+/// A hyperlink found in a message body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Link {
+    pub text: String,
+    pub href: String,
+}
+
+/// A person suggested by the compose To-field autocomplete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contact {
+    pub name: Option<String>,
+    pub email: String,
+}
+
+/// A new message for `Client::save_draft`
+#[derive(Debug, Clone, Default)]
+pub struct Draft {
+    pub to: Vec<String>,
+    pub subject: String,
+    pub body: String,
+    /// Local files to attach
+    pub attachments: Vec<std::path::PathBuf>,
+}
+
+/// A folder in the folder pane
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailFolder {
+    pub name: String,
+    /// Enclosing folder, for subfolders
+    pub parent: Option<String>,
+    /// Unread count from the folder's badge
+    pub unread: Option<u32>,
+    /// Item count, for folders whose badge counts every item (e.g. Drafts)
+    pub total: Option<u32>,
+}
+
+/// Whether a message is in the current list, and its state if so
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum MessageStatus {
+    Present(Box<Message>),
+    Absent { id: String },
+}
+
+/// What `open` left showing in the Outlook tab
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Opened {
+    Folder { name: String },
+    Message { id: String },
+}
+
+/// Outcome of `Client::export_mbox`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MboxExport {
+    /// Messages appended to the file
+    pub written: usize,
+    /// Messages already in the file
+    pub skipped: usize,
+    /// IDs of messages that couldn't be read
+    pub failed: Vec<String>,
+}
+
+/// Which neighbour `read_adjacent` moves to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Next,
+    Previous,
+}
+
+/// Perform an RFC 8058 one-click unsubscribe POST
+pub async fn one_click_unsubscribe(url: &str) -> Result<()> {
+    let resp = reqwest::Client::new()
+        .post(url)
+        .form(&[("List-Unsubscribe", "One-Click")])
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        bail!("Unsubscribe request failed: {}", resp.status());
+    }
+    Ok(())
 }
 
 pub struct Client {
-    port: u16,
+    config: Config,
+    settings: Arc<Settings>,
 }
 
 impl Client {
-    pub fn new(port: u16) -> Self {
-        Self { port }
+    /// Create a client for `config`
+    /// Retry, pacing, max_scroll, localized strings, the message_item selector and the
+    /// cache scope apply to this client's calls only, so clients with different configs
+    /// can be used side by side.
+    pub fn new(config: Config) -> Self {
+        let settings = Arc::new(Settings::new(&config));
+        Self { config, settings }
+    }
+
+    /// Run `f` with this client's settings in effect for the page helpers
+    async fn scoped<T>(&self, f: impl Future<Output = Result<T>>) -> Result<T> {
+        crate::settings::scope(self.settings.clone(), f).await
     }
 
-    pub async fn list_messages(&self, max: u32) -> Result<Vec<Message>> {
-        crate::list::list_messages(self.port, max).await
+    /// Check whether the browser session is signed in to Outlook
+    pub async fn check_auth(&self) -> Result<AuthStatus> {
+        self.scoped(async {
+            let browser = connect_or_start_browser(&self.config).await?;
+            Ok(crate::browser::check_auth(&browser, &self.config).await?)
+        })
+        .await
+    }
+
+    /// Block until the browser session is signed in, or fail after `timeout`
+    pub async fn wait_for_login(&self, timeout: std::time::Duration) -> Result<()> {
+        self.scoped(async { Ok(crate::browser::wait_for_login(&self.config, timeout).await?) })
+            .await
+    }
+
+    pub async fn list_messages(&self, max: u32, view: InboxView) -> Result<Vec<Message>> {
+        self.scoped(async { Ok(crate::list::list_messages(&self.config, max, view).await?) })
+            .await
+    }
+
+    /// `list_messages`, calling `on_batch` with each batch of messages as it is collected
+    pub async fn list_messages_each(
+        &self,
+        max: u32,
+        view: InboxView,
+        mut on_batch: impl FnMut(&[Message]) -> anyhow::Result<()> + Send,
+    ) -> Result<Vec<Message>> {
+        self.scoped(async {
+            Ok(crate::list::list_messages_each(&self.config, max, view, &mut on_batch).await?)
+        })
+        .await
     }
 
     pub async fn list_spam(&self, max: u32) -> Result<Vec<Message>> {
-        crate::list::list_spam(self.port, max).await
+        self.scoped(async { Ok(crate::list::list_spam(&self.config, max).await?) })
+            .await
+    }
+
+    pub async fn list_unread(&self, folder: Folder, max: u32) -> Result<Vec<Message>> {
+        self.scoped(async { Ok(crate::list::list_unread(&self.config, folder, max).await?) })
+            .await
+    }
+
+    pub async fn list_flagged(&self, max: u32) -> Result<Vec<Message>> {
+        self.scoped(async { Ok(crate::list::list_flagged(&self.config, max).await?) })
+            .await
+    }
+
+    pub async fn list_drafts(&self, max: u32) -> Result<Vec<Message>> {
+        self.scoped(async {
+            Ok(crate::list::list_folder(&self.config, Folder::Drafts, max).await?)
+        })
+        .await
     }
 
+    /// Watch the inbox and call `on_new` for each newly arrived message (runs until error)
+    pub async fn watch<F>(&self, interval: std::time::Duration, on_new: F) -> Result<()>
+    where
+        F: FnMut(&Message) -> anyhow::Result<()>,
+    {
+        self.scoped(async {
+            Ok(crate::list::watch_messages(&self.config, interval, on_new).await?)
+        })
+        .await
+    }
+
+    /// Folders in the folder pane, in pane order, with their unread or item counts
+    pub async fn list_folders(&self) -> Result<Vec<MailFolder>> {
+        self.scoped(async { Ok(crate::list::list_folders(&self.config).await?) })
+            .await
+    }
+
+    /// Count messages in a folder, optionally only unread ones
+    pub async fn count(&self, folder: Folder, unread_only: bool) -> Result<usize> {
+        self.scoped(async {
+            Ok(crate::list::count_messages(&self.config, folder, unread_only).await?)
+        })
+        .await
+    }
+
+    /// Map a possibly stale message ID to the ID currently shown in the list
+    pub async fn resolve_id(&self, id: &str) -> Result<String> {
+        self.scoped(async {
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+            Ok(crate::list::resolve_message(&page, id).await?)
+        })
+        .await
+    }
+
+    /// Open a message in the reading pane and read it
+    /// Opening marks the message read; use `peek_message` to avoid that.
     pub async fn get_message(&self, id: &str) -> Result<Message> {
-        crate::list::get_message(self.port, id).await
+        self.scoped(async { Ok(crate::list::get_message(&self.config, id).await?) })
+            .await
     }
 
+    /// Read a message like `get_message`, then mark it unread again if it was unread
+    /// The returned `is_unread` is the state from before it was opened.
+    pub async fn peek_message(&self, id: &str) -> Result<Message> {
+        self.scoped(async {
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let id = crate::list::resolve_message(&page, id).await?;
+            let was_unread = crate::list::find_listed(&page, &id)
+                .await?
+                .is_some_and(|m| m.is_unread);
+
+            let read = self.get_message(&id).await;
+            if was_unread {
+                match (&read, self.set_read(&id, false).await) {
+                    (_, Ok(())) => {}
+                    (Ok(_), Err(e)) => return Err(e),
+                    // The read error is the one worth reporting; the message may not have opened
+                    (Err(_), Err(e)) => {
+                        crate::debug!("peek_message: restoring unread failed: {}", e)
+                    }
+                }
+            }
+            let mut msg = read?;
+            msg.is_unread = was_unread;
+            Ok(msg)
+        })
+        .await
+    }
+
+    /// Open a message, step to the next or previous one with Outlook's Ctrl+. / Ctrl+,
+    /// shortcut and read that one
+    /// If the shortcut doesn't move the selection, the neighbour in list order is read instead.
+    /// The starting message is only opened to step from, so it is left unread if it was;
+    /// with `keep_unread` the message read is treated the same way, as in `peek_message`.
+    pub async fn read_adjacent(
+        &self,
+        id: &str,
+        direction: Direction,
+        keep_unread: bool,
+    ) -> Result<Message> {
+        self.scoped(async {
+            use crate::browser::{click_element, evaluate_json, press_key};
+
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let id = crate::list::resolve_message(&page, id).await?;
+            // Unread state of everything listed, from before opening anything marks it read
+            let listed = crate::list::find_matching(&page, &ListFilter::default()).await?;
+            let was_unread = |id: &str| listed.iter().any(|m| m.id == id && m.is_unread);
+            click_element(&page, &crate::browser::message_selector(&id), Some(1000)).await?;
+            let key = match direction {
+                Direction::Next => ".",
+                Direction::Previous => ",",
+            };
+            press_key(&page, key, Some(&["Ctrl"]), Some(0)).await?;
+
+            let selected_script = format!(
+                r#"
+                JSON.stringify(document.querySelector(':is(' + {item} + ')[aria-selected="true"]')?.getAttribute('data-convid') || '')
+            "#,
+                item = crate::browser::message_item_js()
+            );
+            let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(3);
+            let adjacent = loop {
+                let selected: String =
+                    evaluate_json(&page, "read_adjacent:selected", &selected_script).await?;
+                if !selected.is_empty() && selected != id {
+                    break selected;
+                }
+                if tokio::time::Instant::now() >= deadline {
+                    crate::debug!(
+                        "{:?} shortcut didn't move the selection; using list order",
+                        direction
+                    );
+                    let Some(pos) = listed.iter().position(|m| m.id == id) else {
+                        return Err(Error::MessageNotFound { id });
+                    };
+                    let neighbour = match direction {
+                        Direction::Next => listed.get(pos + 1),
+                        Direction::Previous => pos.checked_sub(1).and_then(|p| listed.get(p)),
+                    };
+                    match neighbour {
+                        Some(m) => break m.id.clone(),
+                        None => bail!(
+                            "No {} message after {} in the current list",
+                            if direction == Direction::Next {
+                                "next"
+                            } else {
+                                "previous"
+                            },
+                            id
+                        ),
+                    }
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+            };
+
+            let read = crate::list::get_message(&self.config, &adjacent).await;
+            let mut restore = Vec::new();
+            if was_unread(&id) {
+                restore.push(id.as_str());
+            }
+            if keep_unread && was_unread(&adjacent) {
+                restore.push(adjacent.as_str());
+            }
+            for restore_id in restore {
+                match (&read, self.restore_unread(&page, restore_id).await) {
+                    (_, Ok(())) => {}
+                    (Ok(_), Err(e)) => return Err(e),
+                    (Err(_), Err(e)) => crate::debug!("read_adjacent: restoring unread failed: {}", e),
+                }
+            }
+            let mut msg = read?;
+            msg.is_unread = was_unread(&adjacent);
+            Ok(msg)
+        })
+        .await
+    }
+
+    /// Mark a message unread again unless it still is (opening it may not have marked it read)
+    async fn restore_unread(&self, page: &chromiumoxide::Page, id: &str) -> Result<()> {
+        let now_read = crate::list::find_listed(page, id)
+            .await?
+            .is_some_and(|m| !m.is_unread);
+        if now_read {
+            self.set_read(id, false).await?;
+        }
+        Ok(())
+    }
+
+    /// Look a message up in the current folder without touching it
+    /// Stale IDs are resolved by subject and sender first, as for every other action.
+    pub async fn message_status(&self, id: &str) -> Result<MessageStatus> {
+        self.scoped(async {
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let resolved = match crate::list::resolve_message(&page, id).await {
+                Ok(resolved) => resolved,
+                Err(e) if matches!(Error::find(&e), Some(Error::MessageNotFound { .. })) => {
+                    return Ok(MessageStatus::Absent { id: id.to_string() });
+                }
+                Err(e) => return Err(e.into()),
+            };
+            Ok(match crate::list::find_listed(&page, &resolved).await? {
+                Some(msg) => MessageStatus::Present(Box::new(msg)),
+                None => MessageStatus::Absent { id: id.to_string() },
+            })
+        })
+        .await
+    }
+
+    /// Messages in the current folder whose sender/subject match `filter`
+    pub async fn find_messages(&self, filter: &ListFilter) -> Result<Vec<Message>> {
+        self.scoped(async {
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+            Ok(crate::list::find_matching(&page, filter).await?)
+        })
+        .await
+    }
+
+    /// The listed state of each of `ids` in the current view, None for any not listed
+    pub async fn find_listed(&self, ids: &[String]) -> Result<Vec<Option<Message>>> {
+        self.scoped(async {
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let mut found = Vec::with_capacity(ids.len());
+            for id in ids {
+                found.push(crate::list::find_listed(&page, id).await?);
+            }
+            Ok(found)
+        })
+        .await
+    }
+
+    pub async fn get_thread(&self, id: &str) -> Result<Vec<Message>> {
+        self.scoped(async { Ok(crate::list::get_thread(&self.config, id).await?) })
+            .await
+    }
+
+    /// Add the category named `label` (ignoring case) to a message
     pub async fn add_label(&self, id: &str, label: &str) -> Result<()> {
+        self.scoped(async {
+            let _pace = crate::retry::pace().await;
+            self.toggle_label(id, label, false, true).await
+        })
+        .await
+    }
+
+    /// `add_label`, also accepting the unique category whose name starts with `label`
+    pub async fn add_label_by_prefix(&self, id: &str, label: &str) -> Result<()> {
+        self.scoped(async {
+            let _pace = crate::retry::pace().await;
+            self.toggle_label(id, label, true, true).await
+        })
+        .await
+    }
+
+    /// Remove the category named `label` (ignoring case) from a message
+    pub async fn remove_label(&self, id: &str, label: &str) -> Result<()> {
+        self.scoped(async {
+            let _pace = crate::retry::pace().await;
+            self.toggle_label(id, label, false, false).await
+        })
+        .await
+    }
+
+    /// `remove_label`, also accepting the unique category whose name starts with `label`
+    pub async fn remove_label_by_prefix(&self, id: &str, label: &str) -> Result<()> {
+        self.scoped(async {
+            let _pace = crate::retry::pace().await;
+            self.toggle_label(id, label, true, false).await
+        })
+        .await
+    }
+
+    /// Click `label` in a message's Categorize menu, which toggles it, then wait until
+    /// the list shows the label as `applied`
+    /// With `prefix` a unique category starting with `label` is accepted too.
+    async fn toggle_label(&self, id: &str, label: &str, prefix: bool, applied: bool) -> Result<()> {
         use crate::menu;
 
-        let browser = connect_or_start_browser(self.port).await?;
-        let page = find_outlook_page(&browser).await?;
+        let browser = connect_or_start_browser(&self.config).await?;
+        let page = find_outlook_page(&browser, &self.config).await?;
 
         // Close any existing menus to ensure clean state
         menu::close_menus(&page).await?;
@@ -49,23 +666,28 @@ impl Client {
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
         // Wait for the message to be visible
-        if !menu::wait_for_message(&page, id).await? {
-            anyhow::bail!("Message not found or not visible: {}", id);
-        }
+        let id = &crate::list::resolve_message(&page, id).await?;
+        crate::retry::with_retry(|| async {
+            if !menu::wait_for_message(&page, id).await? {
+                return Err(Error::MessageNotFound { id: id.to_string() }.into());
+            }
+            Ok(())
+        })
+        .await?;
 
         // Step 1: Check if context menu is open, if not right-click to open it
         if !menu::is_context_menu_open(&page).await? {
             let (x, y) = menu::get_message_position(&page, id)
                 .await?
-                .ok_or_else(|| anyhow::anyhow!("Message not found: {}", id))?;
+                .ok_or_else(|| Error::MessageNotFound { id: id.to_string() })?;
 
             menu::open_context_menu_at(&page, x, y).await?;
         }
 
         // Step 2: Check if category submenu is open, if not click Categorize
-        if !menu::is_category_visible(&page, label).await? {
+        if !menu::is_category_visible(&page, label, prefix).await? {
             if !menu::is_categorize_button_visible(&page).await? {
-                anyhow::bail!("Categorize button not found in context menu");
+                bail!("Categorize button not found in context menu");
             }
 
             menu::click_categorize(&page, Some(300)).await?;
@@ -74,8 +696,18 @@ impl Client {
             let mut submenu_opened = false;
             for _ in 0..10 {
                 // Check if submenu is open by looking for common category items
-                if menu::is_category_visible(&page, "category").await?
-                    || menu::is_category_visible(&page, "Manage categories").await?
+                if menu::is_category_visible(
+                    &page,
+                    &crate::strings::localize("New category"),
+                    false,
+                )
+                .await?
+                    || menu::is_category_visible(
+                        &page,
+                        &crate::strings::localize("Manage categories"),
+                        false,
+                    )
+                    .await?
                 {
                     submenu_opened = true;
                     break;
@@ -84,11 +716,13 @@ impl Client {
             }
 
             if !submenu_opened {
-                anyhow::bail!("Category submenu didn't open");
+                return Err(Error::MenuNotOpen {
+                    menu: "Category submenu".to_string(),
+                });
             }
 
             // Check if the specific label exists, create if not
-            if !menu::is_category_visible(&page, label).await? {
+            if !menu::is_category_visible(&page, label, prefix).await? {
                 // Create the missing category
                 menu::create_category(&page, label).await?;
 
@@ -99,7 +733,7 @@ impl Client {
                 // Reopen context menu with retry
                 let (x, y) = menu::get_message_position(&page, id)
                     .await?
-                    .ok_or_else(|| anyhow::anyhow!("Message not found: {}", id))?;
+                    .ok_or_else(|| Error::MessageNotFound { id: id.to_string() })?;
                 menu::right_click(&page, x, y, Some(500)).await?;
 
                 // Wait for context menu
@@ -112,7 +746,9 @@ impl Client {
                     tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
                 }
                 if !menu_opened {
-                    anyhow::bail!("Context menu didn't reopen after category creation");
+                    return Err(Error::MenuNotOpen {
+                        menu: "Context menu".to_string(),
+                    });
                 }
 
                 // Reopen category submenu
@@ -121,238 +757,1771 @@ impl Client {
         }
 
         // Step 3: Click on the category
-        menu::click_category(&page, label, Some(300)).await
-    }
-
-    pub async fn remove_label(&self, id: &str, label: &str) -> Result<()> {
-        // Remove label is the same as add label - clicking toggles the category
-        self.add_label(id, label).await
+        let name = menu::click_category(&page, label, prefix, Some(300)).await?;
+        let settled = Self::wait_for_listed(&page, id, |m| {
+            m.labels.iter().any(|l| l.eq_ignore_ascii_case(&name)) == applied
+        })
+        .await?;
+        if !settled {
+            let change = if applied { "appear on" } else { "go away from" };
+            bail!("Label '{}' did not {} message {}", name, change, id);
+        }
+        Ok(())
     }
 
-    pub async fn get_unsubscribe_url(&self, id: &str) -> Result<Option<String>> {
-        use crate::browser::click_element;
-
-        let browser = connect_or_start_browser(self.port).await?;
-        let page = find_outlook_page(&browser).await?;
+    /// Find how to unsubscribe from the sender of a message, without acting on it
+    /// Prefers the List-Unsubscribe header (one-click when List-Unsubscribe-Post allows it),
+    /// then Outlook's native unsubscribe banner, then unsubscribe links in the body.
+    pub async fn get_unsubscribe(&self, id: &str) -> Result<Option<Unsubscribe>> {
+        self.scoped(async {
+            use crate::browser::click_element;
+
+            match self.get_headers(id).await {
+                Ok(headers) => {
+                    if let Some(unsub) = parse_list_unsubscribe(&headers) {
+                        return Ok(Some(unsub));
+                    }
+                }
+                Err(e) => crate::debug!("no headers for {}: {:#}", id, e),
+            }
 
-        let selector = crate::browser::message_selector(id);
-        click_element(&page, &selector, Some(2000)).await?;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let id = &crate::list::resolve_message(&page, id).await?;
+            let selector = crate::browser::message_selector(id);
+            click_element(&page, &selector, Some(2000)).await?;
+
+            // Outlook shows an "Unsubscribe" banner above the body when the sender
+            // provides a List-Unsubscribe header
+            let banner_script = format!(
+                r#"
+            (() => {{
+                {find_banner}
+                const el = findUnsubscribeBanner({body});
+                if (!el) return JSON.stringify(null);
+                const href = el.getAttribute('href');
+                return JSON.stringify({{ href: href && /^https?:/i.test(href) ? href : null }});
+            }})()
+        "#,
+                find_banner = FIND_UNSUBSCRIBE_BANNER_JS,
+                body = serde_json::to_string(self.config.selectors.reading_pane())?
+            );
+            let banner: Option<serde_json::Value> =
+                evaluate_json(&page, "get_unsubscribe:banner", &banner_script).await?;
+            if let Some(banner) = banner {
+                let url = banner
+                    .get("href")
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+                return Ok(Some(Unsubscribe {
+                    outlook_banner: url.is_none(),
+                    url,
+                    one_click: false,
+                    handled_by_outlook: false,
+                }));
+            }
 
-        // Search for unsubscribe links in the message body
-        let script = r#"
-            (() => {
-                const bodyEl = document.querySelector('div[role="document"]');
+            // Search for unsubscribe links in the message body
+            let script = format!(
+                r#"
+            (() => {{
+                const bodyEl = document.querySelector({body});
                 if (!bodyEl) return null;
 
                 // Look for links with "unsubscribe" in text or href
                 const links = bodyEl.querySelectorAll('a[href]');
-                for (const link of links) {
+                for (const link of links) {{
                     const href = link.href || '';
                     const text = link.textContent?.toLowerCase() || '';
                     if (text.includes('unsubscribe') ||
                         text.includes('opt out') ||
                         text.includes('opt-out') ||
                         href.toLowerCase().includes('unsubscribe') ||
-                        href.toLowerCase().includes('optout')) {
+                        href.toLowerCase().includes('optout')) {{
                         return href;
-                    }
-                }
+                    }}
+                }}
 
                 // Also check for List-Unsubscribe in any visible headers
                 const allText = bodyEl.innerText || '';
                 const match = allText.match(/unsubscribe[:\s]*(https?:\/\/[^\s<>"]+)/i);
-                if (match) {
+                if (match) {{
                     return match[1];
-                }
+                }}
 
                 return null;
-            })()
-        "#;
+            }})()
+        "#,
+                body = serde_json::to_string(self.config.selectors.reading_pane())?
+            );
+
+            let result = evaluate(&page, "get_unsubscribe", script).await?;
+            let url = result.into_value::<Option<String>>().unwrap_or(None);
+            Ok(url.map(|url| Unsubscribe {
+                url: Some(url),
+                one_click: false,
+                handled_by_outlook: false,
+                outlook_banner: false,
+            }))
+        })
+        .await
+    }
 
-        let result = page.evaluate(script).await?;
-        Ok(result.into_value::<Option<String>>().unwrap_or(None))
+    /// Unsubscribe through Outlook's banner: click it and confirm, so Outlook sends the request
+    /// Returns false when the message has no banner to click.
+    pub async fn unsubscribe(&self, id: &str) -> Result<bool> {
+        self.scoped(async {
+            use crate::browser::click_element;
+            use crate::menu::click_dialog_button;
+
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let id = &crate::list::resolve_message(&page, id).await?;
+            let selector = crate::browser::message_selector(id);
+            click_element(&page, &selector, Some(2000)).await?;
+
+            let script = format!(
+                r#"
+            (() => {{
+                {find_banner}
+                const el = findUnsubscribeBanner({body});
+                if (!el) return false;
+                el.click();
+                return true;
+            }})()
+        "#,
+                find_banner = FIND_UNSUBSCRIBE_BANNER_JS,
+                body = serde_json::to_string(self.config.selectors.reading_pane())?
+            );
+            let result = evaluate(&page, "unsubscribe:banner", script).await?;
+            if !result.into_value::<bool>().unwrap_or(false) {
+                return Ok(false);
+            }
+            // Outlook asks for confirmation, then sends the one-click request itself
+            click_dialog_button(&page, &["unsubscribe", "ok", "yes"], None).await?;
+            Ok(true)
+        })
+        .await
+    }
+
+    /// Get hyperlinks from a message body, deduplicated by href
+    /// `mailto:` and `javascript:` links are skipped unless `all` is set
+    pub async fn get_links(&self, id: &str, all: bool) -> Result<Vec<Link>> {
+        self.scoped(async {
+            use crate::browser::click_element;
+
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let id = &crate::list::resolve_message(&page, id).await?;
+            let selector = crate::browser::message_selector(id);
+            click_element(&page, &selector, Some(2000)).await?;
+
+            let script = format!(
+                r#"
+            (() => {{
+                const bodyEl = document.querySelector({body});
+                if (!bodyEl) return JSON.stringify([]);
+                const links = Array.from(bodyEl.querySelectorAll('a[href]')).map(link => ({{
+                    text: link.textContent?.trim() || '',
+                    href: link.href || ''
+                }}));
+                return JSON.stringify(links);
+            }})()
+        "#,
+                body = serde_json::to_string(self.config.selectors.reading_pane())?
+            );
+
+            let links: Vec<Link> = evaluate_json(&page, "get_links", &script).await?;
+
+            let mut seen = std::collections::HashSet::new();
+            Ok(links
+                .into_iter()
+                .filter(|link| !link.href.is_empty())
+                .filter(|link| {
+                    let href = link.href.to_lowercase();
+                    all || !(href.starts_with("mailto:") || href.starts_with("javascript:"))
+                })
+                .filter(|link| seen.insert(link.href.clone()))
+                .collect())
+        })
+        .await
     }
 
     pub async fn archive(&self, id: &str) -> Result<()> {
-        use crate::browser::{click_element, press_key};
+        self.scoped(async { self.remove_from_list(id, "e", "Archive").await })
+            .await
+    }
+
+    pub async fn trash(&self, id: &str) -> Result<()> {
+        self.scoped(async { self.remove_from_list(id, "Delete", "Delete").await })
+            .await
+    }
+
+    /// Select a message and press `key` to move it out of the list
+    /// If the message is still listed afterwards, click the toolbar button labelled `button`
+    /// with real mouse events instead; errors if neither removes it.
+    async fn remove_from_list(&self, id: &str, key: &str, button: &str) -> Result<()> {
+        use crate::browser::{click_element, press_key, wait_until_gone};
 
-        let browser = connect_or_start_browser(self.port).await?;
-        let page = find_outlook_page(&browser).await?;
+        let _pace = crate::retry::pace().await;
+        let browser = connect_or_start_browser(&self.config).await?;
+        let page = find_outlook_page(&browser, &self.config).await?;
 
+        let id = &crate::list::resolve_message(&page, id).await?;
         let selector = crate::browser::message_selector(id);
         click_element(&page, &selector, None).await?;
-        press_key(&page, "e", None, None).await?;
+        press_key(&page, key, None, Some(0)).await?;
+        if wait_until_gone(&page, &selector, 3000).await? {
+            return Ok(());
+        }
+
+        crate::debug!("{} key had no effect on {}; using toolbar button", key, id);
+        let button_selector = format!(
+            "button{}",
+            crate::strings::attr_selector("aria-label", button)
+        );
+        crate::menu::left_click_element(&page, &button_selector, Some(0)).await?;
+        if !wait_until_gone(&page, &selector, 3000).await? {
+            bail!("{} did not remove message {} from the list", button, id);
+        }
         Ok(())
     }
 
-    pub async fn trash(&self, id: &str) -> Result<()> {
-        use crate::browser::{click_element, press_key};
+    /// Send keyboard shortcuts (e.g. `Ctrl+Shift+v`) to the page, one after another
+    /// With `id`, that message is selected first so shortcuts like `r` or `e` act on it
+    pub async fn send_keys(&self, keys: &[String], id: Option<&str>) -> Result<()> {
+        self.scoped(async {
+            use crate::browser::{click_element, parse_key_combo, press_key};
+
+            // Parse everything up front so a typo doesn't leave half a sequence sent
+            let combos = keys
+                .iter()
+                .map(|k| parse_key_combo(k))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            if let Some(id) = id {
+                let id = &crate::list::resolve_message(&page, id).await?;
+                let selector = crate::browser::message_selector(id);
+                click_element(&page, &selector, None).await?;
+            }
+            for (key, modifiers) in &combos {
+                press_key(&page, key, Some(modifiers), None).await?;
+            }
+            Ok(())
+        })
+        .await
+    }
 
-        let browser = connect_or_start_browser(self.port).await?;
-        let page = find_outlook_page(&browser).await?;
+    /// Build a multi-selection: click the first message, Ctrl-click the rest
+    /// Fails unless the number of selected items matches, so a shortcut never hits the wrong set
+    async fn select_messages(page: &chromiumoxide::Page, ids: &[String]) -> Result<()> {
+        use crate::browser::{click_element, ctrl_click_element};
+
+        let Some((first, rest)) = ids.split_first() else {
+            bail!("No messages given");
+        };
+
+        let mut progress = crate::progress::Progress::new("Selecting", ids.len());
+        let first = crate::list::resolve_message(page, first).await?;
+        click_element(page, &crate::browser::message_selector(&first), None).await?;
+        progress.inc();
+        for id in rest {
+            let id = crate::list::resolve_message(page, id).await?;
+            ctrl_click_element(page, &crate::browser::message_selector(&id), Some(150)).await?;
+            progress.inc();
+        }
+        drop(progress);
+
+        let result = crate::browser::evaluate(
+            page,
+            "select_messages:count",
+            format!(
+                r#"document.querySelectorAll(':is(' + {} + ')[aria-selected="true"]').length"#,
+                crate::browser::message_item_js()
+            ),
+        )
+        .await?;
+        let selected = result.into_value::<usize>().unwrap_or(0);
+        if selected != ids.len() {
+            bail!(
+                "Selected {} message(s) but expected {}; not acting on the selection",
+                selected,
+                ids.len()
+            );
+        }
+        Ok(())
+    }
 
-        let selector = crate::browser::message_selector(id);
-        click_element(&page, &selector, None).await?;
-        press_key(&page, "Delete", None, None).await?;
+    /// Select `ids` and press `key` once for the whole selection
+    async fn act_on_selection(&self, ids: &[String], key: &str) -> Result<()> {
+        let browser = connect_or_start_browser(&self.config).await?;
+        let page = find_outlook_page(&browser, &self.config).await?;
+
+        Self::select_messages(&page, ids).await?;
+        crate::browser::press_key(&page, key, None, Some(1000)).await?;
         Ok(())
     }
 
-    pub async fn mark_spam(&self, id: &str) -> Result<()> {
-        use crate::menu::{click_menu_item, open_context_menu};
+    pub async fn archive_many(&self, ids: &[String]) -> Result<()> {
+        self.scoped(async {
+            let _pace = crate::retry::pace().await;
+            self.act_on_selection(ids, "e").await
+        })
+        .await
+    }
+
+    pub async fn trash_many(&self, ids: &[String]) -> Result<()> {
+        self.scoped(async {
+            let _pace = crate::retry::pace().await;
+            self.act_on_selection(ids, "Delete").await
+        })
+        .await
+    }
 
-        let browser = connect_or_start_browser(self.port).await?;
-        let page = find_outlook_page(&browser).await?;
+    pub async fn mark_read_many(&self, ids: &[String]) -> Result<()> {
+        self.scoped(async {
+            let _pace = crate::retry::pace().await;
+            self.act_on_selection(ids, "q").await
+        })
+        .await
+    }
 
-        let selector = crate::browser::message_selector(id);
-        open_context_menu(&page, &selector).await?;
+    /// Select `ids` and move them all to `folder` with a single "Move to" action
+    /// Errors if any of them is still in the current list afterwards
+    pub async fn move_many(&self, ids: &[String], folder: Folder) -> Result<()> {
+        self.scoped(async {
+            use crate::browser::wait_until_gone;
+            use crate::menu::{click_menu_item, open_context_menu};
 
-        // Click "Report" to open submenu
-        click_menu_item(&page, "report", Some(500)).await?;
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
 
-        // Click "Junk" in submenu
-        click_menu_item(&page, "junk", None).await?;
+            let mut resolved = Vec::with_capacity(ids.len());
+            for id in ids {
+                resolved.push(crate::list::resolve_message(&page, id).await?);
+            }
+            Self::select_messages(&page, &resolved).await?;
 
-        Ok(())
+            // Right-clicking an item that is part of the selection acts on the whole selection
+            open_context_menu(&page, &crate::browser::message_selector(&resolved[0])).await?;
+            click_menu_item(&page, "move", Some(500)).await?;
+            click_menu_item(&page, &folder.display_name().to_lowercase(), Some(1000)).await?;
+
+            let mut remaining = Vec::new();
+            for id in &resolved {
+                if !wait_until_gone(&page, &crate::browser::message_selector(id), 3000).await? {
+                    remaining.push(id.as_str());
+                }
+            }
+            if !remaining.is_empty() {
+                bail!(
+                    "Still in the list after moving to {}: {}",
+                    folder.display_name(),
+                    remaining.join(", ")
+                );
+            }
+            Ok(())
+        })
+        .await
     }
 
-    pub async fn unspam(&self, id: &str) -> Result<()> {
-        use crate::browser::navigate_to_junk;
-        use crate::menu::{click_menu_item, open_context_menu};
+    pub async fn mark_spam(&self, id: &str) -> Result<()> {
+        self.scoped(async {
+            use crate::menu::context_menu_action;
 
-        let browser = connect_or_start_browser(self.port).await?;
-        let page = find_outlook_page(&browser).await?;
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
 
-        navigate_to_junk(&page).await?;
+            let id = &crate::list::resolve_message(&page, id).await?;
+            context_menu_action(&page, id, &["report"], Some(&["junk"]))
+                .await?
+                .ensure()?;
+            Ok(())
+        })
+        .await
+    }
 
-        let selector = crate::browser::message_selector(id);
-        open_context_menu(&page, &selector).await?;
+    /// Block the message's sender: Outlook adds them to the blocked list and junks their mail
+    pub async fn block_sender(&self, id: &str) -> Result<()> {
+        self.scoped(async {
+            use crate::menu::{click_dialog_button, context_menu_action};
 
-        // Try "Not junk" first, fall back to "Move to" -> "Inbox"
-        if click_menu_item(&page, "not junk", None).await.is_err() {
-            click_menu_item(&page, "move", Some(500)).await?;
-            click_menu_item(&page, "inbox", None).await?;
-        }
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
 
-        Ok(())
+            let id = &crate::list::resolve_message(&page, id).await?;
+            // "Block" opens a submenu with "Block sender"
+            context_menu_action(&page, id, &["block"], Some(&["block sender"]))
+                .await?
+                .ensure()?;
+
+            // Some tenants confirm before blocking
+            if let Err(e) = click_dialog_button(&page, &["ok", "block"], Some(500)).await {
+                crate::debug!("block_sender: no confirmation: {}", e);
+            }
+
+            Ok(())
+        })
+        .await
     }
 
-    pub async fn mark_read(&self, id: &str) -> Result<()> {
-        use crate::menu::{click_menu_item, open_context_menu};
+    pub async fn unspam(&self, id: &str) -> Result<()> {
+        self.scoped(async {
+            self.move_via_context_menu(Folder::Junk, id, &["not junk"])
+                .await
+        })
+        .await
+    }
 
-        let browser = connect_or_start_browser(self.port).await?;
-        let page = find_outlook_page(&browser).await?;
+    /// Move a message from Deleted Items back to the Inbox
+    pub async fn restore(&self, id: &str) -> Result<()> {
+        self.scoped(async {
+            self.move_via_context_menu(Folder::Deleted, id, &["restore"])
+                .await
+        })
+        .await
+    }
 
-        let selector = crate::browser::message_selector(id);
-        open_context_menu(&page, &selector).await?;
+    /// Open `folder`, right-click the message and pick the first of `options` the menu offers,
+    /// falling back to "Move to" -> "Inbox"
+    async fn move_via_context_menu(
+        &self,
+        folder: Folder,
+        id: &str,
+        options: &[&str],
+    ) -> Result<()> {
+        use crate::browser::navigate_to_folder;
+        use crate::menu::{ActionStatus, context_menu_action};
+
+        let _pace = crate::retry::pace().await;
+        let browser = connect_or_start_browser(&self.config).await?;
+        let page = find_outlook_page(&browser, &self.config).await?;
+
+        navigate_to_folder(&page, folder).await?;
+
+        let id = &crate::list::resolve_message(&page, id).await?;
+        if let ActionStatus::MissingItem(_) = context_menu_action(&page, id, options, None).await? {
+            context_menu_action(&page, id, &["move"], Some(&["inbox"]))
+                .await?
+                .ensure()?;
+        }
 
-        click_menu_item(&page, "mark as read", None).await?;
         Ok(())
     }
 
-    pub async fn mark_unread(&self, id: &str) -> Result<()> {
-        use crate::menu::{click_menu_item, open_context_menu};
+    /// Pin or unpin a message at the top of the list
+    pub async fn set_pinned(&self, id: &str, pinned: bool) -> Result<()> {
+        self.scoped(async {
+            use crate::menu::{click_menu_item, close_menus, list_menu_items, open_context_menu};
+
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let id = &crate::list::resolve_message(&page, id).await?;
+            let selector = crate::browser::message_selector(id);
+            open_context_menu(&page, &selector).await?;
+
+            let items = list_menu_items(&page).await?;
+            let has_item = |name: &str| {
+                items
+                    .iter()
+                    .any(|item| item.trim().eq_ignore_ascii_case(name))
+            };
+
+            // The menu offers whichever action isn't the current state
+            let (wanted, opposite) = if pinned {
+                ("pin", "unpin")
+            } else {
+                ("unpin", "pin")
+            };
+            if has_item(opposite) && !has_item(wanted) {
+                close_menus(&page).await?;
+                return Ok(());
+            }
+            if !has_item(wanted) {
+                close_menus(&page).await?;
+                bail!("Menu item not found: {}", wanted);
+            }
 
-        let browser = connect_or_start_browser(self.port).await?;
-        let page = find_outlook_page(&browser).await?;
+            Ok(click_menu_item(&page, wanted, None).await?)
+        })
+        .await
+    }
 
-        let selector = crate::browser::message_selector(id);
-        open_context_menu(&page, &selector).await?;
+    /// Move an inbox message to Focused or Other, training the Focused Inbox classifier
+    /// With `always`, picks "Always move to ..." so future mail from the sender follows.
+    /// A message already on that side is left alone. The inbox is left on Focused.
+    pub async fn set_focused(&self, id: &str, focused: bool, always: bool) -> Result<()> {
+        self.scoped(async {
+            use crate::list::select_inbox_pivot;
+            use crate::menu::{
+                click_menu_item_exact, close_menus, list_menu_items, open_context_menu,
+            };
+
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+            crate::browser::navigate_to_inbox(&page).await?;
+
+            // The message sits on the side it's being moved away from
+            let (from, to) = if focused {
+                ("other", "focused")
+            } else {
+                ("focused", "other")
+            };
+            if !select_inbox_pivot(&page, from).await? {
+                bail!("Focused Inbox is turned off");
+            }
 
-        click_menu_item(&page, "mark as unread", None).await?;
+            let result = async {
+                let id = &crate::list::resolve_message(&page, id).await?;
+                if crate::menu::get_message_position(&page, id)
+                    .await?
+                    .is_none()
+                {
+                    // Not on this side, so it may already be where it's wanted
+                    select_inbox_pivot(&page, to).await?;
+                    if crate::menu::get_message_position(&page, id)
+                        .await?
+                        .is_none()
+                    {
+                        return Err(Error::MessageNotFound { id: id.to_string() });
+                    }
+                    if always {
+                        bail!(
+                            "{} is already in {}; only messages in {} can be always moved",
+                            id,
+                            to,
+                            from
+                        );
+                    }
+                    return Ok(());
+                }
+                open_context_menu(&page, &crate::browser::message_selector(id)).await?;
+
+                let wanted = crate::strings::localize(&format!("move to {}", to));
+                let always_wanted = crate::strings::localize(&format!("always move to {}", to));
+                let items = list_menu_items(&page).await?;
+                let item = items.iter().find(|item| {
+                    let text = item.trim().to_lowercase();
+                    if always {
+                        text.starts_with(&always_wanted)
+                    } else {
+                        text.starts_with(&wanted)
+                    }
+                });
+                let Some(item) = item else {
+                    close_menus(&page).await?;
+                    bail!(
+                        "Menu item not found: {}",
+                        if always { &always_wanted } else { &wanted }
+                    );
+                };
+                Ok(click_menu_item_exact(&page, item.trim(), Some(1000)).await?)
+            }
+            .await;
+
+            select_inbox_pivot(&page, "focused").await?;
+            result
+        })
+        .await
+    }
+
+    pub async fn mark_read(&self, id: &str) -> Result<()> {
+        self.scoped(async { self.set_read(id, true).await }).await
+    }
+
+    pub async fn mark_unread(&self, id: &str) -> Result<()> {
+        self.scoped(async { self.set_read(id, false).await }).await
+    }
+
+    /// Mark a message read or unread from its context menu, then confirm the unread marker toggled
+    async fn set_read(&self, id: &str, read: bool) -> Result<()> {
+        use crate::menu::context_menu_action;
+
+        let _pace = crate::retry::pace().await;
+        let browser = connect_or_start_browser(&self.config).await?;
+        let page = find_outlook_page(&browser, &self.config).await?;
+
+        let id = &crate::list::resolve_message(&page, id).await?;
+        let item = if read {
+            "mark as read"
+        } else {
+            "mark as unread"
+        };
+        context_menu_action(&page, id, &[item], None)
+            .await?
+            .ensure()?;
+        if !Self::wait_for_listed(&page, id, |m| m.is_unread != read).await? {
+            bail!(
+                "Message {} is still {}",
+                id,
+                if read { "unread" } else { "read" }
+            );
+        }
         Ok(())
     }
 
+    /// Poll the listed message for up to 3 seconds until `check` holds
+    /// A message that has left the list never satisfies the check
+    async fn wait_for_listed(
+        page: &chromiumoxide::Page,
+        id: &str,
+        check: impl Fn(&Message) -> bool,
+    ) -> Result<bool> {
+        let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(3);
+        loop {
+            if crate::list::find_listed(page, id)
+                .await?
+                .is_some_and(|m| check(&m))
+            {
+                return Ok(true);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Ok(false);
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+        }
+    }
+
     pub async fn clear_labels(&self, id: &str) -> Result<()> {
-        use crate::menu::{click_menu_item, open_context_menu};
+        self.scoped(async {
+            use crate::menu::context_menu_action;
 
-        let browser = connect_or_start_browser(self.port).await?;
-        let page = find_outlook_page(&browser).await?;
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
 
-        let selector = crate::browser::message_selector(id);
-        open_context_menu(&page, &selector).await?;
+            let id = &crate::list::resolve_message(&page, id).await?;
+            context_menu_action(&page, id, &["categorize"], Some(&["clear"]))
+                .await?
+                .ensure()?;
+            Ok(())
+        })
+        .await
+    }
 
-        click_menu_item(&page, "categorize", Some(500)).await?;
-        click_menu_item(&page, "clear", None).await?;
-        Ok(())
+    /// Known labels from the cache if it is fresher than the configured TTL,
+    /// otherwise scraped again via `list_labels`
+    pub async fn labels_cached(&self) -> Result<Vec<String>> {
+        self.scoped(async {
+            let cache: LabelsCache =
+                crate::cache::load(crate::cache::Location::Config, LABELS_CACHE);
+            let age = unix_now().saturating_sub(cache.fetched_at);
+            if !cache.labels.is_empty() && age < self.config.labels_ttl().as_secs() {
+                crate::debug!("labels_cached: using cache ({}s old)", age);
+                return Ok(cache.labels);
+            }
+            self.list_labels().await
+        })
+        .await
     }
 
+    /// Scrape the label list from the Manage Categories dialog and refresh the cache
     pub async fn list_labels(&self) -> Result<Vec<String>> {
-        use crate::browser::navigate_to_inbox;
-        use crate::menu;
+        self.scoped(async {
+            use crate::menu;
 
-        let browser = connect_or_start_browser(self.port).await?;
-        let page = find_outlook_page(&browser).await?;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
 
-        navigate_to_inbox(&page).await?;
+            open_categories_dialog(&page).await?;
 
-        // Find any message to right-click
-        let first_msg_script = r#"
-            (() => {
-                const item = document.querySelector('[data-convid]');
-                return item?.getAttribute('data-convid') || null;
-            })()
-        "#;
+            // Extract categories from the dialog
+            let categories = menu::extract_categories_from_dialog(&page).await?;
+            menu::close_dialog(&page).await?;
 
-        let result = page.evaluate(first_msg_script).await?;
-        let msg_id: Option<String> = result.into_value().ok();
+            // If we didn't find categories in the dialog, fall back to the submenu items
+            if categories.is_empty() {
+                let fallback_categories = menu::extract_categories_from_submenu(&page).await?;
 
-        let msg_id =
-            msg_id.ok_or_else(|| anyhow::anyhow!("No messages found to open category menu"))?;
+                // Close menu by pressing Escape
+                crate::browser::press_key(&page, "Escape", None, None).await?;
 
-        // Wait for message to be visible
-        if !menu::wait_for_message(&page, &msg_id).await? {
-            anyhow::bail!("Message not visible");
-        }
+                save_labels_cache(&fallback_categories);
+                return Ok(fallback_categories);
+            }
 
-        // Step 1: Right-click to open context menu
-        if !menu::is_context_menu_open(&page).await? {
-            let (x, y) = menu::get_message_position(&page, &msg_id)
+            save_labels_cache(&categories);
+            Ok(categories)
+        })
+        .await
+    }
+
+    /// Create a category in the Manage Categories dialog
+    /// Returns false without changing anything if a category with that name already exists
+    pub async fn create_label(&self, name: &str, color: Option<&str>) -> Result<bool> {
+        self.scoped(async {
+            use crate::menu;
+
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            open_categories_dialog(&page).await?;
+
+            let existing = menu::extract_categories_from_dialog(&page).await?;
+            if existing.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+                menu::close_dialog(&page).await?;
+                return Ok(false);
+            }
+
+            menu::click_dialog_button(&page, &["create category", "new category"], Some(500)).await?;
+
+            let focus_script = r#"
+                (() => {
+                    const dialog = document.querySelector('[role="dialog"]');
+                    const input = dialog?.querySelector('input[type="text"]');
+                    if (!input) return false;
+                    input.focus();
+                    input.select();
+                    return true;
+                })()
+            "#;
+            let result = evaluate(&page, "create_label:focus", focus_script).await?;
+            if !result.into_value::<bool>().unwrap_or(false) {
+                bail!("Could not find category name input");
+            }
+            crate::browser::type_text(&page, name).await?;
+            tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+
+            if let Some(color) = color {
+                // Color swatches are buttons/radios labelled with the color name
+                let color_script = format!(
+                    r#"
+                    (() => {{
+                        const color = {color};
+                        const dialog = document.querySelector('[role="dialog"]');
+                        if (!dialog) return false;
+                        const swatches = dialog.querySelectorAll('[role="radio"], [role="option"], button[title], button[aria-label]');
+                        for (const swatch of swatches) {{
+                            const label = (swatch.getAttribute('aria-label') || swatch.getAttribute('title') || '').toLowerCase();
+                            if (label === color || label.startsWith(color + ' ')) {{
+                                swatch.click();
+                                return true;
+                            }}
+                        }}
+                        return false;
+                    }})()
+                    "#,
+                    color = serde_json::to_string(&color.to_lowercase())?
+                );
+                let result = evaluate(&page, "create_label:color", color_script).await?;
+                if !result.into_value::<bool>().unwrap_or(false) {
+                    bail!("Color not found in category dialog: {}", color);
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+            }
+
+            menu::click_dialog_button(&page, &["save", "create", "ok"], Some(500)).await?;
+            menu::close_dialog(&page).await?;
+            save_labels_cache(&[]);
+            Ok(true)
+        })
+        .await
+    }
+
+    /// Create a folder at the top of the mailbox, or as a subfolder of `parent`
+    /// Returns false without changing anything if a folder of that name already exists there
+    pub async fn create_folder(&self, name: &str, parent: Option<&str>) -> Result<bool> {
+        self.scoped(async {
+            use crate::menu;
+
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            ensure_signed_in(&browser, &self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let same_place = |f: &MailFolder| {
+                f.name.eq_ignore_ascii_case(name)
+                    && f.parent.as_deref().map(str::to_lowercase) == parent.map(str::to_lowercase)
+            };
+            let folders = crate::list::folder_tree(&page).await?;
+            if folders.iter().any(same_place) {
+                return Ok(false);
+            }
+
+            let position = match parent {
+                Some(parent) => {
+                    if !folders.iter().any(|f| f.name.eq_ignore_ascii_case(parent)) {
+                        bail!("Parent folder not found: {}", parent);
+                    }
+                    menu::get_folder_position(&page, parent).await?
+                }
+                None => menu::get_folder_root_position(&page).await?,
+            };
+            let Some((x, y)) = position else {
+                bail!(
+                    "Could not find {} in the folder pane",
+                    parent.unwrap_or("the mailbox root")
+                );
+            };
+            menu::open_context_menu_at(&page, x, y).await?;
+
+            // Folders offer "Create new subfolder", the account heading "Create new folder"
+            let items = menu::list_menu_items(&page).await?;
+            let Some(item) = ["create new subfolder", "create new folder"]
+                .into_iter()
+                .find(|wanted| {
+                    let wanted = crate::strings::localize(wanted);
+                    items.iter().any(|i| i.to_lowercase().contains(&wanted))
+                })
+            else {
+                menu::close_menus(&page).await?;
+                bail!("Menu item not found: create new subfolder / create new folder");
+            };
+            menu::click_menu_item(&page, item, Some(500)).await?;
+
+            // The name is typed into an inline box in the tree and committed with Enter
+            crate::browser::type_text(&page, name).await?;
+            crate::browser::press_key(&page, "Enter", None, Some(1000)).await?;
+
+            for _ in 0..10 {
+                if crate::list::folder_tree(&page)
+                    .await?
+                    .iter()
+                    .any(same_place)
+                {
+                    return Ok(true);
+                }
+                let error = evaluate(
+                    &page,
+                    "create_folder:error",
+                    r#"document.querySelector('[role="alert"], [role="alertdialog"]')?.textContent?.trim() || null"#,
+                )
                 .await?
-                .ok_or_else(|| anyhow::anyhow!("Message not found"))?;
+                .into_value::<Option<String>>()
+                .unwrap_or(None);
+                if let Some(error) = error.filter(|e| e.to_lowercase().contains("already exists")) {
+                    crate::browser::press_key(&page, "Escape", None, None).await?;
+                    bail!("Outlook refused the folder name: {}", error);
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+            }
+            bail!("Folder '{}' did not appear in the folder pane", name)
+        })
+        .await
+    }
+
+    /// Delete a category from the Manage Categories dialog
+    /// Errors listing the available categories if `name` isn't one of them
+    pub async fn delete_label(&self, name: &str) -> Result<()> {
+        self.scoped(async {
+            use crate::menu;
+
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            open_categories_dialog(&page).await?;
+
+            let existing = menu::extract_categories_from_dialog(&page).await?;
+            let Some(name) = existing.iter().find(|c| c.eq_ignore_ascii_case(name)) else {
+                menu::close_dialog(&page).await?;
+                return Err(Error::CategoryNotFound {
+                    name: name.to_string(),
+                    available: existing,
+                });
+            };
+
+            let delete_script = format!(
+                r#"
+                (() => {{
+                    const name = {name};
+                    const dialog = document.querySelector('[role="dialog"]');
+                    if (!dialog) return false;
+                    for (const row of dialog.querySelectorAll('tr[aria-label]')) {{
+                        if (row.getAttribute('aria-label') !== name) continue;
+                        const btn = row.querySelector('button[aria-label*="Delete"], button[title*="Delete"], button[aria-label*="Remove"]');
+                        if (!btn) return false;
+                        btn.click();
+                        return true;
+                    }}
+                    return false;
+                }})()
+                "#,
+                name = serde_json::to_string(name)?
+            );
+            let result = evaluate(&page, "delete_label:click", delete_script).await?;
+            if !result.into_value::<bool>().unwrap_or(false) {
+                menu::close_dialog(&page).await?;
+                bail!("Delete control not found for category: {}", name);
+            }
 
-            menu::right_click(&page, x, y, Some(1000)).await?;
+            menu::click_dialog_button(&page, &["delete", "yes", "ok"], Some(500)).await?;
+            menu::close_dialog(&page).await?;
+            save_labels_cache(&[]);
+            Ok(())
+        })
+        .await
+    }
 
-            if !menu::is_context_menu_open(&page).await? {
-                anyhow::bail!("Context menu didn't open");
+    /// Empty a folder (intended for Junk and Deleted Items)
+    /// Returns the number of messages visible before emptying, if any were listed
+    pub async fn empty_folder(&self, folder: Folder) -> Result<Option<usize>> {
+        self.scoped(async {
+            use crate::browser::navigate_to_folder;
+            use crate::menu::click_dialog_button;
+
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            navigate_to_folder(&page, folder).await?;
+
+            let count_script = format!(
+                "document.querySelectorAll({}).length",
+                serde_json::to_string(self.config.selectors.message_item())?
+            );
+            let result = evaluate(&page, "empty_folder:count", count_script).await?;
+            let count = result.into_value::<usize>().ok().filter(|&n| n > 0);
+
+            let click_script = r#"
+                (() => {
+                    const btn = document.querySelector('button[aria-label*="Empty folder"], button[title*="Empty folder"]');
+                    if (!btn) return false;
+                    btn.click();
+                    return true;
+                })()
+            "#;
+            let result = evaluate(&page, "empty_folder:click", click_script).await?;
+            if !result.into_value::<bool>().unwrap_or(false) {
+                bail!("Empty folder button not found in {}", folder.display_name());
             }
+
+            click_dialog_button(&page, &["delete all", "empty", "ok", "yes"], Some(1000)).await?;
+
+            Ok(count)
+        })
+        .await
+    }
+
+    /// Mark every message in a folder as read
+    /// Uses the folder's "Mark all as read" action when available, otherwise marks
+    /// visible unread messages one by one. Returns how many unread messages were affected.
+    pub async fn mark_all_read(&self, folder: Folder) -> Result<usize> {
+        self.scoped(async {
+            use crate::browser::navigate_to_folder;
+            use crate::menu;
+
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            navigate_to_folder(&page, folder).await?;
+
+            let unread_script = format!(
+                r#"
+            (() => {{
+                const ids = [];
+                document.querySelectorAll({item}).forEach(item => {{
+                    const ariaLabel = item.getAttribute('aria-label') || '';
+                    if (ariaLabel.toLowerCase().includes('unread')) {{
+                        ids.push(item.getAttribute('data-convid'));
+                    }}
+                }});
+                return JSON.stringify(ids);
+            }})()
+        "#,
+                item = serde_json::to_string(self.config.selectors.message_item())?
+            );
+            let unread: Vec<String> =
+                evaluate_json(&page, "mark_all_read:unread", &unread_script).await?;
+
+            if unread.is_empty() {
+                return Ok(0);
+            }
+
+            // Prefer the bulk action from the folder's context menu
+            if let Some((x, y)) = menu::get_folder_position(&page, folder.display_name()).await? {
+                menu::open_context_menu_at(&page, x, y).await?;
+                let items = menu::list_menu_items(&page).await?;
+                if items
+                    .iter()
+                    .any(|item| item.to_lowercase().contains("mark all as read"))
+                {
+                    menu::click_menu_item(&page, "mark all as read", Some(1000)).await?;
+                    return Ok(unread.len());
+                }
+                menu::close_menus(&page).await?;
+            }
+
+            // Fall back to marking each visible unread message
+            let mut progress = crate::progress::Progress::new("Marking read", unread.len());
+            for id in &unread {
+                let selector = crate::browser::message_selector(id);
+                menu::open_context_menu(&page, &selector).await?;
+                menu::click_menu_item(&page, "mark as read", None).await?;
+                progress.inc();
+            }
+
+            Ok(unread.len())
+        })
+        .await
+    }
+
+    /// Archive the read messages among the first `max` in `folder` with one multi-select action
+    /// With `older_than`, only messages at least that many days old qualify; messages whose
+    /// date can't be parsed are left alone. Returns the messages archived, or that would be
+    /// with `dry_run`.
+    pub async fn archive_read(
+        &self,
+        folder: Folder,
+        max: u32,
+        older_than: Option<u32>,
+        dry_run: bool,
+    ) -> Result<Vec<Message>> {
+        self.scoped(async {
+            let messages = crate::list::list_folder(&self.config, folder, max).await?;
+            let read: Vec<Message> = messages
+                .into_iter()
+                .filter(|m| !m.is_unread)
+                .filter(|m| match older_than {
+                    Some(days) => m
+                        .date
+                        .as_deref()
+                        .and_then(crate::output::days_old)
+                        .is_some_and(|age| age >= i64::from(days)),
+                    None => true,
+                })
+                .collect();
+
+            if !dry_run && !read.is_empty() {
+                let ids: Vec<String> = read.iter().map(|m| m.id.clone()).collect();
+                self.archive_many(&ids).await?;
+            }
+            Ok(read)
+        })
+        .await
+    }
+
+    /// Show the message list grouped into conversations, or as individual messages
+    /// Uses the Conversations menu on the ribbon's View tab, then goes back to the ribbon
+    /// tab that was showing before. Returns false when the list was already shown that way.
+    pub async fn set_conversation_view(&self, enabled: bool) -> Result<bool> {
+        self.scoped(async {
+            use crate::browser::{evaluate_json, evaluate_with_args};
+            use crate::strings::localize;
+
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let tab_script = r#"
+                (() => {
+                    const tab = document.querySelector('[role="tab"][aria-selected="true"]');
+                    const text = tab && (tab.getAttribute('aria-label') || tab.getAttribute('name') || tab.textContent || '');
+                    return JSON.stringify(text ? text.trim().toLowerCase() : null);
+                })()
+            "#;
+            let previous_tab: Option<String> =
+                evaluate_json(&page, "set_conversation_view:tab", tab_script).await?;
+
+            // Ribbon tab, then the Conversations dropdown on it
+            let click_script = r#"
+                (selector, text) => {
+                    const found = Array.from(document.querySelectorAll(selector))
+                        .find(el => (el.getAttribute('aria-label') || el.getAttribute('name') || el.textContent || '')
+                            .trim().toLowerCase().startsWith(text));
+                    if (!found) return false;
+                    found.click();
+                    return true;
+                }
+            "#;
+            let view_tab = localize("view");
+            let result = async {
+                for (selector, text) in [
+                    (r#"[role="tab"]"#, "view"),
+                    (
+                        r#"button[aria-haspopup], [role="menuitem"]"#,
+                        "conversations",
+                    ),
+                ] {
+                    let result = evaluate_with_args(
+                        &page,
+                        "set_conversation_view:open",
+                        click_script,
+                        &[selector.into(), localize(text).into()],
+                    )
+                    .await?;
+                    if !result.into_value::<bool>().unwrap_or(false) {
+                        crate::menu::close_menus(&page).await?;
+                        bail!("Could not find the {} control on the ribbon", text);
+                    }
+                    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+                }
+
+                // Builds differ in wording; the first candidate present stands for each state.
+                // The list counts as grouped unless the "off" item is the checked one, so
+                // picking an ordering such as "newest on top" never counts as turning it on.
+                let on_texts = ["show as conversations", "newest messages on top"];
+                let off_texts = ["show as individual messages", "individual messages", "off"];
+                let pick_script = r#"
+                    (onTexts, offTexts, enable) => {
+                        const items = Array.from(document.querySelectorAll(
+                            '[role="menuitem"], [role="menuitemradio"], [role="menuitemcheckbox"], [role="option"]'));
+                        const textOf = item => (item.textContent || '').trim().toLowerCase();
+                        const find = texts => {
+                            for (const text of texts) {
+                                const item = items.find(item => textOf(item) === text)
+                                    || items.find(item => textOf(item).includes(text) && text.length > 3);
+                                if (item) return item;
+                            }
+                            return null;
+                        };
+                        const checked = item => item.getAttribute('aria-checked') === 'true'
+                            || item.getAttribute('aria-selected') === 'true';
+                        const on = find(onTexts);
+                        const off = find(offTexts);
+                        if (!on && !off) return 'missing';
+                        const isOn = off ? !checked(off) : checked(on);
+                        if (isOn === enable) return 'already';
+                        const target = enable ? on : off;
+                        if (!target) return 'missing';
+                        target.click();
+                        return 'clicked';
+                    }
+                "#;
+                let localized =
+                    |texts: &[&str]| -> Vec<String> { texts.iter().map(|t| localize(t)).collect() };
+                let result = evaluate_with_args(
+                    &page,
+                    "set_conversation_view:pick",
+                    pick_script,
+                    &[
+                        serde_json::to_value(localized(&on_texts))?,
+                        serde_json::to_value(localized(&off_texts))?,
+                        enabled.into(),
+                    ],
+                )
+                .await?;
+                let status = result.into_value::<String>().unwrap_or_default();
+                crate::debug!("set_conversation_view {}: {}", enabled, status);
+                match status.as_str() {
+                    "clicked" => {
+                        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+                        Ok(true)
+                    }
+                    "already" => {
+                        crate::menu::close_menus(&page).await?;
+                        Ok(false)
+                    }
+                    _ => {
+                        crate::menu::close_menus(&page).await?;
+                        let candidates: &[&str] = if enabled { &on_texts } else { &off_texts };
+                        bail!("Menu item not found: {}", candidates.join(" / "))
+                    }
+                }
+            }
+            .await;
+
+            if let Some(tab) = previous_tab.filter(|tab| !tab.starts_with(&view_tab)) {
+                let restored = evaluate_with_args(
+                    &page,
+                    "set_conversation_view:restore",
+                    click_script,
+                    &[r#"[role="tab"]"#.into(), tab.clone().into()],
+                )
+                .await
+                .ok()
+                .and_then(|r| r.into_value::<bool>().ok())
+                .unwrap_or(false);
+                if !restored {
+                    crate::debug!("Could not switch the ribbon back to the {} tab", tab);
+                }
+            }
+            result
+        })
+        .await
+    }
+
+    /// Snooze a message until the given time
+    pub async fn snooze(&self, id: &str, when: &SnoozeTime) -> Result<()> {
+        self.scoped(async {
+            use crate::menu::{
+                click_dialog_button, click_menu_item, list_menu_items, open_context_menu,
+            };
+
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let id = &crate::list::resolve_message(&page, id).await?;
+            let selector = crate::browser::message_selector(id);
+            open_context_menu(&page, &selector).await?;
+
+            let items = list_menu_items(&page).await?;
+            let snooze_item = ["snooze", "remind me"]
+                .into_iter()
+                .find(|name| items.iter().any(|item| item.to_lowercase().contains(name)));
+            let Some(snooze_item) = snooze_item else {
+                crate::menu::close_menus(&page).await?;
+                bail!("Snooze is not available for this account");
+            };
+
+            click_menu_item(&page, snooze_item, Some(500)).await?;
+            click_menu_item(&page, when.menu_label(), Some(500)).await?;
+
+            if let SnoozeTime::Date(date) = when {
+                // Fill the date picker in the "Choose a date" dialog
+                let focus_script = r#"
+                (() => {
+                    const dialog = document.querySelector('[role="dialog"]');
+                    if (!dialog) return false;
+                    const input = dialog.querySelector('input');
+                    if (!input) return false;
+                    input.focus();
+                    input.select();
+                    return true;
+                })()
+            "#;
+                let result = evaluate(&page, "snooze:focus", focus_script).await?;
+                if !result.into_value::<bool>().unwrap_or(false) {
+                    bail!("Could not find snooze date picker");
+                }
+                crate::browser::type_text(&page, date).await?;
+                tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+                click_dialog_button(&page, &["snooze", "save", "ok"], None).await?;
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Export a message as a reconstructed .eml file
+    /// The raw MIME source isn't available from the page, so this is lossy; see `output::to_eml`
+    pub async fn export_eml(&self, id: &str, path: &std::path::Path) -> Result<()> {
+        self.scoped(async {
+            let msg = self.get_message(id).await?;
+            std::fs::write(path, crate::output::to_eml(&msg))?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Append the messages in `folder` (up to `max`) to the mbox file at `path`
+    /// Messages whose ID is already in the file are skipped, so an interrupted export can
+    /// be rerun to finish it. Each message is scrolled back into view and read with
+    /// `peek_message`, leaving its read state alone. A message that can't be read is
+    /// reported on stderr and left out instead of ending the export.
+    pub async fn export_mbox(
+        &self,
+        folder: Folder,
+        max: u32,
+        path: &std::path::Path,
+    ) -> Result<MboxExport> {
+        self.scoped(async {
+            use std::io::Write;
+
+            let done = match std::fs::read_to_string(path) {
+                Ok(text) => crate::output::mbox_ids(&text),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Default::default(),
+                Err(e) => return Err(e.into()),
+            };
+            let messages = crate::list::list_folder(&self.config, folder, max).await?;
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let mut progress = crate::progress::Progress::new("Exporting", messages.len());
+            let mut export = MboxExport::default();
+            for listed in &messages {
+                if done.contains(&listed.id) {
+                    export.skipped += 1;
+                    progress.inc();
+                    continue;
+                }
+                let read = async {
+                    if !crate::list::scroll_into_view(&page, &listed.id).await? {
+                        return Err(Error::MessageNotFound {
+                            id: listed.id.clone(),
+                        });
+                    }
+                    self.peek_message(&listed.id).await
+                };
+                match read.await {
+                    Ok(mut msg) => {
+                        msg.id = listed.id.clone();
+                        msg.folder = listed.folder.clone();
+                        // Written one at a time so whatever was read survives an interruption
+                        file.write_all(crate::output::to_mbox(&msg).as_bytes())?;
+                        export.written += 1;
+                    }
+                    Err(e) => {
+                        crate::progress::notice(format_args!(
+                            "Skipping {}: {:#}",
+                            listed.id,
+                            anyhow::Error::from(e)
+                        ));
+                        export.failed.push(listed.id.clone());
+                    }
+                }
+                progress.inc();
+            }
+            Ok(export)
+        })
+        .await
+    }
+
+    /// Look up people by name or address through the compose To-field autocomplete
+    /// Opens a new message to get at the suggestions and discards it afterwards
+    pub async fn find_contact(&self, query: &str) -> Result<Vec<Contact>> {
+        self.scoped(async {
+            use crate::compose::{discard_compose, open_compose, recipient_suggestions};
+
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            open_compose(&page).await?;
+            let contacts = recipient_suggestions(&page, query).await;
+            // Discard even when reading failed so no stray draft is left behind
+            discard_compose(&page).await?;
+            Ok(contacts?)
+        })
+        .await
+    }
+
+    /// Compose a message and save it as a draft without sending, returning the draft's ID
+    /// The ID is the one that appears in Drafts after saving.
+    pub async fn save_draft(&self, draft: &Draft) -> Result<String> {
+        self.save_draft_with_review(draft, |_| Ok(true)).await
+    }
+
+    /// `save_draft`, letting `review` see the recipients Outlook resolved once the pane is
+    /// filled; returning false discards the draft instead of saving it
+    /// The compose pane is discarded whenever the draft isn't saved, errors included.
+    pub async fn save_draft_with_review(
+        &self,
+        draft: &Draft,
+        review: impl FnOnce(&[String]) -> anyhow::Result<bool>,
+    ) -> Result<String> {
+        let Draft {
+            to,
+            subject,
+            body,
+            attachments,
+        } = draft;
+        self.scoped(async {
+            use crate::compose::{
+                attach_files, discard_compose, fill_compose, open_compose, resolved_recipients,
+                save_compose,
+            };
+
+            // Check attachments up front so a typo doesn't leave a half-built draft
+            for path in attachments {
+                if !path.is_file() {
+                    bail!("Attachment not found: {}", path.display());
+                }
+            }
+
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            // The new draft is whichever ID shows up in Drafts that wasn't there before
+            let before: std::collections::HashSet<String> =
+                crate::list::extract_folder(&page, Folder::Drafts, 50)
+                    .await?
+                    .into_iter()
+                    .map(|m| m.id)
+                    .collect();
+
+            let composed = async {
+                open_compose(&page).await?;
+                fill_compose(&page, to, subject, body).await?;
+                attach_files(&page, attachments).await?;
+                if !review(&resolved_recipients(&page).await?)? {
+                    anyhow::bail!("Draft discarded");
+                }
+                save_compose(&page).await
+            }
+            .await;
+            if let Err(e) = composed {
+                if let Err(discard) = discard_compose(&page).await {
+                    crate::debug!("save_draft: could not discard compose pane: {}", discard);
+                }
+                return Err(e.into());
+            }
+
+            let mut added: Vec<Message> = crate::list::extract_folder(&page, Folder::Drafts, 50)
+                .await?
+                .into_iter()
+                .filter(|m| !before.contains(&m.id))
+                .collect();
+            // Another draft saved meanwhile (e.g. by hand) is told apart by its subject
+            if added.len() > 1 {
+                added.retain(|m| m.subject.as_deref() == Some(subject));
+            }
+            match added.as_slice() {
+                [draft] => Ok(draft.id.clone()),
+                [] => bail!("Saved draft '{}' not found in Drafts", subject),
+                _ => bail!("Several new drafts match '{}'", subject),
+            }
+        })
+        .await
+    }
+
+    /// Click "Undo" in the notification toast Outlook shows after archive/delete/move
+    /// The toast only lasts a few seconds, so this acts at once and errors if none is showing
+    pub async fn undo(&self) -> Result<()> {
+        self.scoped(async {
+            let _pace = crate::retry::pace().await;
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let script = r#"
+                (() => {
+                    const candidates = document.querySelectorAll('button, a, [role="button"], [role="link"]');
+                    for (const el of candidates) {
+                        const text = el.textContent?.trim().toLowerCase() || '';
+                        const label = el.getAttribute('aria-label')?.toLowerCase() || '';
+                        if (text !== 'undo' && label !== 'undo') continue;
+                        // Skip the compose editor's undo; the toast's lives in a live region
+                        if (!el.closest('[role="alert"], [role="status"], [aria-live], [class*="notification" i], [class*="toast" i]')) continue;
+                        el.click();
+                        return true;
+                    }
+                    return false;
+                })()
+            "#;
+            let result = evaluate(&page, "undo", script).await?;
+            if !result.into_value::<bool>().unwrap_or(false) {
+                bail!("No undo notification is showing");
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Show `target` in the Outlook tab and bring the tab to the front, to carry on by hand
+    /// `target` is a well-known folder (inbox, junk, ...), a folder name from the folder
+    /// pane, or the ID of a message in the current list, which is then selected.
+    pub async fn open(&self, target: &str) -> Result<Opened> {
+        self.scoped(async {
+            use crate::browser::{click_element, message_selector};
+            use crate::menu;
+            use clap::ValueEnum;
+
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+            page.bring_to_front().await?;
+
+            let well_known = Folder::from_str(target, true).ok().or_else(|| {
+                Folder::value_variants()
+                    .iter()
+                    .copied()
+                    .find(|f| f.display_name().eq_ignore_ascii_case(target))
+            });
+            if let Some(folder) = well_known {
+                crate::browser::navigate_to_folder(&page, folder).await?;
+                return Ok(Opened::Folder {
+                    name: folder.display_name().to_string(),
+                });
+            }
+
+            let folders = crate::list::folder_tree(&page).await?;
+            if let Some(folder) = folders.iter().find(|f| f.name.eq_ignore_ascii_case(target)) {
+                let Some((x, y)) = menu::get_folder_position(&page, &folder.name).await? else {
+                    bail!("Could not find {} in the folder pane", folder.name);
+                };
+                menu::left_click(&page, x, y, Some(500)).await?;
+                crate::browser::wait_after_navigation(&page).await;
+                return Ok(Opened::Folder {
+                    name: folder.name.clone(),
+                });
+            }
+
+            let id = crate::list::resolve_message(&page, target).await?;
+            if menu::get_message_position(&page, &id).await?.is_none() {
+                bail!(
+                    "'{}' is neither a folder nor a message in the current list",
+                    target
+                );
+            }
+            click_element(&page, &message_selector(&id), Some(1000)).await?;
+            Ok(Opened::Message { id })
+        })
+        .await
+    }
+
+    /// Save a PNG screenshot of the Outlook tab to `path`
+    /// Captures the visible viewport, or the whole scrollable page with `full`
+    pub async fn screenshot(&self, path: &std::path::Path, full: bool) -> Result<()> {
+        self.scoped(async {
+            use chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotFormat;
+            use chromiumoxide::page::ScreenshotParams;
+
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let params = ScreenshotParams::builder()
+                .format(CaptureScreenshotFormat::Png)
+                .full_page(full)
+                .build();
+            page.save_screenshot(params, path).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Internet headers (or the full source) of a message, from Outlook's
+    /// "View message source" or "View message details" dialog
+    /// Errors when the account offers neither, as some tenants hide them.
+    pub async fn get_headers(&self, id: &str) -> Result<String> {
+        self.scoped(async {
+            use crate::browser::click_element;
+            use crate::menu::{click_menu_item, close_dialog, close_menus, list_menu_items};
+
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let id = &crate::list::resolve_message(&page, id).await?;
+            let selector = crate::browser::message_selector(id);
+            click_element(&page, &selector, Some(2000)).await?;
+
+            click_element(
+                &page,
+                r#"[aria-label="More actions"], [aria-label="More items"]"#,
+                Some(500),
+            )
+            .await?;
+
+            // Newer versions tuck the options into a "View" submenu
+            const OPTIONS: [&str; 2] = ["view message source", "view message details"];
+            let has_option = |items: &[String]| {
+                OPTIONS.into_iter().find(|name| {
+                    let name = crate::strings::localize(name);
+                    items.iter().any(|item| item.to_lowercase().contains(&name))
+                })
+            };
+            let mut items = list_menu_items(&page).await?;
+            if has_option(&items).is_none()
+                && crate::menu::try_click_menu_item(&page, "view", Some(500)).await?
+            {
+                items = list_menu_items(&page).await?;
+            }
+            let Some(option) = has_option(&items) else {
+                close_menus(&page).await?;
+                bail!(
+                    "Outlook offers no message source or details for this message (the account may hide them)"
+                );
+            };
+            click_menu_item(&page, option, Some(1000)).await?;
+
+            // The dialog shows the text in a <pre> or read-only textarea once it has loaded
+            let script = r#"
+                (() => {
+                    const dialog = document.querySelector('[role="dialog"]');
+                    if (!dialog) return null;
+                    const box = dialog.querySelector('textarea, pre');
+                    const text = box ? (box.value || box.textContent) : '';
+                    return text.trim() || null;
+                })()
+            "#;
+            for _ in 0..20 {
+                let result = evaluate(&page, "get_headers", script).await?;
+                if let Some(text) = result.into_value::<Option<String>>().unwrap_or(None) {
+                    close_dialog(&page).await?;
+                    return Ok(text);
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+            }
+            close_dialog(&page).await?;
+            bail!("The '{}' dialog showed no text", option)
+        })
+        .await
+    }
+
+    /// Download the original message via Outlook's own "Download" action into `dir`
+    /// The file keeps the name Outlook gives it; its path is returned once it has finished
+    /// downloading.
+    pub async fn download_eml(
+        &self,
+        id: &str,
+        dir: &std::path::Path,
+    ) -> Result<std::path::PathBuf> {
+        self.scoped(async {
+            use crate::browser::click_element;
+            use crate::menu::{click_menu_item, list_menu_items};
+            use chromiumoxide::cdp::browser_protocol::browser::{
+                SetDownloadBehaviorBehavior, SetDownloadBehaviorParams,
+            };
+
+            let browser = connect_or_start_browser(&self.config).await?;
+            let page = find_outlook_page(&browser, &self.config).await?;
+
+            let dir = std::fs::canonicalize(dir)?;
+            let params = SetDownloadBehaviorParams::builder()
+                .behavior(SetDownloadBehaviorBehavior::Allow)
+                .download_path(dir.to_string_lossy())
+                .build()
+                .map_err(|e| anyhow::anyhow!(e))?;
+            browser.execute(params).await?;
+            let entries = |dir: &std::path::Path| -> Result<std::collections::HashSet<_>> {
+                Ok(std::fs::read_dir(dir)?
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .collect())
+            };
+            let before = entries(&dir)?;
+
+            let id = &crate::list::resolve_message(&page, id).await?;
+            let selector = crate::browser::message_selector(id);
+            click_element(&page, &selector, Some(2000)).await?;
+
+            // "More actions" (...) menu in the reading pane
+            click_element(
+                &page,
+                r#"[aria-label="More actions"], [aria-label="More items"]"#,
+                Some(500),
+            )
+            .await?;
+
+            let items = list_menu_items(&page).await?;
+            let download = ["download", "save as"]
+                .into_iter()
+                .find(|name| items.iter().any(|item| item.to_lowercase().contains(name)));
+            let Some(download) = download else {
+                crate::menu::close_menus(&page).await?;
+                bail!("Outlook has no Download option for this message");
+            };
+            click_menu_item(&page, download, Some(500)).await?;
+
+            // Some versions open a submenu offering the format
+            crate::menu::try_click_menu_item(&page, "eml", Some(0)).await?;
+
+            // The browser writes to a .crdownload file and renames it when done
+            let deadline = tokio::time::Instant::now() + DOWNLOAD_TIMEOUT;
+            loop {
+                let finished = entries(&dir)?.into_iter().find(|path| {
+                    !before.contains(path)
+                        && path.extension().is_none_or(|ext| ext != "crdownload")
+                        && path.is_file()
+                });
+                if let Some(path) = finished {
+                    return Ok(path);
+                }
+                if tokio::time::Instant::now() >= deadline {
+                    return Err(Error::Timeout {
+                        what: format!("Download into {}", dir.display()),
+                        secs: DOWNLOAD_TIMEOUT.as_secs(),
+                    });
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+            }
+        })
+        .await
+    }
+}
+
+/// Store the label list for `labels_cached`; an empty list invalidates the cache
+/// Failing to write the cache isn't worth failing the command over
+fn save_labels_cache(labels: &[String]) {
+    let cache = LabelsCache {
+        fetched_at: unix_now(),
+        labels: labels.to_vec(),
+    };
+    if let Err(e) = crate::cache::save(crate::cache::Location::Config, LABELS_CACHE, &cache) {
+        crate::debug!("save_labels_cache: {}", e);
+    }
+}
+
+/// Open the Manage Categories dialog from the first message's context menu
+/// Leaves the Categorize submenu open behind the dialog
+async fn open_categories_dialog(page: &chromiumoxide::Page) -> anyhow::Result<()> {
+    use crate::browser::navigate_to_inbox;
+    use crate::menu;
+
+    navigate_to_inbox(page).await?;
+
+    // Find any message to right-click
+    let first_msg_script = format!(
+        r#"
+        (() => {{
+            const item = document.querySelector({item});
+            return item?.getAttribute('data-convid') || null;
+        }})()
+    "#,
+        item = crate::browser::message_item_js()
+    );
+
+    let result = evaluate(page, "open_categories_dialog:first_msg", first_msg_script).await?;
+    let msg_id: Option<String> = result.into_value().ok();
+
+    let msg_id =
+        msg_id.ok_or_else(|| anyhow::anyhow!("No messages found to open category menu"))?;
+
+    // Wait for message to be visible
+    crate::retry::with_retry(|| async {
+        if !menu::wait_for_message(page, &msg_id).await? {
+            anyhow::bail!("Message not visible");
         }
+        Ok(())
+    })
+    .await?;
+
+    // Step 1: Right-click to open context menu
+    if !menu::is_context_menu_open(page).await? {
+        let (x, y) = menu::get_message_position(page, &msg_id)
+            .await?
+            .ok_or_else(|| Error::MessageNotFound { id: msg_id.clone() })?;
 
-        // Step 2: Click Categorize to open submenu
-        menu::click_categorize(&page, Some(800)).await?;
-
-        // Step 3: Click "Manage Categories" to open the full list
-        menu::click_manage_categories(&page, Some(1000)).await?;
-
-        // Step 4: Extract categories from the dialog
-        let categories = menu::extract_categories_from_dialog(&page).await?;
-
-        // Close dialog by clicking the X button or backdrop
-        let close_script = r#"
-            (() => {
-                // Try close button first
-                const closeBtn = document.querySelector('[role="dialog"] button[aria-label*="Close"], [role="dialog"] button[aria-label*="close"]');
-                if (closeBtn) { closeBtn.click(); return true; }
-                // Try clicking backdrop/overlay
-                const backdrop = document.querySelector('[class*="overlay"], [class*="backdrop"]');
-                if (backdrop) { backdrop.click(); return true; }
-                // Click outside dialog
-                document.body.click();
-                return false;
-            })()
-        "#;
-        page.evaluate(close_script).await?;
-        tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
-
-        // If we didn't find categories in the dialog, fall back to the submenu items
-        if categories.is_empty() {
-            let fallback_categories = menu::extract_categories_from_submenu(&page).await?;
-
-            // Close menu by pressing Escape
-            crate::browser::press_key(&page, "Escape", None, None).await?;
-
-            return Ok(fallback_categories);
+        menu::right_click(page, x, y, Some(1000)).await?;
+
+        if !menu::is_context_menu_open(page).await? {
+            return Err(Error::MenuNotOpen {
+                menu: "Context menu".to_string(),
+            }
+            .into());
         }
+    }
+
+    // Step 2: Click Categorize to open submenu
+    menu::click_categorize(page, Some(800)).await?;
+
+    // Step 3: Click "Manage Categories" to open the full list
+    menu::click_manage_categories(page, Some(1000)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_unsubscribe_one_click() {
+        let headers = "Subject: News\r\nList-Unsubscribe: <mailto:u@example.com>,\r\n <https://example.com/u?x=1>\r\nList-Unsubscribe-Post: List-Unsubscribe=One-Click\r\n";
+        let unsub = parse_list_unsubscribe(headers).unwrap();
+        assert_eq!(unsub.url.as_deref(), Some("https://example.com/u?x=1"));
+        assert!(unsub.one_click);
+    }
+
+    #[test]
+    fn list_unsubscribe_without_post_header() {
+        let headers = "list-unsubscribe: <https://example.com/u>\n";
+        let unsub = parse_list_unsubscribe(headers).unwrap();
+        assert_eq!(unsub.url.as_deref(), Some("https://example.com/u"));
+        assert!(!unsub.one_click);
+
+        let mailto = parse_list_unsubscribe("List-Unsubscribe: <mailto:u@example.com>\n").unwrap();
+        assert_eq!(mailto.url.as_deref(), Some("mailto:u@example.com"));
+        assert!(!mailto.one_click);
 
-        Ok(categories)
+        assert!(parse_list_unsubscribe("Subject: hi\n").is_none());
     }
 }
@@ -1,14 +1,66 @@
-use anyhow::{Context, Result, anyhow};
+use crate::config::Config;
+use crate::error::Error;
+use crate::settings::Settings;
+use anyhow::{Context, Result, anyhow, bail};
 use chromiumoxide::browser::Browser;
 use futures::StreamExt;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 /// Build a CSS selector for a message by ID
 pub fn message_selector(id: &str) -> String {
-    format!("[data-convid=\"{}\"]", id)
+    format!("[data-convid=\"{}\"]", css_string(id))
 }
 
+/// Escape `value` for use inside a double-quoted CSS string, as `CSS.escape` would
+/// Quotes and backslashes are backslash-escaped and control characters become hex escapes.
+fn css_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\0' => out.push('\u{fffd}'),
+            c if c.is_control() => out.push_str(&format!("\\{:x} ", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// CSS selector for a row in the message list
+pub const MESSAGE_ITEM_SELECTOR: &str = "[data-convid]";
+
+/// The message row selector in effect, for the page helpers that take no config
+pub fn message_item() -> String {
+    crate::settings::current().message_item.clone()
+}
+
+/// The message row selector as a JavaScript string literal, ready to splice into a script
+pub fn message_item_js() -> String {
+    serde_json::to_string(&message_item()).unwrap_or_default()
+}
+
+/// CSS selector for the message body in the reading pane
+pub const READING_PANE_SELECTOR: &str = r#"div[role="document"]"#;
+
+/// CSS selector for the subject line in the reading pane
+pub const SUBJECT_SELECTOR: &str =
+    r#".allowTextSelection, [class*="SubjectLine"], [class*="JdFsz"]"#;
+
+/// CSS selector for the sender in a reading pane header
+pub const SENDER_SELECTOR: &str =
+    r#"[class*="Sender"], [class*="sender"], [class*="From"], button[class*="Persona"]"#;
+
+/// CSS selector for the sent/received timestamp in a reading pane header
+pub const DATE_SELECTOR: &str =
+    r#"[data-testid="SentReceivedSavedTime"], [class*="SentReceived"], [class*="DateTime"]"#;
+
 #[derive(Debug, Deserialize)]
 struct BrowserVersion {
     #[serde(rename = "webSocketDebuggerUrl")]
@@ -16,6 +68,7 @@ struct BrowserVersion {
 }
 
 /// Browser executable paths to try in order of preference
+#[cfg(unix)]
 const BROWSER_CANDIDATES: &[(&str, &[&str])] = &[
     (
         "Vivaldi",
@@ -52,11 +105,63 @@ const BROWSER_CANDIDATES: &[(&str, &[&str])] = &[
     ),
 ];
 
+/// Browser executable paths to try in order of preference
+/// `%VAR%` placeholders are expanded from the environment at runtime
+#[cfg(windows)]
+const BROWSER_CANDIDATES: &[(&str, &[&str])] = &[
+    (
+        "Vivaldi",
+        &[
+            r"%LOCALAPPDATA%\Vivaldi\Application\vivaldi.exe",
+            r"%ProgramFiles%\Vivaldi\Application\vivaldi.exe",
+        ],
+    ),
+    (
+        "Chromium",
+        &[
+            r"%LOCALAPPDATA%\Chromium\Application\chrome.exe",
+            r"%ProgramFiles%\Chromium\Application\chrome.exe",
+        ],
+    ),
+    (
+        "Chrome",
+        &[
+            r"%ProgramFiles%\Google\Chrome\Application\chrome.exe",
+            r"%ProgramFiles(x86)%\Google\Chrome\Application\chrome.exe",
+            r"%LOCALAPPDATA%\Google\Chrome\Application\chrome.exe",
+        ],
+    ),
+    (
+        "Edge",
+        &[
+            r"%ProgramFiles(x86)%\Microsoft\Edge\Application\msedge.exe",
+            r"%ProgramFiles%\Microsoft\Edge\Application\msedge.exe",
+        ],
+    ),
+];
+
+/// Expand `%VAR%` placeholders in a candidate path
+/// Returns None if a referenced variable is not set
+fn expand_path(path: &str) -> Option<PathBuf> {
+    let mut out = String::new();
+    let mut rest = path;
+    while let Some(start) = rest.find('%') {
+        let end = rest[start + 1..].find('%')? + start + 1;
+        out.push_str(&rest[..start]);
+        out.push_str(&std::env::var(&rest[start + 1..end]).ok()?);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Some(PathBuf::from(out))
+}
+
 /// Find the first available browser executable
-fn find_browser() -> Option<(&'static str, &'static str)> {
+pub fn find_browser() -> Option<(&'static str, PathBuf)> {
     for (name, paths) in BROWSER_CANDIDATES {
         for path in *paths {
-            if std::path::Path::new(path).exists() {
+            if let Some(path) = expand_path(path)
+                && path.exists()
+            {
                 return Some((name, path));
             }
         }
@@ -65,19 +170,27 @@ fn find_browser() -> Option<(&'static str, &'static str)> {
 }
 
 /// Start a browser with remote debugging enabled
-pub fn start_browser(port: u16) -> Result<()> {
+pub fn start_browser(config: &Config) -> Result<()> {
+    let port = config.port();
     let (name, path) = find_browser().ok_or_else(|| {
         anyhow!("No supported browser found. Install one of: Vivaldi, Chromium, or Chrome")
     })?;
 
-    eprintln!(
+    crate::progress::notice(format_args!(
         "Starting {} with remote debugging on port {}...",
         name, port
-    );
+    ));
+
+    let mut cmd = Command::new(&path);
+    cmd.arg(format!("--remote-debugging-port={}", port));
+    if config.headless {
+        cmd.arg("--headless=new");
+    }
+    if let Some(dir) = config.profile_dir() {
+        cmd.arg(format!("--user-data-dir={}", dir.display()));
+    }
 
-    Command::new(path)
-        .arg(format!("--remote-debugging-port={}", port))
-        .arg("https://outlook.office.com/mail/")
+    cmd.arg(config.outlook_url())
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .spawn()
@@ -88,7 +201,10 @@ pub fn start_browser(port: u16) -> Result<()> {
 
 pub async fn get_browser_ws_url(port: u16) -> Result<String> {
     let url = format!("http://127.0.0.1:{}/json/version", port);
-    let resp: BrowserVersion = reqwest::get(&url)
+    let resp: BrowserVersion = reqwest::Client::new()
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
         .await
         .context(format!("Failed to connect to browser on port {}", port))?
         .json()
@@ -96,37 +212,222 @@ pub async fn get_browser_ws_url(port: u16) -> Result<String> {
     Ok(resp.ws_url)
 }
 
+/// WebSocket URL of a remote browser given as `--endpoint`
+/// A `ws://`/`wss://` endpoint is used as is. For `http://`/`https://` the browser's
+/// `/json/version` is asked, and the host in its answer is replaced by the endpoint's,
+/// since a browser behind a tunnel reports the address it sees itself on.
+pub async fn endpoint_ws_url(endpoint: &str) -> Result<String> {
+    let url = reqwest::Url::parse(endpoint)
+        .with_context(|| format!("Invalid browser endpoint '{}'", endpoint))?;
+    match url.scheme() {
+        "ws" | "wss" => return Ok(url.to_string()),
+        "http" | "https" => {}
+        scheme => bail!(
+            "Unsupported browser endpoint scheme '{}'; use ws://, wss://, http:// or https://",
+            scheme
+        ),
+    }
+
+    // Relative to the endpoint's path, so a proxy prefix like /chrome/ is kept
+    let mut base = url.clone();
+    if !base.path().ends_with('/') {
+        base.set_path(&format!("{}/", base.path()));
+    }
+    let version_url = base
+        .join("json/version")
+        .with_context(|| format!("Invalid browser endpoint '{}'", endpoint))?;
+    let resp: BrowserVersion = reqwest::Client::new()
+        .get(version_url)
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+        .with_context(|| format!("Browser endpoint {} is unreachable", endpoint))?
+        .json()
+        .await
+        .with_context(|| format!("{} is not a browser debugging endpoint", endpoint))?;
+
+    let mut ws_url = reqwest::Url::parse(&resp.ws_url)
+        .with_context(|| format!("Browser returned a bad WebSocket URL '{}'", resp.ws_url))?;
+    let secure = url.scheme() == "https";
+    // Only fails for URLs that can't have a host, which a ws:// URL always can
+    let _ = ws_url.set_scheme(if secure { "wss" } else { "ws" });
+    let _ = ws_url.set_host(url.host_str());
+    let _ = ws_url.set_port(url.port_or_known_default());
+    Ok(ws_url.to_string())
+}
+
+/// WebSocket URL of the configured browser: the endpoint if one is set, else the local port
+pub async fn resolve_ws_url(config: &Config) -> Result<String> {
+    match &config.endpoint {
+        Some(endpoint) => endpoint_ws_url(endpoint).await,
+        None => get_browser_ws_url(config.port()).await,
+    }
+}
+
+/// What answers on the debugging port
+#[derive(Debug)]
+enum PortStatus {
+    /// Nothing is listening
+    Closed,
+    /// Something is listening but isn't a DevTools endpoint
+    Incompatible(String),
+    /// A DevTools endpoint with this WebSocket URL
+    DevTools(String),
+}
+
+/// Probe `/json/version` on `port` to tell an empty port from a foreign service
+async fn probe_port(port: u16) -> PortStatus {
+    let url = format!("http://127.0.0.1:{}/json/version", port);
+    let resp = match reqwest::Client::new()
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+    {
+        Ok(resp) => resp,
+        Err(e) if e.is_connect() => return PortStatus::Closed,
+        Err(e) => return PortStatus::Incompatible(e.to_string()),
+    };
+    if !resp.status().is_success() {
+        return PortStatus::Incompatible(format!("/json/version returned {}", resp.status()));
+    }
+    match resp.json::<BrowserVersion>().await {
+        Ok(version) => PortStatus::DevTools(version.ws_url),
+        Err(_) => PortStatus::Incompatible("/json/version has no webSocketDebuggerUrl".to_string()),
+    }
+}
+
+/// Executable file names of all candidate browsers
+fn browser_process_names() -> impl Iterator<Item = String> {
+    BROWSER_CANDIDATES.iter().flat_map(|(_, paths)| {
+        paths.iter().filter_map(|path| {
+            // Split on both separators so Windows paths work regardless of host
+            path.rsplit(['/', '\\'])
+                .next()
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+        })
+    })
+}
+
 /// Check if a browser process is already running
+#[cfg(unix)]
 fn is_browser_running() -> bool {
-    for (_, paths) in BROWSER_CANDIDATES {
-        for path in *paths {
-            if let Some(name) = std::path::Path::new(path).file_name() {
-                let name_str = name.to_string_lossy();
-                // Check if process is running using pgrep
-                if let Ok(output) = Command::new("pgrep")
-                    .arg("-x")
-                    .arg(name_str.as_ref())
-                    .output()
-                {
-                    if output.status.success() {
-                        return true;
-                    }
-                }
-            }
+    browser_process_names().any(|name| {
+        // Check if process is running using pgrep
+        Command::new("pgrep")
+            .arg("-x")
+            .arg(&name)
+            .output()
+            .is_ok_and(|output| output.status.success())
+    })
+}
+
+/// Check if a browser process is already running
+#[cfg(windows)]
+fn is_browser_running() -> bool {
+    browser_process_names().any(|name| {
+        // tasklist exits successfully even without a match, so inspect its output
+        Command::new("tasklist")
+            .arg("/FI")
+            .arg(format!("IMAGENAME eq {}", name))
+            .arg("/NH")
+            .output()
+            .is_ok_and(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .to_lowercase()
+                    .contains(&name.to_lowercase())
+            })
+    })
+}
+
+/// Connection reused by every command while `serve` runs (see `keep_connection`)
+static SHARED_BROWSER: Mutex<Option<Arc<Browser>>> = Mutex::new(None);
+
+/// Set by `keep_connection`
+static KEEP_CONNECTION: AtomicBool = AtomicBool::new(false);
+
+/// Reuse one browser connection for the rest of the process instead of reconnecting per call
+pub fn keep_connection() {
+    KEEP_CONNECTION.store(true, Ordering::Relaxed);
+}
+
+/// Try to connect to browser, starting one if needed
+/// With `keep_connection`, a live connection from an earlier call is returned instead.
+pub async fn connect_or_start_browser(config: &Config) -> Result<Arc<Browser>> {
+    if !KEEP_CONNECTION.load(Ordering::Relaxed) {
+        return Ok(Arc::new(
+            connect_or_launch(config).await.map_err(unavailable)?,
+        ));
+    }
+
+    let cached = SHARED_BROWSER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let Some(browser) = cached {
+        let alive = tokio::time::timeout(std::time::Duration::from_secs(2), browser.version())
+            .await
+            .is_ok_and(|r| r.is_ok());
+        if alive {
+            return Ok(browser);
         }
+        crate::debug!("shared browser connection is gone, reconnecting");
     }
-    false
+
+    let browser = Arc::new(connect_or_launch(config).await.map_err(unavailable)?);
+    *SHARED_BROWSER.lock().unwrap_or_else(|e| e.into_inner()) = Some(browser.clone());
+    Ok(browser)
 }
 
-/// Try to connect to browser, starting one if needed
-pub async fn connect_or_start_browser(port: u16) -> Result<Browser> {
+/// Report a failed connect or launch as `Error::BrowserUnavailable`, keeping its message
+fn unavailable(e: anyhow::Error) -> anyhow::Error {
+    Error::BrowserUnavailable {
+        reason: format!("{:#}", e),
+    }
+    .into()
+}
+
+async fn connect_or_launch(config: &Config) -> Result<Browser> {
+    // A remote browser is never ours to start
+    if let Some(endpoint) = &config.endpoint {
+        crate::debug!("connect_browser at {}", endpoint);
+        let ws_url = endpoint_ws_url(endpoint).await?;
+        return connect_ws_url(&ws_url)
+            .await
+            .with_context(|| format!("Browser endpoint {} is unreachable", endpoint));
+    }
+
+    let port = config.port();
+
     // First try to connect to existing browser
-    if let Ok(browser) = connect_browser(port).await {
-        return Ok(browser);
+    let err = match connect_browser(port).await {
+        Ok(browser) => return Ok(browser),
+        Err(e) => e,
+    };
+
+    // Find out why before deciding to start a browser: only an empty port means there's none
+    match probe_port(port).await {
+        PortStatus::Closed => {}
+        PortStatus::DevTools(ws_url) => {
+            return Err(err.context(format!(
+                "A browser is debuggable on port {} ({}) but connecting to it failed",
+                port, ws_url
+            )));
+        }
+        PortStatus::Incompatible(detail) => {
+            return Err(anyhow!(
+                "Port {} is in use by something that isn't a browser debugging endpoint ({}).\n\
+                Free the port or choose another one with --port",
+                port,
+                detail
+            ));
+        }
     }
 
     // Check if browser is running without remote debugging
-    if is_browser_running() {
+    // A separate profile directory gets its own browser instance, so that's fine
+    if config.profile_dir().is_none() && is_browser_running() {
         return Err(anyhow!(
             "Browser is running but remote debugging is not enabled.\n\
             Please close your browser and run this command again,\n\
@@ -135,8 +436,23 @@ pub async fn connect_or_start_browser(port: u16) -> Result<Browser> {
         ));
     }
 
-    // No browser running, start one
-    start_browser(port)?;
+    if config.no_launch {
+        return Err(anyhow!(
+            "No browser is reachable on port {} and launching one is disabled (--no-launch).\n\
+            Start it yourself with: vivaldi --remote-debugging-port={}",
+            port,
+            port
+        ));
+    }
+
+    launch_browser(config).await
+}
+
+/// Start a browser with remote debugging and connect to it once it is up
+/// This is the opt-in half of `connect_or_start_browser`; `no_launch` turns it off there.
+pub async fn launch_browser(config: &Config) -> Result<Browser> {
+    let port = config.port();
+    start_browser(config)?;
 
     // Wait for browser to start and retry connection (60 second timeout)
     for attempt in 1..=120 {
@@ -155,11 +471,18 @@ pub async fn connect_or_start_browser(port: u16) -> Result<Browser> {
 }
 
 pub async fn connect_browser(port: u16) -> Result<Browser> {
+    crate::debug!("connect_browser on port {}", port);
     let ws_url = get_browser_ws_url(port).await?;
+    connect_ws_url(&ws_url).await
+}
 
-    let (mut browser, mut handler) = Browser::connect(&ws_url)
-        .await
-        .context("Failed to connect to browser via WebSocket")?;
+/// Connect to the browser whose DevTools WebSocket is at `ws_url`
+pub async fn connect_ws_url(ws_url: &str) -> Result<Browser> {
+    let (mut browser, mut handler) =
+        tokio::time::timeout(std::time::Duration::from_secs(10), Browser::connect(ws_url))
+            .await
+            .map_err(|_| anyhow!("Timed out connecting to browser via WebSocket"))?
+            .context("Failed to connect to browser via WebSocket")?;
 
     tokio::spawn(async move { while handler.next().await.is_some() {} });
 
@@ -172,25 +495,341 @@ pub async fn connect_browser(port: u16) -> Result<Browser> {
     Ok(browser)
 }
 
-pub async fn find_outlook_page(browser: &Browser) -> Result<chromiumoxide::Page> {
+/// All open Outlook tabs, in browser order
+pub async fn find_outlook_pages(
+    browser: &Browser,
+    config: &Config,
+) -> Result<Vec<chromiumoxide::Page>> {
     let pages = browser.pages().await?;
     let timeout = std::time::Duration::from_secs(2);
+    let mut found = Vec::new();
 
     for page in pages {
         let url_result = tokio::time::timeout(timeout, page.url()).await;
-        if let Ok(Ok(Some(u))) = url_result {
-            if u.contains("outlook.office.com")
-                || u.contains("outlook.live.com")
-                || u.contains("outlook.office365.com")
-            {
-                return Ok(page);
+        if let Ok(Ok(Some(u))) = url_result
+            && config.is_outlook_url(&u)
+        {
+            found.push(page);
+        }
+    }
+
+    Ok(found)
+}
+
+/// Email address of the account signed in to an Outlook tab
+pub async fn page_account(page: &chromiumoxide::Page) -> Result<Option<String>> {
+    let script = r#"
+        (() => {
+            const el = document.querySelector('#mectrl_currentAccount_secondary, #O365_MainLink_Me, [aria-label*="Account manager"]');
+            if (!el) return null;
+            const text = (el.textContent || '') + ' ' + (el.getAttribute('aria-label') || '');
+            const match = text.match(/[\w.+-]+@[\w-]+\.[\w.-]+/);
+            return match ? match[0] : null;
+        })()
+    "#;
+
+    let result = evaluate(page, "page_account", script).await?;
+    Ok(result.into_value::<Option<String>>().unwrap_or(None))
+}
+
+/// Pick the Outlook tab to work with
+/// Filters by `config.account` when set, then picks `config.tab` (default: the first)
+pub async fn find_outlook_page(browser: &Browser, config: &Config) -> Result<chromiumoxide::Page> {
+    let mut pages = find_outlook_pages(browser, config).await?;
+
+    if pages.is_empty() {
+        if config.headless {
+            return Err(anyhow!(
+                "No Outlook tab found in headless browser.\n\
+                Headless sessions must use a profile that is already signed in to Outlook;\n\
+                pass --profile <dir> pointing at a logged-in browser profile."
+            ));
+        }
+
+        return Err(anyhow!(
+            "No Outlook tab found. Open Outlook in the browser first."
+        ));
+    }
+
+    if let Some(account) = &config.account {
+        let mut matching = Vec::new();
+        for page in pages {
+            let page_email = page_account(&page).await.ok().flatten();
+            if page_email.is_some_and(|e| e.eq_ignore_ascii_case(account)) {
+                matching.push(page);
+            }
+        }
+        if matching.is_empty() {
+            return Err(anyhow!("No Outlook tab signed in as {}", account));
+        }
+        pages = matching;
+    }
+
+    let index = config.tab.unwrap_or(0);
+    let count = pages.len();
+    pages.into_iter().nth(index).ok_or_else(|| {
+        anyhow!(
+            "Outlook tab {} not found ({} tab{} open)",
+            index,
+            count,
+            if count == 1 { "" } else { "s" }
+        )
+    })
+}
+
+/// Whether the browser session is signed in to Outlook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AuthStatus {
+    LoggedIn,
+    LoggedOut,
+    Unknown,
+}
+
+/// Hostnames of the Microsoft sign-in pages Outlook redirects to
+const LOGIN_HOSTS: &[&str] = &["login.microsoftonline.com", "login.live.com"];
+
+/// Inspect open tabs for signs of an expired or missing session
+pub async fn check_auth(browser: &Browser, config: &Config) -> Result<AuthStatus> {
+    let pages = browser.pages().await?;
+    let timeout = std::time::Duration::from_secs(2);
+    let mut on_login_page = false;
+
+    for page in pages {
+        let url = match tokio::time::timeout(timeout, page.url()).await {
+            Ok(Ok(Some(u))) => u,
+            _ => continue,
+        };
+
+        if LOGIN_HOSTS.iter().any(|host| url.contains(host)) {
+            on_login_page = true;
+            continue;
+        }
+        if !config.is_outlook_url(&url) {
+            continue;
+        }
+
+        let script = format!(
+            r#"
+            (() => {{
+                if (document.querySelector('input[type="password"]')) return 'logged_out';
+                if (document.querySelector({item}) ||
+                    document.querySelector('[role="listbox"], [role="tree"]')) return 'logged_in';
+                return 'unknown';
+            }})()
+        "#,
+            item = message_item_js()
+        );
+        let result = evaluate(&page, "check_auth", script).await?;
+        match result.into_value::<String>().unwrap_or_default().as_str() {
+            "logged_in" => return Ok(AuthStatus::LoggedIn),
+            "logged_out" => return Ok(AuthStatus::LoggedOut),
+            _ => {}
+        }
+    }
+
+    // No mail list anywhere, but a sign-in page is open
+    if on_login_page {
+        return Ok(AuthStatus::LoggedOut);
+    }
+
+    Ok(AuthStatus::Unknown)
+}
+
+/// Poll the session until it is signed in, prompting once on stderr
+/// Connects (or starts a browser) first so a fresh profile shows the sign-in page.
+pub async fn wait_for_login(config: &Config, timeout: std::time::Duration) -> Result<()> {
+    let settings = Arc::new(Settings::new(config));
+    crate::settings::scope(settings, async {
+        let browser = connect_or_start_browser(config).await?;
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut prompted = false;
+
+        loop {
+            if check_auth(&browser, config).await? == AuthStatus::LoggedIn {
+                if prompted {
+                    crate::progress::notice(format_args!("Signed in."));
+                }
+                return Ok(());
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(anyhow::Error::new(Error::NotAuthenticated).context(format!(
+                    "Not signed in to Outlook after {} seconds",
+                    timeout.as_secs()
+                )));
+            }
+            if !prompted {
+                eprintln!("Waiting for you to sign in to Outlook in the browser...");
+                prompted = true;
             }
+            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        }
+    })
+    .await
+}
+
+/// Bail with a clear message if the session is signed out
+pub async fn ensure_signed_in(browser: &Browser, config: &Config) -> Result<()> {
+    if check_auth(browser, config).await? == AuthStatus::LoggedOut {
+        return Err(Error::NotAuthenticated.into());
+    }
+    Ok(())
+}
+
+/// Upper bound for a single script evaluation, so a wedged page can't hang a command
+const EVALUATE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Evaluate a script on the page, logging its name, outcome, and (at trace level) the raw result
+pub async fn evaluate(
+    page: &chromiumoxide::Page,
+    name: &str,
+    script: impl Into<chromiumoxide::js::Evaluation>,
+) -> Result<chromiumoxide::js::EvaluationResult> {
+    crate::debug!("evaluate {}", name);
+    let result = tokio::time::timeout(EVALUATE_TIMEOUT, page.evaluate(script))
+        .await
+        .map_err(|_| Error::Timeout {
+            what: format!("Script '{}'", name),
+            secs: EVALUATE_TIMEOUT.as_secs(),
+        })?;
+    match result {
+        Ok(result) => {
+            crate::debug!("evaluate {}: ok", name);
+            crate::trace!(
+                "evaluate {} returned: {}",
+                name,
+                result
+                    .value()
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| "undefined".to_string())
+            );
+            Ok(result)
+        }
+        Err(e) => {
+            crate::debug!("evaluate {}: failed: {}", name, e);
+            Err(e.into())
+        }
+    }
+}
+
+/// Run a read-only script that returns `JSON.stringify(...)` and parse its result as `T`
+/// A valid but empty result (`[]`) comes back as is; a missing or unparseable payload is
+/// an error, retried with the configured backoff. The raw payload is logged with -v.
+pub async fn evaluate_json<T: serde::de::DeserializeOwned>(
+    page: &chromiumoxide::Page,
+    name: &str,
+    script: &str,
+) -> Result<T> {
+    crate::retry::with_retry(|| async {
+        let result = evaluate(page, name, script).await?;
+        let Some(json) = result.value().and_then(|v| v.as_str()) else {
+            let raw = result
+                .value()
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "undefined".to_string());
+            crate::debug!("evaluate {}: expected a JSON string, got: {}", name, raw);
+            bail!("Script '{}' did not return JSON", name);
+        };
+        serde_json::from_str(json).map_err(|e| {
+            crate::debug!("evaluate {}: unparseable payload: {}", name, json);
+            anyhow!("Script '{}' returned unexpected JSON: {}", name, e)
+        })
+    })
+    .await
+}
+
+/// Call the JS function `function` with `args` passed as CDP call arguments
+/// The values reach the page as JSON, so quotes and backslashes in them never touch the
+/// script source.
+pub async fn evaluate_with_args(
+    page: &chromiumoxide::Page,
+    name: &str,
+    function: &str,
+    args: &[serde_json::Value],
+) -> Result<chromiumoxide::js::EvaluationResult> {
+    use chromiumoxide::cdp::js_protocol::runtime::{CallArgument, CallFunctionOnParams};
+
+    let params = CallFunctionOnParams::builder()
+        .function_declaration(function)
+        .arguments(
+            args.iter()
+                .map(|arg| CallArgument::builder().value(arg.clone()).build())
+                .collect::<Vec<_>>(),
+        )
+        .build()
+        .map_err(|e| anyhow!(e))?;
+    evaluate(page, name, params).await
+}
+
+/// How long navigation waits for the new folder to finish loading
+const NAVIGATION_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Texts of the placeholder Outlook shows instead of the list for an empty folder
+const EMPTY_FOLDER_TEXTS: &[&str] = &[
+    "nothing in folder",
+    "all caught up",
+    "we didn't find anything",
+    "no items",
+];
+
+/// Wait until the message list has loaded: no spinner or "Working on it" is showing, and
+/// either messages or the empty-folder placeholder are rendered
+/// Returns false if that didn't happen within `timeout`. A page still being navigated
+/// away from (marked by the navigate functions) never counts as loaded.
+pub async fn wait_for_idle(
+    page: &chromiumoxide::Page,
+    timeout: std::time::Duration,
+) -> Result<bool> {
+    let script = r#"
+        (emptyTexts, workingText, item) => {
+            if (window.__outlookWebLeaving) return false;
+            const visible = el => el.getBoundingClientRect().height > 0;
+            const spinners = document.querySelectorAll('[role="progressbar"], .ms-Spinner');
+            if (Array.from(spinners).some(visible)) return false;
+            const main = document.querySelector('[role="main"]') || document.body;
+            const text = (main.innerText || '').toLowerCase();
+            if (text.includes(workingText)) return false;
+            if (document.querySelector(item)) return true;
+            return emptyTexts.some(t => text.includes(t));
         }
+    "#;
+    let empty_texts: Vec<String> = EMPTY_FOLDER_TEXTS
+        .iter()
+        .map(|t| crate::strings::localize(t))
+        .collect();
+    let args = [
+        serde_json::to_value(&empty_texts)?,
+        crate::strings::localize("working on it").into(),
+        message_item().into(),
+    ];
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        // The old document goes away mid-navigation, so a failed evaluate just means "not yet"
+        let idle = evaluate_with_args(page, "wait_for_idle", script, &args)
+            .await
+            .ok()
+            .and_then(|r| r.into_value::<bool>().ok())
+            .unwrap_or(false);
+        if idle {
+            return Ok(true);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Ok(false);
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
     }
+}
 
-    Err(anyhow!(
-        "No Outlook tab found. Open Outlook in the browser first."
-    ))
+/// Wait for the folder just navigated to, going ahead anyway if it never settles
+pub async fn wait_after_navigation(page: &chromiumoxide::Page) {
+    match wait_for_idle(page, NAVIGATION_IDLE_TIMEOUT).await {
+        Ok(true) => {}
+        Ok(false) => crate::debug!(
+            "list still loading after {}s; continuing",
+            NAVIGATION_IDLE_TIMEOUT.as_secs()
+        ),
+        Err(e) => crate::debug!("wait_for_idle failed: {}", e),
+    }
 }
 
 /// Navigate to inbox if not already there
@@ -199,8 +838,9 @@ pub async fn navigate_to_inbox(page: &chromiumoxide::Page) -> Result<()> {
         (() => {
             const url = window.location.href;
             if (url.includes('/inbox') || url.match(/\/mail\/\d+\/?($|id\/)/)) return 'already';
-            const match = url.match(/(https:\/\/outlook\.[^\/]+\/mail\/\d+\/)/);
+            const match = url.match(/(https:\/\/[^\/]+\/mail\/\d+\/)/);
             if (match) {
+                window.__outlookWebLeaving = true;
                 window.location.href = match[1] + 'inbox';
                 return 'navigating';
             }
@@ -208,9 +848,9 @@ pub async fn navigate_to_inbox(page: &chromiumoxide::Page) -> Result<()> {
         })()
     "#;
 
-    let result = page.evaluate(script).await?;
+    let result = evaluate(page, "navigate_to_inbox", script).await?;
     if result.into_value::<String>().unwrap_or_default() == "navigating" {
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        wait_after_navigation(page).await;
     }
 
     Ok(())
@@ -220,40 +860,163 @@ pub async fn navigate_to_inbox(page: &chromiumoxide::Page) -> Result<()> {
 pub async fn type_text(page: &chromiumoxide::Page, text: &str) -> Result<()> {
     use chromiumoxide::cdp::browser_protocol::input::InsertTextParams;
 
+    crate::debug!("type_text ({} chars)", text.chars().count());
     let params = InsertTextParams::builder().text(text).build().unwrap();
     page.execute(params).await?;
 
     Ok(())
 }
 
+/// Split a shortcut like `Ctrl+Shift+v` into its key and modifiers for `press_key`
+/// Modifier names are case-insensitive (`control`, `cmd`, `option` are accepted too);
+/// single letters are lowercased as `press_key` expects.
+pub fn parse_key_combo(combo: &str) -> Result<(String, Vec<&'static str>)> {
+    let mut parts: Vec<&str> = combo.split('+').collect();
+    // A trailing "+" means the plus key itself, e.g. "Ctrl++"
+    if combo == "+" || combo.ends_with("++") {
+        parts.truncate(parts.len() - 2);
+        parts.push("+");
+    }
+    let Some((key, mods)) = parts.split_last() else {
+        anyhow::bail!("Empty key combination");
+    };
+    if key.is_empty() {
+        anyhow::bail!("Missing key in {:?}", combo);
+    }
+
+    let modifiers = mods
+        .iter()
+        .map(|m| match m.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => Ok("Ctrl"),
+            "shift" => Ok("Shift"),
+            "alt" | "option" => Ok("Alt"),
+            "meta" | "cmd" | "super" => Ok("Meta"),
+            _ => Err(anyhow!("Unknown modifier {:?} in {:?}", m, combo)),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let key = if key.chars().count() == 1 {
+        key.to_lowercase()
+    } else {
+        key.to_string()
+    };
+    Ok((key, modifiers))
+}
+
+/// DOM `code` and Windows virtual key code for a key, as CDP key events need them
+/// Letters and digits map directly; named keys and US-layout punctuation use a table.
+fn key_codes(key: &str) -> (String, i64) {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphabetic() {
+            let upper = c.to_ascii_uppercase();
+            return (format!("Key{}", upper), upper as i64);
+        }
+        if c.is_ascii_digit() {
+            return (format!("Digit{}", c), c as i64);
+        }
+    }
+
+    let (code, vk) = match key {
+        "Enter" => ("Enter", 13),
+        "Escape" => ("Escape", 27),
+        "Delete" => ("Delete", 46),
+        "Backspace" => ("Backspace", 8),
+        "Tab" => ("Tab", 9),
+        " " | "Space" => ("Space", 32),
+        "ArrowLeft" => ("ArrowLeft", 37),
+        "ArrowUp" => ("ArrowUp", 38),
+        "ArrowRight" => ("ArrowRight", 39),
+        "ArrowDown" => ("ArrowDown", 40),
+        "Home" => ("Home", 36),
+        "End" => ("End", 35),
+        "PageUp" => ("PageUp", 33),
+        "PageDown" => ("PageDown", 34),
+        "Insert" => ("Insert", 45),
+        ";" => ("Semicolon", 186),
+        "=" | "+" => ("Equal", 187),
+        "," => ("Comma", 188),
+        "-" => ("Minus", 189),
+        "." => ("Period", 190),
+        "/" => ("Slash", 191),
+        "`" => ("Backquote", 192),
+        "[" => ("BracketLeft", 219),
+        "\\" => ("Backslash", 220),
+        "]" => ("BracketRight", 221),
+        "'" => ("Quote", 222),
+        _ => {
+            if let Some(n) = key.strip_prefix('F').and_then(|n| n.parse::<i64>().ok())
+                && (1..=12).contains(&n)
+            {
+                return (key.to_string(), 111 + n);
+            }
+            (key, 0)
+        }
+    };
+    (code.to_string(), vk)
+}
+
 /// Press a key on the page with optional modifiers
 /// For letters, pass lowercase (e.g., "e"). For special keys, pass the key name (e.g., "Delete")
 /// Modifiers: "Ctrl", "Shift", "Alt", "Meta"
+/// Sends trusted CDP key events to the focused element, so listeners that ignore
+/// synthetic `KeyboardEvent`s still see the key.
 pub async fn press_key(
     page: &chromiumoxide::Page,
     key: &str,
     modifiers: Option<&[&str]>,
     sleep_ms: Option<u64>,
 ) -> Result<()> {
-    // For single lowercase letters, code is "Key" + uppercase
-    // For special keys like "Delete", code matches the key
-    let code = if key.len() == 1 && key.chars().next().unwrap().is_ascii_lowercase() {
-        format!("Key{}", key.to_uppercase())
-    } else {
-        key.to_string()
+    use chromiumoxide::cdp::browser_protocol::input::{
+        DispatchKeyEventParams, DispatchKeyEventType,
     };
 
     let mods = modifiers.unwrap_or(&[]);
+    crate::debug!("press_key {} {:?}", key, mods);
     let ctrl = mods.contains(&"Ctrl");
     let shift = mods.contains(&"Shift");
     let alt = mods.contains(&"Alt");
     let meta = mods.contains(&"Meta");
+    // CDP modifier bitmask: Alt=1, Ctrl=2, Meta=4, Shift=8
+    let bits = (alt as i64) | (ctrl as i64) << 1 | (meta as i64) << 2 | (shift as i64) << 3;
 
-    let script = format!(
-        "document.dispatchEvent(new KeyboardEvent('keydown', {{ key: '{}', code: '{}', ctrlKey: {}, shiftKey: {}, altKey: {}, metaKey: {}, bubbles: true }}))",
-        key, code, ctrl, shift, alt, meta
-    );
-    page.evaluate(script).await?;
+    let (code, vk) = key_codes(key);
+    // Shift+letter reports the capital letter as its key, like a real keyboard
+    let key = if shift && key.len() == 1 && key.chars().all(|c| c.is_ascii_lowercase()) {
+        key.to_ascii_uppercase()
+    } else {
+        key.to_string()
+    };
+    // Printable keys without a command modifier also carry text, so they produce input
+    let text = (key.chars().count() == 1 && !ctrl && !alt && !meta).then(|| key.clone());
+
+    let down = DispatchKeyEventParams::builder()
+        .r#type(if text.is_some() {
+            DispatchKeyEventType::KeyDown
+        } else {
+            DispatchKeyEventType::RawKeyDown
+        })
+        .modifiers(bits)
+        .key(&key)
+        .code(&code)
+        .windows_virtual_key_code(vk)
+        .native_virtual_key_code(vk);
+    let down = match &text {
+        Some(text) => down.text(text).unmodified_text(text),
+        None => down,
+    };
+    page.execute(down.build().map_err(|e| anyhow!(e))?).await?;
+
+    let up = DispatchKeyEventParams::builder()
+        .r#type(DispatchKeyEventType::KeyUp)
+        .modifiers(bits)
+        .key(&key)
+        .code(&code)
+        .windows_virtual_key_code(vk)
+        .native_virtual_key_code(vk)
+        .build()
+        .map_err(|e| anyhow!(e))?;
+    page.execute(up).await?;
 
     let ms = sleep_ms.unwrap_or(500);
     tokio::time::sleep(tokio::time::Duration::from_millis(ms)).await;
@@ -271,18 +1034,19 @@ pub async fn click_element(
     let script = format!(
         r#"
         (() => {{
-            const item = document.querySelector('{}');
+            const item = document.querySelector({});
             if (!item) return false;
             item.click();
             return true;
         }})()
     "#,
-        selector
+        serde_json::to_string(selector)?
     );
 
-    let result = page.evaluate(script).await?;
+    let result = evaluate(page, "click_element", script).await?;
     let clicked = result.into_value::<bool>().unwrap_or(false);
 
+    crate::debug!("click_element {}: clicked={}", selector, clicked);
     if !clicked {
         anyhow::bail!("Element not found: {}", selector);
     }
@@ -293,30 +1057,136 @@ pub async fn click_element(
     Ok(())
 }
 
-/// Navigate to junk/spam folder
-pub async fn navigate_to_junk(page: &chromiumoxide::Page) -> Result<()> {
-    let script = r#"
-        (() => {
+/// Wait up to `timeout_ms` for no element to match `selector`
+/// Returns whether it disappeared in time
+pub async fn wait_until_gone(
+    page: &chromiumoxide::Page,
+    selector: &str,
+    timeout_ms: u64,
+) -> Result<bool> {
+    let script = format!(
+        "document.querySelector({}) === null",
+        serde_json::to_string(selector)?
+    );
+    let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_millis(timeout_ms);
+    loop {
+        let result = evaluate(page, "wait_until_gone", script.as_str()).await?;
+        if result.into_value::<bool>().unwrap_or(false) {
+            return Ok(true);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Ok(false);
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+    }
+}
+
+/// Ctrl-click an element by selector to add it to the current selection
+/// Sleeps after clicking for the specified duration (default 300ms)
+pub async fn ctrl_click_element(
+    page: &chromiumoxide::Page,
+    selector: &str,
+    sleep_ms: Option<u64>,
+) -> Result<()> {
+    let script = format!(
+        r#"
+        (() => {{
+            const item = document.querySelector({});
+            if (!item) return false;
+            const opts = {{ bubbles: true, cancelable: true, view: window, ctrlKey: true, metaKey: true }};
+            item.dispatchEvent(new MouseEvent('mousedown', opts));
+            item.dispatchEvent(new MouseEvent('mouseup', opts));
+            item.dispatchEvent(new MouseEvent('click', opts));
+            return true;
+        }})()
+    "#,
+        serde_json::to_string(selector)?
+    );
+
+    let result = evaluate(page, "ctrl_click_element", script).await?;
+    let clicked = result.into_value::<bool>().unwrap_or(false);
+
+    crate::debug!("ctrl_click_element {}: clicked={}", selector, clicked);
+    if !clicked {
+        anyhow::bail!("Element not found: {}", selector);
+    }
+
+    let ms = sleep_ms.unwrap_or(300);
+    tokio::time::sleep(tokio::time::Duration::from_millis(ms)).await;
+
+    Ok(())
+}
+
+/// Well-known Outlook mail folders
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Folder {
+    Inbox,
+    Junk,
+    Deleted,
+    Sent,
+    Drafts,
+    Archive,
+}
+
+impl Folder {
+    /// URL path segment used by Outlook for this folder
+    pub fn path(&self) -> &'static str {
+        match self {
+            Folder::Inbox => "inbox",
+            Folder::Junk => "junkemail",
+            Folder::Deleted => "deleteditems",
+            Folder::Sent => "sentitems",
+            Folder::Drafts => "drafts",
+            Folder::Archive => "archive",
+        }
+    }
+
+    /// Name shown in the Outlook folder pane
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Folder::Inbox => "Inbox",
+            Folder::Junk => "Junk Email",
+            Folder::Deleted => "Deleted Items",
+            Folder::Sent => "Sent Items",
+            Folder::Drafts => "Drafts",
+            Folder::Archive => "Archive",
+        }
+    }
+}
+
+/// Navigate to a folder if not already there
+pub async fn navigate_to_folder(page: &chromiumoxide::Page, folder: Folder) -> Result<()> {
+    let script = format!(
+        r#"
+        (() => {{
             const url = window.location.href;
-            if (url.includes('/junkemail')) return 'already';
-            const match = url.match(/(https:\/\/outlook\.[^\/]+\/mail\/\d+\/)/);
-            if (match) {
-                window.location.href = match[1] + 'junkemail';
+            if (url.includes('/{path}')) return 'already';
+            const match = url.match(/(https:\/\/[^\/]+\/mail\/\d+\/)/);
+            if (match) {{
+                window.__outlookWebLeaving = true;
+                window.location.href = match[1] + '{path}';
                 return 'navigating';
-            }
+            }}
             return 'failed';
-        })()
-    "#;
+        }})()
+    "#,
+        path = folder.path()
+    );
 
-    let result = page.evaluate(script).await?;
+    let result = evaluate(page, "navigate_to_folder", script).await?;
     let status = result.into_value::<String>().unwrap_or_default();
 
     if status == "failed" {
         anyhow::bail!("Failed to parse Outlook URL for navigation");
     }
     if status == "navigating" {
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        wait_after_navigation(page).await;
     }
 
     Ok(())
 }
+
+/// Navigate to junk/spam folder
+pub async fn navigate_to_junk(page: &chromiumoxide::Page) -> Result<()> {
+    navigate_to_folder(page, Folder::Junk).await
+}
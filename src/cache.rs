@@ -0,0 +1,60 @@
+use anyhow::Result;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::path::PathBuf;
+
+/// Directory a cache is kept in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    /// The platform cache directory, for state that is fine to lose
+    Cache,
+    /// The platform config directory, next to the default `config.json`
+    Config,
+}
+
+/// Path of cache `name`, kept apart per account or profile (see `Settings::cache_scope`)
+/// Without a scope the shared, unsuffixed cache files are used.
+fn cache_path(location: Location, name: &str) -> Result<PathBuf> {
+    let dir =
+        match location {
+            Location::Cache => dirs::cache_dir()
+                .ok_or_else(|| anyhow::anyhow!("Could not find cache directory"))?,
+            Location::Config => dirs::config_dir()
+                .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?,
+        };
+    let file = match &crate::settings::current().cache_scope {
+        Some(scope) => {
+            let scope: String = scope
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || "@.-_".contains(c) {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            format!("{}-{}.json", name, scope)
+        }
+        None => format!("{}.json", name),
+    };
+    Ok(dir.join("outlook-web").join(file))
+}
+
+/// Load a cached value, falling back to the default if missing or unreadable
+pub fn load<T: DeserializeOwned + Default>(location: Location, name: &str) -> T {
+    cache_path(location, name)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save<T: Serialize>(location: Location, name: &str, value: &T) -> Result<()> {
+    let path = cache_path(location, name)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string(value)?)?;
+    Ok(())
+}
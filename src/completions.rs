@@ -0,0 +1,363 @@
+use clap::{Arg, Command};
+
+/// Shell to generate a completion script for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+/// Live values the binary lists for completion scripts through `__complete`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Dynamic {
+    /// Folder names from the folder pane
+    Folders,
+    /// Category names, from the label cache when it is fresh
+    Labels,
+}
+
+impl Dynamic {
+    /// Kind of live value an argument takes, from its value name (`LABEL` or `FOLDER`)
+    /// Arguments with a fixed set of values are completed statically instead.
+    fn of(arg: &Arg) -> Option<Dynamic> {
+        if !possible_values(arg).is_empty() {
+            return None;
+        }
+        match arg.get_value_names()?.first()?.as_str() {
+            "LABEL" => Some(Dynamic::Labels),
+            "FOLDER" => Some(Dynamic::Folders),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Dynamic::Folders => "folders",
+            Dynamic::Labels => "labels",
+        }
+    }
+}
+
+/// 1-based positions of the positionals of `cmd` that take live values
+fn dynamic_positionals(cmd: &Command) -> Vec<(usize, Dynamic)> {
+    cmd.get_positionals()
+        .enumerate()
+        .filter_map(|(i, arg)| Some((i + 1, Dynamic::of(arg)?)))
+        .collect()
+}
+
+/// Completion script for `shell` covering `cmd`'s subcommands, their flags, and the
+/// values of enumerated arguments
+pub fn generate(shell: Shell, mut cmd: Command) -> String {
+    // Building propagates global flags into every subcommand
+    cmd.build();
+    match shell {
+        Shell::Bash => bash(&cmd),
+        Shell::Zsh => format!(
+            "#compdef {}\nautoload -U +X bashcompinit && bashcompinit\n{}",
+            cmd.get_name(),
+            bash(&cmd)
+        ),
+        Shell::Fish => fish(&cmd),
+        Shell::Powershell => powershell(&cmd),
+    }
+}
+
+fn visible_subcommands(cmd: &Command) -> impl Iterator<Item = &Command> {
+    cmd.get_subcommands().filter(|c| !c.is_hide_set())
+}
+
+fn visible_options(cmd: &Command) -> impl Iterator<Item = &Arg> {
+    cmd.get_arguments()
+        .filter(|a| !a.is_positional() && !a.is_hide_set())
+}
+
+/// `--long` and `-s` spellings of an option
+fn option_spellings(arg: &Arg) -> Vec<String> {
+    arg.get_long()
+        .map(|l| format!("--{}", l))
+        .into_iter()
+        .chain(arg.get_short().map(|s| format!("-{}", s)))
+        .collect()
+}
+
+/// Spellings of every visible option of `cmd`
+fn option_words(cmd: &Command) -> Vec<String> {
+    visible_options(cmd).flat_map(option_spellings).collect()
+}
+
+/// Values clap accepts for an enumerated argument (empty for free-form ones)
+fn possible_values(arg: &Arg) -> Vec<String> {
+    arg.get_possible_values()
+        .iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| v.get_name().to_string())
+        .collect()
+}
+
+/// Whether an option takes the next word as its value
+/// Options with `require_equals` only take a value as `--opt=value`.
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_action().takes_values()
+        && !arg.is_require_equals_set()
+        && arg.get_num_args().is_none_or(|n| n.max_values() > 0)
+}
+
+/// First line of an argument's or subcommand's help text
+fn summary(help: Option<&clap::builder::StyledStr>) -> String {
+    help.map(|h| h.to_string().lines().next().unwrap_or("").to_string())
+        .unwrap_or_default()
+}
+
+fn bash(cmd: &Command) -> String {
+    let bin = cmd.get_name();
+    let func = format!("_{}", bin.replace('-', "_"));
+    let names: Vec<&str> = visible_subcommands(cmd).map(|c| c.get_name()).collect();
+
+    let mut top = names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    top.extend(option_words(cmd));
+
+    let mut cases = String::new();
+    for sub in visible_subcommands(cmd) {
+        cases.push_str(&format!("        {})\n", sub.get_name()));
+        cases.push_str("            case \"$prev\" in\n");
+        // Free-form values get nothing of ours, so bash falls back to file names
+        let mut free = Vec::new();
+        for arg in visible_options(sub).filter(|a| takes_value(a)) {
+            let spellings = option_spellings(arg);
+            if let Some(kind) = Dynamic::of(arg) {
+                cases.push_str(&format!(
+                    "                {}) {}_dynamic {}; return ;;\n",
+                    spellings.join("|"),
+                    func,
+                    kind.name()
+                ));
+                continue;
+            }
+            let values = possible_values(arg);
+            if values.is_empty() {
+                free.extend(spellings);
+                continue;
+            }
+            cases.push_str(&format!(
+                "                {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;\n",
+                spellings.join("|"),
+                values.join(" ")
+            ));
+        }
+        if !free.is_empty() {
+            cases.push_str(&format!("                {}) return ;;\n", free.join("|")));
+        }
+        cases.push_str("            esac\n");
+
+        for (position, kind) in dynamic_positionals(sub) {
+            cases.push_str(&format!(
+                "            if [[ \"$cur\" != -* && $pos -eq {} ]]; then\n                {}_dynamic {}; return\n            fi\n",
+                position - 1,
+                func,
+                kind.name()
+            ));
+        }
+
+        let mut words = option_words(sub);
+        for arg in sub.get_positionals().filter(|a| !a.is_hide_set()) {
+            words.extend(possible_values(arg));
+        }
+        cases.push_str(&format!("            opts=\"{}\"\n", words.join(" ")));
+        cases.push_str("            ;;\n");
+    }
+
+    format!(
+        r#"# Ask the binary itself for live folder or label names
+{func}_dynamic() {{
+    local IFS=$'\n'
+    COMPREPLY=($(compgen -W "$("${{COMP_WORDS[0]}}" __complete "$1" 2>/dev/null)" -- "$cur"))
+    COMPREPLY=("${{COMPREPLY[@]// /\\ }}")
+}}
+
+{func}() {{
+    local cur prev cmd opts word pos
+    cur="${{COMP_WORDS[COMP_CWORD]}}"
+    prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    cmd=""
+    # Positionals already given after the subcommand
+    pos=0
+    for word in "${{COMP_WORDS[@]:1:COMP_CWORD-1}}"; do
+        if [[ -z "$cmd" ]]; then
+            case "$word" in
+                {names}) cmd="$word" ;;
+            esac
+        elif [[ "$word" != -* ]]; then
+            pos=$((pos + 1))
+        fi
+    done
+
+    case "$cmd" in
+        "")
+            opts="{top}"
+            ;;
+{cases}    esac
+    COMPREPLY=($(compgen -W "$opts" -- "$cur"))
+}}
+complete -o default -F {func} {bin}
+"#,
+        names = names.join("|"),
+        top = top.join(" "),
+    )
+}
+
+fn fish(cmd: &Command) -> String {
+    let bin = cmd.get_name();
+    let quote = |s: &str| format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"));
+    let mut out = String::new();
+
+    let option_line = |condition: &str, arg: &Arg| {
+        let mut line = format!("complete -c {} -n {}", bin, quote(condition));
+        if let Some(long) = arg.get_long() {
+            line.push_str(&format!(" -l {}", long));
+        }
+        if let Some(short) = arg.get_short() {
+            line.push_str(&format!(" -s {}", short));
+        }
+        if takes_value(arg) {
+            line.push_str(" -r");
+            let values = possible_values(arg);
+            if !values.is_empty() {
+                line.push_str(&format!(" -f -a {}", quote(&values.join(" "))));
+            } else if let Some(kind) = Dynamic::of(arg) {
+                let call = format!("({} __complete {} 2>/dev/null)", bin, kind.name());
+                line.push_str(&format!(" -f -a {}", quote(&call)));
+            }
+        }
+        let help = summary(arg.get_help());
+        if !help.is_empty() {
+            line.push_str(&format!(" -d {}", quote(&help)));
+        }
+        line.push('\n');
+        line
+    };
+
+    for arg in visible_options(cmd) {
+        out.push_str(&option_line("__fish_use_subcommand", arg));
+    }
+    for sub in visible_subcommands(cmd) {
+        out.push_str(&format!(
+            "complete -c {} -n __fish_use_subcommand -f -a {} -d {}\n",
+            bin,
+            sub.get_name(),
+            quote(&summary(sub.get_about()))
+        ));
+    }
+    for sub in visible_subcommands(cmd) {
+        let condition = format!("__fish_seen_subcommand_from {}", sub.get_name());
+        for arg in visible_options(sub) {
+            out.push_str(&option_line(&condition, arg));
+        }
+        for arg in sub.get_positionals().filter(|a| !a.is_hide_set()) {
+            let values = possible_values(arg);
+            if !values.is_empty() {
+                out.push_str(&format!(
+                    "complete -c {} -n {} -f -a {}\n",
+                    bin,
+                    quote(&condition),
+                    quote(&values.join(" "))
+                ));
+            }
+        }
+        // The command line holds the binary and subcommand before the positionals
+        for (position, kind) in dynamic_positionals(sub) {
+            let at = format!(
+                "{}; and test (__fish_number_of_cmd_args_wo_opts) -eq {}",
+                condition,
+                position + 1
+            );
+            out.push_str(&format!(
+                "complete -c {} -n {} -f -a {}\n",
+                bin,
+                quote(&at),
+                quote(&format!("({} __complete {} 2>/dev/null)", bin, kind.name()))
+            ));
+        }
+    }
+    out
+}
+
+fn powershell(cmd: &Command) -> String {
+    let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+    let list = |words: Vec<String>| {
+        words
+            .iter()
+            .map(|w| quote(w))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut top: Vec<String> = visible_subcommands(cmd)
+        .map(|c| c.get_name().to_string())
+        .collect();
+    top.extend(option_words(cmd));
+
+    let mut table = format!("        '' = @({})\n", list(top));
+    let mut dynamic = String::new();
+    for sub in visible_subcommands(cmd) {
+        let positions = dynamic_positionals(sub);
+        if !positions.is_empty() {
+            let entries: Vec<String> = positions
+                .iter()
+                .map(|(position, kind)| format!("{} = '{}'", position, kind.name()))
+                .collect();
+            dynamic.push_str(&format!(
+                "        {} = @{{ {} }}\n",
+                quote(sub.get_name()),
+                entries.join("; ")
+            ));
+        }
+        let mut words = option_words(sub);
+        for arg in sub.get_positionals().filter(|a| !a.is_hide_set()) {
+            words.extend(possible_values(arg));
+        }
+        table.push_str(&format!(
+            "        {} = @({})\n",
+            quote(sub.get_name()),
+            list(words)
+        ));
+    }
+
+    format!(
+        r#"Register-ArgumentCompleter -Native -CommandName {bin} -ScriptBlock {{
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $words = @{{
+{table}    }}
+    $dynamic = @{{
+{dynamic}    }}
+    $subcommand = ''
+    $position = 0
+    foreach ($element in $commandAst.CommandElements | Select-Object -Skip 1) {{
+        if ($element.Extent.EndOffset -ge $cursorPosition) {{ break }}
+        $word = $element.ToString()
+        if ($subcommand -eq '') {{
+            if ($words.ContainsKey($word)) {{ $subcommand = $word }}
+        }} elseif (-not $word.StartsWith('-')) {{
+            $position++
+        }}
+    }}
+    if ($dynamic.ContainsKey($subcommand) -and -not $wordToComplete.StartsWith('-')) {{
+        $kind = $dynamic[$subcommand][$position + 1]
+        if ($kind) {{
+            & {bin} __complete $kind 2>$null | Where-Object {{ $_ -like "$wordToComplete*" }} | ForEach-Object {{
+                $text = if ($_ -match '\s') {{ "'" + ($_ -replace "'", "''") + "'" }} else {{ $_ }}
+                [System.Management.Automation.CompletionResult]::new($text, $_, 'ParameterValue', $_)
+            }}
+            return
+        }}
+    }}
+    $words[$subcommand] | Where-Object {{ $_ -like "$wordToComplete*" }} | ForEach-Object {{
+        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+    }}
+}}
+"#,
+        bin = quote(cmd.get_name()),
+    )
+}
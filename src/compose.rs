@@ -0,0 +1,242 @@
+use crate::api::Contact;
+use crate::browser::{click_element, evaluate, evaluate_json, type_text};
+use crate::strings::attr_selector;
+use anyhow::Result;
+use chromiumoxide::Page;
+
+/// Button labelled (or titled) with the localized `text`
+fn button_selector(text: &str) -> String {
+    format!(
+        "button{}, button{}",
+        attr_selector("aria-label", text),
+        attr_selector("title", text)
+    )
+}
+
+/// "New mail" button in the ribbon / left rail
+fn new_mail_selector() -> String {
+    button_selector("New mail")
+}
+
+/// Recipient well for the To line of the compose pane
+fn to_field_selector() -> String {
+    let to = attr_selector("aria-label", "To");
+    format!(r#"div[role="textbox"]{to}, input{to}, [contenteditable="true"]{to}"#)
+}
+
+/// Subject line of the compose pane
+fn subject_field_selector() -> String {
+    format!(
+        "input{}, input{}",
+        attr_selector("aria-label", "Add a subject"),
+        attr_selector("placeholder", "Add a subject")
+    )
+}
+
+/// Body editor of the compose pane
+const BODY_FIELD_SELECTOR: &str = r#"div[role="textbox"][aria-label*="Message body"]"#;
+
+/// "Attach file" button in the compose ribbon
+fn attach_selector() -> String {
+    button_selector("Attach file")
+}
+
+/// Discard button in the compose pane toolbar
+fn discard_selector() -> String {
+    button_selector("Discard")
+}
+
+/// Finds the open compose pane: the innermost element holding both its To field and body
+const FIND_COMPOSE_ROOT_JS: &str = r#"
+    function findComposeRoot(toSelector, bodySelector) {
+        let el = document.querySelector(toSelector);
+        while (el && !el.querySelector(bodySelector)) el = el.parentElement;
+        return el;
+    }
+"#;
+
+/// Open a new compose pane and wait for its To field
+pub async fn open_compose(page: &Page) -> Result<()> {
+    click_element(page, &new_mail_selector(), Some(1000)).await?;
+
+    let script = format!(
+        "document.querySelector({}) !== null",
+        serde_json::to_string(&to_field_selector())?
+    );
+    crate::retry::with_retry(|| async {
+        let result = evaluate(page, "open_compose:wait", script.as_str()).await?;
+        if !result.into_value::<bool>().unwrap_or(false) {
+            anyhow::bail!("Compose pane didn't open");
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Discard the open compose pane, confirming the prompt if Outlook asks
+pub async fn discard_compose(page: &Page) -> Result<()> {
+    use crate::menu::click_dialog_button;
+
+    // Close any autocomplete dropdown first so the click reaches the toolbar
+    crate::browser::press_key(page, "Escape", None, Some(200)).await?;
+    click_element(page, &discard_selector(), Some(500)).await?;
+    // The confirmation only appears once something was typed
+    if let Err(e) = click_dialog_button(page, &["discard", "ok"], Some(500)).await {
+        crate::debug!("discard_compose: no confirmation: {}", e);
+    }
+    Ok(())
+}
+
+/// Fill the open compose pane's recipients, subject and body
+/// Each recipient is typed and committed with `;` so Outlook resolves it.
+pub async fn fill_compose(page: &Page, to: &[String], subject: &str, body: &str) -> Result<()> {
+    use crate::menu::left_click_element;
+
+    left_click_element(page, &to_field_selector(), Some(200)).await?;
+    for address in to {
+        type_text(page, address).await?;
+        type_text(page, ";").await?;
+        tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+    }
+
+    left_click_element(page, &subject_field_selector(), Some(200)).await?;
+    type_text(page, subject).await?;
+
+    left_click_element(page, BODY_FIELD_SELECTOR, Some(200)).await?;
+    type_text(page, body).await?;
+    Ok(())
+}
+
+/// Attach local files to the open compose pane and wait for their attachment chips
+/// A page can't set a file input's value itself, so the paths are handed to the input
+/// through CDP, with the native file chooser intercepted so it never opens.
+pub async fn attach_files(page: &Page, files: &[std::path::PathBuf]) -> Result<()> {
+    use chromiumoxide::cdp::browser_protocol::dom::SetFileInputFilesParams;
+    use chromiumoxide::cdp::browser_protocol::page::SetInterceptFileChooserDialogParams;
+
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let paths = files
+        .iter()
+        .map(|f| Ok(std::fs::canonicalize(f)?.to_string_lossy().into_owned()))
+        .collect::<Result<Vec<_>>>()?;
+
+    page.execute(SetInterceptFileChooserDialogParams::new(true))
+        .await?;
+    let result = async {
+        click_element(page, &attach_selector(), Some(500)).await?;
+        // Outlook offers cloud locations first; the local option creates the file input
+        if !crate::menu::try_click_menu_item(page, "browse this computer", Some(500)).await? {
+            crate::debug!("attach_files: no browse menu item");
+        }
+
+        let input = page.find_element(r#"input[type="file"]"#).await?;
+        let params = SetFileInputFilesParams::builder()
+            .files(paths)
+            .backend_node_id(input.backend_node_id)
+            .build()
+            .map_err(|e| anyhow::anyhow!(e))?;
+        page.execute(params).await?;
+        anyhow::Ok(())
+    }
+    .await;
+
+    // Interception stays on for the tab otherwise, swallowing every later file dialog
+    page.execute(SetInterceptFileChooserDialogParams::new(false))
+        .await?;
+    result?;
+
+    // Each attachment shows up as a chip titled with its file name once uploaded
+    let names: Vec<String> = files
+        .iter()
+        .filter_map(|f| f.file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+        .collect();
+    // Only chips in this compose pane count, not file names shown elsewhere on the page
+    let script = format!(
+        r#"
+        (() => {{
+            {find_compose_root}
+            const names = {names};
+            const root = findComposeRoot({to}, {body});
+            if (!root) return names;
+            const chips = Array.from(root.querySelectorAll('[title], [aria-label]'));
+            return names.filter(name => !chips
+                .some(el => (el.getAttribute('title') || el.getAttribute('aria-label') || '').includes(name)));
+        }})()
+    "#,
+        find_compose_root = FIND_COMPOSE_ROOT_JS,
+        names = serde_json::to_string(&names)?,
+        to = serde_json::to_string(&to_field_selector())?,
+        body = serde_json::to_string(BODY_FIELD_SELECTOR)?
+    );
+    let mut missing = names.clone();
+    for _ in 0..30 {
+        let result = evaluate(page, "attach_files:chips", script.as_str()).await?;
+        missing = result.into_value::<Vec<String>>().unwrap_or(missing);
+        if missing.is_empty() {
+            return Ok(());
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    }
+    anyhow::bail!("Attachment(s) did not appear: {}", missing.join(", "))
+}
+
+/// Addresses Outlook resolved on the To line of the open compose pane, read from its chips
+pub async fn resolved_recipients(page: &Page) -> Result<Vec<String>> {
+    let script = format!(
+        r#"
+        (() => {{
+            const field = document.querySelector({to});
+            if (!field) return JSON.stringify([]);
+            const well = field.closest('[role="group"], [class*="well" i]') || field.parentElement;
+            const emailRe = /[\w.+'-]+@[\w-]+(\.[\w-]+)+/;
+            const found = [];
+            well.querySelectorAll('[title], [aria-label]').forEach(el => {{
+                const text = el.getAttribute('title') || el.getAttribute('aria-label') || '';
+                const match = text.match(emailRe);
+                if (match && !found.some(e => e.toLowerCase() === match[0].toLowerCase())) {{
+                    found.push(match[0]);
+                }}
+            }});
+            return JSON.stringify(found);
+        }})()
+    "#,
+        to = serde_json::to_string(&to_field_selector())?
+    );
+    evaluate_json(page, "resolved_recipients", &script).await
+}
+
+/// Save the open compose pane as a draft with Ctrl+S
+pub async fn save_compose(page: &Page) -> Result<()> {
+    crate::browser::press_key(page, "s", Some(&["Ctrl"]), Some(1500)).await
+}
+
+/// Type `query` into the To field and read the autocomplete suggestions
+/// Assumes a compose pane is open
+pub async fn recipient_suggestions(page: &Page, query: &str) -> Result<Vec<Contact>> {
+    crate::menu::left_click_element(page, &to_field_selector(), Some(200)).await?;
+    type_text(page, query).await?;
+    // Suggestions come from the directory service, so give them a moment
+    tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+
+    let script = r#"
+        (() => {
+            const emailRe = /[\w.+'-]+@[\w-]+(\.[\w-]+)+/;
+            const contacts = [];
+            document.querySelectorAll('[role="listbox"] [role="option"]').forEach(option => {
+                const text = option.getAttribute('aria-label') || option.textContent || '';
+                const match = text.match(emailRe);
+                if (!match) return;
+                const email = match[0];
+                if (contacts.some(c => c.email.toLowerCase() === email.toLowerCase())) return;
+                const name = text.replace(email, '').replace(/[<>()]/g, '').trim();
+                contacts.push({ name: name || null, email });
+            });
+            return JSON.stringify(contacts);
+        })()
+    "#;
+    evaluate_json(page, "recipient_suggestions", script).await
+}
@@ -1,40 +1,324 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Outlook URL opened when starting a new browser
+pub const DEFAULT_OUTLOOK_URL: &str = "https://outlook.office.com/mail/";
+
+/// Hostnames always recognised as Outlook tabs
+pub const DEFAULT_OUTLOOK_HOSTS: &[&str] = &[
+    "outlook.office.com",
+    "outlook.live.com",
+    "outlook.office365.com",
+];
+
+/// CSS selector overrides, so a markup change can be patched without a new release
+/// Unset entries use the built-in selectors.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Selectors {
+    /// Row in the message list
+    pub message_item: Option<String>,
+    /// Message body in the reading pane
+    pub reading_pane: Option<String>,
+    /// Subject line in the reading pane
+    pub subject: Option<String>,
+    /// Sender in a reading pane header
+    pub sender: Option<String>,
+    /// Sent/received timestamp in a reading pane header
+    pub date: Option<String>,
+}
+
+impl Selectors {
+    pub fn message_item(&self) -> &str {
+        self.message_item
+            .as_deref()
+            .unwrap_or(crate::browser::MESSAGE_ITEM_SELECTOR)
+    }
+
+    pub fn reading_pane(&self) -> &str {
+        self.reading_pane
+            .as_deref()
+            .unwrap_or(crate::browser::READING_PANE_SELECTOR)
+    }
+
+    pub fn subject(&self) -> &str {
+        self.subject
+            .as_deref()
+            .unwrap_or(crate::browser::SUBJECT_SELECTOR)
+    }
+
+    pub fn sender(&self) -> &str {
+        self.sender
+            .as_deref()
+            .unwrap_or(crate::browser::SENDER_SELECTOR)
+    }
+
+    pub fn date(&self) -> &str {
+        self.date
+            .as_deref()
+            .unwrap_or(crate::browser::DATE_SELECTOR)
+    }
+
+    /// Replace each selector that `other` sets, keeping the rest
+    pub fn overlay(&mut self, other: Selectors) {
+        let Selectors {
+            message_item,
+            reading_pane,
+            subject,
+            sender,
+            date,
+        } = other;
+        self.message_item = message_item.or(self.message_item.take());
+        self.reading_pane = reading_pane.or(self.reading_pane.take());
+        self.subject = subject.or(self.subject.take());
+        self.sender = sender.or(self.sender.take());
+        self.date = date.or(self.date.take());
+    }
+}
+
+/// Settings for one account setup, picked per run with `--profile-name`
+/// Each value set here replaces the top-level one; `outlook_hosts` adds to it.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub port: Option<u16>,
+    pub endpoint: Option<String>,
+    pub outlook_url: Option<String>,
+    #[serde(default)]
+    pub outlook_hosts: Vec<String>,
+    /// Browser profile directory to launch with
+    pub profile: Option<PathBuf>,
+    pub account: Option<String>,
+    #[serde(default)]
+    pub selectors: Selectors,
+}
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Config {
     pub port: Option<u16>,
+    /// DevTools address of a browser running elsewhere (ws://, wss://, http:// or https://)
+    /// When set, the tool connects there and never starts a browser itself.
+    pub endpoint: Option<String>,
+    /// Outlook URL to open when starting a browser (for vanity/enterprise domains)
+    pub outlook_url: Option<String>,
+    /// Extra hostnames to recognise as Outlook tabs
+    #[serde(default)]
+    pub outlook_hosts: Vec<String>,
+    /// Start the browser without a visible window
+    #[serde(default)]
+    pub headless: bool,
+    /// Never start a browser; fail when none is reachable
+    #[serde(default)]
+    pub no_launch: bool,
+    /// Browser profile directory to launch with
+    pub profile: Option<PathBuf>,
+    /// Email of the account whose Outlook tab should be used
+    pub account: Option<String>,
+    /// Index of the Outlook tab to use (set per run with --tab)
+    #[serde(skip)]
+    pub tab: Option<usize>,
+    /// Profile applied with `apply_profile`, if any
+    #[serde(skip)]
+    pub profile_name: Option<String>,
+    /// How many times to try flaky page operations
+    pub retry_attempts: Option<u32>,
+    /// Initial delay between retries in milliseconds (doubles each time)
+    pub retry_delay_ms: Option<u64>,
+    /// How many times a listing may scroll the message list to reach `-n`
+    pub max_scroll: Option<u32>,
+    /// Minimum gap in milliseconds between mutating operations (0 = none)
+    pub pace_ms: Option<u64>,
+    /// Master list of known labels, maintained with `sync-labels --add`
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// How long the cached label list stays fresh, in seconds
+    pub labels_ttl_secs: Option<u64>,
+    /// Focused Inbox pivot that `list` shows by default
+    pub inbox_view: Option<crate::list::InboxView>,
+    /// Canned responses by name, with `{{key}}` placeholders (see `template::render`)
+    #[serde(default)]
+    pub templates: std::collections::BTreeMap<String, String>,
+    /// Overrides for the CSS selectors used to read messages
+    #[serde(default)]
+    pub selectors: Selectors,
+    /// Localized menu and button text, keyed by the English text (see `strings::localize`)
+    #[serde(default)]
+    pub strings: std::collections::BTreeMap<String, String>,
+    /// Named account setups (see `Config::apply_profile`)
+    #[serde(default)]
+    pub profiles: std::collections::BTreeMap<String, Profile>,
 }
 
 impl Config {
     pub fn port(&self) -> u16 {
         self.port.unwrap_or(9222)
     }
+
+    pub fn retry_attempts(&self) -> u32 {
+        self.retry_attempts.unwrap_or(3)
+    }
+
+    pub fn retry_delay(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.retry_delay_ms.unwrap_or(300))
+    }
+
+    pub fn max_scroll(&self) -> u32 {
+        self.max_scroll.unwrap_or(50)
+    }
+
+    pub fn pace(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.pace_ms.unwrap_or(0))
+    }
+
+    pub fn labels_ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.labels_ttl_secs.unwrap_or(24 * 60 * 60))
+    }
+
+    pub fn inbox_view(&self) -> crate::list::InboxView {
+        self.inbox_view.unwrap_or(crate::list::InboxView::Focused)
+    }
+
+    pub fn outlook_url(&self) -> &str {
+        self.outlook_url.as_deref().unwrap_or(DEFAULT_OUTLOOK_URL)
+    }
+
+    /// Built-in hosts followed by configured ones
+    pub fn outlook_hosts(&self) -> impl Iterator<Item = &str> {
+        DEFAULT_OUTLOOK_HOSTS
+            .iter()
+            .copied()
+            .chain(self.outlook_hosts.iter().map(String::as_str))
+    }
+
+    /// Profile directory to pass as --user-data-dir when starting a browser
+    /// Headless sessions default to a dedicated profile so they don't fight a running browser
+    pub fn profile_dir(&self) -> Option<PathBuf> {
+        self.profile.clone().or_else(|| {
+            self.headless
+                .then(|| dirs::data_dir().map(|d| d.join("outlook-web").join("profile")))
+                .flatten()
+        })
+    }
+
+    /// Overlay the named profile on the top-level settings
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            if known.is_empty() {
+                anyhow::bail!("Unknown profile '{}' (no profiles configured)", name);
+            }
+            anyhow::bail!("Unknown profile '{}' (known: {})", name, known.join(", "));
+        };
+        if profile.port.is_some() {
+            self.port = profile.port;
+        }
+        if profile.endpoint.is_some() {
+            self.endpoint = profile.endpoint;
+        }
+        if profile.outlook_url.is_some() {
+            self.outlook_url = profile.outlook_url;
+        }
+        self.outlook_hosts.extend(profile.outlook_hosts);
+        if profile.profile.is_some() {
+            self.profile = profile.profile;
+        }
+        if profile.account.is_some() {
+            self.account = profile.account;
+        }
+        self.selectors.overlay(profile.selectors);
+        self.profile_name = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Name the caches are kept under: the account if one is set, else the profile name
+    pub fn cache_scope(&self) -> Option<&str> {
+        self.account.as_deref().or(self.profile_name.as_deref())
+    }
+
+    /// Every setting with defaults filled in, as JSON (stored values alone omit the defaults)
+    pub fn resolved(&self) -> serde_json::Value {
+        serde_json::json!({
+            "port": self.port(),
+            "endpoint": self.endpoint,
+            "outlook_url": self.outlook_url(),
+            "outlook_hosts": self.outlook_hosts().collect::<Vec<_>>(),
+            "headless": self.headless,
+            "no_launch": self.no_launch,
+            "profile": self.profile_dir(),
+            "account": self.account,
+            "retry_attempts": self.retry_attempts(),
+            "retry_delay_ms": self.retry_delay().as_millis() as u64,
+            "max_scroll": self.max_scroll(),
+            "pace_ms": self.pace().as_millis(),
+            "labels": self.labels,
+            "labels_ttl_secs": self.labels_ttl().as_secs(),
+            "inbox_view": self.inbox_view(),
+            "selectors": {
+                "message_item": self.selectors.message_item(),
+                "reading_pane": self.selectors.reading_pane(),
+                "subject": self.selectors.subject(),
+                "sender": self.selectors.sender(),
+                "date": self.selectors.date(),
+            },
+            "templates": self.templates,
+            "strings": self.strings,
+            "profiles": self.profiles.keys().collect::<Vec<_>>(),
+        })
+    }
+
+    /// Check if a URL belongs to Outlook
+    pub fn is_outlook_url(&self, url: &str) -> bool {
+        self.outlook_hosts().any(|host| url.contains(host))
+    }
 }
 
-fn config_path() -> Result<PathBuf> {
+/// Environment variable naming an explicit config file
+pub const CONFIG_ENV: &str = "OUTLOOK_WEB_CONFIG";
+
+/// Location of the config file: `explicit` (from --config), then $OUTLOOK_WEB_CONFIG,
+/// then `outlook-web/config.json` in the platform config directory
+pub fn config_path(explicit: Option<&Path>) -> Result<PathBuf> {
+    if let Some(path) = explicit {
+        return Ok(path.to_path_buf());
+    }
+    if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|p| !p.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
     let config_dir =
         dirs::config_dir().ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?;
     Ok(config_dir.join("outlook-web").join("config.json"))
 }
 
-pub fn load_config() -> Result<Config> {
-    let path = config_path()?;
+pub fn load_config(path: &Path) -> Result<Config> {
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file {}", path.display()))?;
+    // An empty (or whitespace-only) file is treated like a missing one
+    if content.trim().is_empty() {
+        return Ok(Config::default());
+    }
+    serde_json::from_str(&content).with_context(|| {
+        format!(
+            "Invalid config file {} (fix it, or run `outlook-web config --reset`)",
+            path.display()
+        )
+    })
+}
+
+/// Remove the config file so every setting goes back to its default
+pub fn reset_config(path: &Path) -> Result<()> {
     if path.exists() {
-        let content = std::fs::read_to_string(&path)?;
-        Ok(serde_json::from_str(&content)?)
-    } else {
-        Ok(Config::default())
+        std::fs::remove_file(path)?;
     }
+    Ok(())
 }
 
-pub fn save_config(config: &Config) -> Result<()> {
-    let path = config_path()?;
+pub fn save_config(path: &Path, config: &Config) -> Result<()> {
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
     let content = serde_json::to_string_pretty(config)?;
-    std::fs::write(&path, content)?;
+    std::fs::write(path, content)?;
     Ok(())
 }
@@ -0,0 +1,140 @@
+use crate::browser::{self, AuthStatus};
+use crate::config::Config;
+use crate::settings::Settings;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Outcome of one diagnostic check
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    /// Whether a failure stops the CLI from working at all
+    pub critical: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn pass(name: &'static str, critical: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            ok: true,
+            critical,
+            detail: detail.into(),
+        }
+    }
+
+    fn fail(name: &'static str, critical: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            ok: false,
+            critical,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Run each connection check in turn, skipping the ones a failed step makes pointless
+/// Unlike other commands this never starts a browser; it reports what is there.
+pub async fn diagnose(config: &Config) -> Vec<Check> {
+    let settings = Arc::new(Settings::new(config));
+    crate::settings::scope(settings, async {
+        let mut checks = Vec::new();
+        // A browser on disk only matters when we'd have to start one ourselves
+        checks.push(match browser::find_browser() {
+            _ if config.endpoint.is_some() => {
+                Check::pass("browser", false, "Remote browser, not started locally")
+            }
+            Some((name, path)) => {
+                Check::pass("browser", false, format!("{} ({})", name, path.display()))
+            }
+            None => Check::fail(
+                "browser",
+                false,
+                "No supported browser found on disk (Vivaldi, Chromium, Chrome, Edge)",
+            ),
+        });
+
+        let ws_url = match browser::resolve_ws_url(config).await {
+            Ok(url) => url,
+            Err(e) => {
+                checks.push(Check::fail("debug port", true, format!("{:#}", e)));
+                return skip_rest(checks, "debug port unreachable");
+            }
+        };
+        let address = match &config.endpoint {
+            Some(endpoint) => endpoint.clone(),
+            None => format!("127.0.0.1:{}", config.port()),
+        };
+        checks.push(Check::pass(
+            "debug port",
+            true,
+            format!("{} answers", address),
+        ));
+
+        let browser = match browser::connect_ws_url(&ws_url).await {
+            Ok(b) => b,
+            Err(e) => {
+                checks.push(Check::fail("outlook tab", true, format!("{:#}", e)));
+                return skip_rest(checks, "couldn't connect to the browser");
+            }
+        };
+
+        let tabs = browser::find_outlook_pages(&browser, config)
+            .await
+            .unwrap_or_default();
+        if tabs.is_empty() {
+            checks.push(Check::fail(
+                "outlook tab",
+                true,
+                format!("No tab open on {}", config.outlook_url()),
+            ));
+            return skip_rest(checks, "no Outlook tab");
+        }
+        checks.push(Check::pass(
+            "outlook tab",
+            true,
+            format!(
+                "{} tab{} open",
+                tabs.len(),
+                if tabs.len() == 1 { "" } else { "s" }
+            ),
+        ));
+
+        checks.push(match browser::check_auth(&browser, config).await {
+            Ok(AuthStatus::LoggedIn) => Check::pass("auth", true, "Signed in"),
+            Ok(AuthStatus::LoggedOut) => {
+                Check::fail("auth", true, "Signed out; sign in in the browser")
+            }
+            // The mail list may still be loading, so don't fail hard on this
+            Ok(AuthStatus::Unknown) => Check::fail(
+                "auth",
+                false,
+                "Couldn't tell; the page may still be loading",
+            ),
+            Err(e) => Check::fail("auth", false, format!("{:#}", e)),
+        });
+
+        let round_trip = browser::evaluate(&tabs[0], "doctor", "1 + 1")
+            .await
+            .map(|r| r.into_value::<i64>().ok());
+        checks.push(match round_trip {
+            Ok(Some(2)) => Check::pass("evaluate", true, "Page scripts run"),
+            Ok(_) => Check::fail("evaluate", true, "Script returned an unexpected value"),
+            Err(e) => Check::fail("evaluate", true, format!("{:#}", e)),
+        });
+
+        checks
+    })
+    .await
+}
+
+/// Record the checks that depend on an earlier failure as failed with `reason`
+fn skip_rest(mut checks: Vec<Check>, reason: &str) -> Vec<Check> {
+    for name in ["debug port", "outlook tab", "auth", "evaluate"] {
+        if !checks.iter().any(|c| c.name == name) {
+            checks.push(Check::fail(name, true, format!("Skipped: {}", reason)));
+        }
+    }
+    checks
+}
@@ -0,0 +1,178 @@
+/// Failures a caller may want to tell apart, returned by `Client` methods
+/// Everything else is carried as `Other`. Lower-level functions still return
+/// `anyhow::Result`; a typed error raised there comes back out of `Client` as itself.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The message isn't in the current list
+    #[error("Message not found: {id}")]
+    MessageNotFound { id: String },
+    /// A context menu or submenu didn't appear
+    #[error("{menu} didn't open")]
+    MenuNotOpen { menu: String },
+    /// The Outlook session is signed out
+    #[error("You appear to be signed out — sign in to Outlook in the browser")]
+    NotAuthenticated,
+    /// No browser could be reached or started
+    #[error("{reason}")]
+    BrowserUnavailable { reason: String },
+    /// The category isn't one of the account's categories
+    #[error("Category not found: {name} (available: {})", available.join(", "))]
+    CategoryNotFound {
+        name: String,
+        available: Vec<String>,
+    },
+    /// A page script or the whole command ran out of time
+    #[error("{what} timed out after {secs} seconds")]
+    Timeout { what: String, secs: u64 },
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /// An `Other` error carrying just `message`
+    pub fn msg<M>(message: M) -> Self
+    where
+        M: std::fmt::Display + std::fmt::Debug + Send + Sync + 'static,
+    {
+        Error::Other(anyhow::Error::msg(message))
+    }
+}
+
+impl Error {
+    /// The first typed error (anything but `Other`) in `error`'s chain
+    /// Looks inside `Other` too, since it hides the chain of the error it wraps.
+    pub fn find(error: &anyhow::Error) -> Option<&Error> {
+        error
+            .chain()
+            .find_map(|cause| match cause.downcast_ref::<Error>()? {
+                Error::Other(inner) => Error::find(inner),
+                typed => Some(typed),
+            })
+    }
+
+    /// A copy of a typed error; `Other` can't be copied
+    fn typed_clone(&self) -> Option<Error> {
+        Some(match self {
+            Error::MessageNotFound { id } => Error::MessageNotFound { id: id.clone() },
+            Error::MenuNotOpen { menu } => Error::MenuNotOpen { menu: menu.clone() },
+            Error::NotAuthenticated => Error::NotAuthenticated,
+            Error::BrowserUnavailable { reason } => Error::BrowserUnavailable {
+                reason: reason.clone(),
+            },
+            Error::CategoryNotFound { name, available } => Error::CategoryNotFound {
+                name: name.clone(),
+                available: available.clone(),
+            },
+            Error::Timeout { what, secs } => Error::Timeout {
+                what: what.clone(),
+                secs: *secs,
+            },
+            Error::Other(_) => return None,
+        })
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    /// Recover a typed error passed up through `anyhow`, even under added context (which
+    /// is then dropped); wrap anything else
+    fn from(e: anyhow::Error) -> Self {
+        if let Some(typed) = Error::find(&e).and_then(Error::typed_clone) {
+            return typed;
+        }
+        match e.downcast::<Error>() {
+            Ok(other) => other,
+            Err(e) => Error::Other(e),
+        }
+    }
+}
+
+impl From<chromiumoxide::error::CdpError> for Error {
+    fn from(e: chromiumoxide::error::CdpError) -> Self {
+        Error::Other(e.into())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Other(e.into())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Other(e.into())
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Other(e.into())
+    }
+}
+
+/// Exit code for a failure that has no code of its own
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code when the Outlook session is signed out
+pub const EXIT_NOT_AUTHENTICATED: i32 = 3;
+/// Exit code when no browser could be reached or started
+pub const EXIT_BROWSER_UNAVAILABLE: i32 = 4;
+/// Exit code when the message isn't in the current list
+pub const EXIT_MESSAGE_NOT_FOUND: i32 = 5;
+/// Exit code when something timed out (matches coreutils `timeout`)
+pub const EXIT_TIMEOUT: i32 = 124;
+
+/// Process exit code for `error`, so scripts can tell failures apart
+pub fn exit_code(error: &Error) -> i32 {
+    match error {
+        Error::NotAuthenticated => EXIT_NOT_AUTHENTICATED,
+        Error::BrowserUnavailable { .. } => EXIT_BROWSER_UNAVAILABLE,
+        Error::MessageNotFound { .. } => EXIT_MESSAGE_NOT_FOUND,
+        Error::Timeout { .. } => EXIT_TIMEOUT,
+        Error::MenuNotOpen { .. } | Error::CategoryNotFound { .. } | Error::Other(_) => {
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Return early with `Error::msg(format!(...))`, the typed counterpart of `anyhow::bail!`
+#[macro_export]
+macro_rules! bail {
+    ($($arg:tt)*) => {
+        return Err($crate::error::Error::msg(format!($($arg)*)))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn typed_error_survives_context() {
+        let wrapped = Err::<(), _>(anyhow::Error::from(Error::Timeout {
+            what: "Script".into(),
+            secs: 30,
+        }))
+        .context("Failed to parse message")
+        .unwrap_err();
+        assert!(matches!(
+            Error::from(wrapped),
+            Error::Timeout { secs: 30, .. }
+        ));
+    }
+
+    #[test]
+    fn typed_error_inside_other() {
+        let inner = anyhow::Error::from(Error::NotAuthenticated).context("Reading inbox");
+        let outer = anyhow::Error::from(Error::Other(inner)).context("list");
+        assert!(matches!(Error::from(outer), Error::NotAuthenticated));
+    }
+
+    #[test]
+    fn untyped_error_stays_other() {
+        let plain = anyhow::anyhow!("boom").context("outer");
+        assert!(matches!(Error::from(plain), Error::Other(_)));
+    }
+}
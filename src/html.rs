@@ -0,0 +1,314 @@
+/// Tags that start a new line before and after their content
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "blockquote",
+    "div",
+    "dl",
+    "dt",
+    "dd",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Block tags that are also set off from their neighbours by a blank line
+const PARAGRAPH_TAGS: &[&str] = &["blockquote", "h1", "h2", "h3", "h4", "h5", "h6", "p"];
+
+/// Tags whose content is never shown
+const HIDDEN_TAGS: &[&str] = &["head", "script", "style", "title"];
+
+/// Convert an HTML body to readable plain text
+/// Blocks and `<br>` become line breaks and paragraphs are set off by a blank line. List items
+/// get a `* ` bullet, table cells are separated by tabs, and each link is marked `[n]` with
+/// its URL listed as a footnote.
+/// Runs of whitespace collapse as a browser would, and at most one blank line is kept.
+pub fn to_text(html: &str) -> String {
+    let mut out = String::new();
+    let mut links: Vec<String> = Vec::new();
+    // href of the link currently open, if any
+    let mut open_link: Option<String> = None;
+    let mut hidden_depth = 0usize;
+    let mut pre_depth = 0usize;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            push_text(&mut out, rest, pre_depth > 0, hidden_depth > 0);
+            break;
+        };
+        push_text(&mut out, &rest[..lt], pre_depth > 0, hidden_depth > 0);
+        rest = &rest[lt..];
+
+        // Comments can contain '>', so skip to their own terminator
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+        let Some(gt) = rest.find('>') else {
+            push_text(&mut out, rest, pre_depth > 0, hidden_depth > 0);
+            break;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if HIDDEN_TAGS.contains(&name.as_str()) {
+            if closing {
+                hidden_depth = hidden_depth.saturating_sub(1);
+            } else if !tag.ends_with('/') {
+                hidden_depth += 1;
+            }
+            continue;
+        }
+        if hidden_depth > 0 {
+            continue;
+        }
+
+        match (name.as_str(), closing) {
+            ("br", _) => out.push('\n'),
+            ("pre", false) => {
+                new_line(&mut out);
+                pre_depth += 1;
+            }
+            ("pre", true) => {
+                pre_depth = pre_depth.saturating_sub(1);
+                new_line(&mut out);
+            }
+            ("li", false) => {
+                new_line(&mut out);
+                out.push_str("* ");
+            }
+            ("td" | "th", false) if !out.is_empty() && !out.ends_with('\n') => out.push('\t'),
+            ("a", false) => open_link = attribute(tag, "href"),
+            ("a", true) => {
+                // Links whose text already is the URL don't need a footnote
+                if let Some(href) = open_link.take()
+                    && !href.starts_with('#')
+                    && !out.trim_end().ends_with(href.as_str())
+                {
+                    links.push(href);
+                    out.push_str(&format!("[{}]", links.len()));
+                }
+            }
+            ("img", _) => {
+                if let Some(alt) = attribute(tag, "alt").filter(|a| !a.trim().is_empty()) {
+                    push_text(&mut out, &alt, false, false);
+                }
+            }
+            (name, _) if PARAGRAPH_TAGS.contains(&name) => {
+                new_line(&mut out);
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+            }
+            (name, _) if BLOCK_TAGS.contains(&name) => new_line(&mut out),
+            _ => {}
+        }
+    }
+
+    let mut text = tidy(&out);
+    if !links.is_empty() {
+        text.push_str("\n\n");
+        for (i, href) in links.iter().enumerate() {
+            text.push_str(&format!("[{}] {}\n", i + 1, href));
+        }
+        text.pop();
+    }
+    text
+}
+
+/// Append decoded text, collapsing whitespace unless inside `<pre>`
+fn push_text(out: &mut String, raw: &str, pre: bool, hidden: bool) {
+    if hidden || raw.is_empty() {
+        return;
+    }
+    let text = decode_entities(raw);
+    if pre {
+        out.push_str(&text);
+        return;
+    }
+    for c in text.chars() {
+        if c.is_whitespace() && c != '\u{a0}' {
+            if !out.is_empty() && !out.ends_with([' ', '\n', '\t']) {
+                out.push(' ');
+            }
+        } else {
+            out.push(if c == '\u{a0}' { ' ' } else { c });
+        }
+    }
+}
+
+/// End the current line unless it is already empty
+fn new_line(out: &mut String) {
+    while out.ends_with(' ') {
+        out.pop();
+    }
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Trim trailing space from each line and keep at most one blank line between paragraphs
+fn tidy(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// Value of attribute `name` in a tag's source, with entities decoded
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find(name) {
+        let start = from + pos;
+        from = start + name.len();
+        // Must be a whole attribute name followed by '='
+        if !lower[..start].ends_with(|c: char| c.is_whitespace()) {
+            continue;
+        }
+        let after = lower[from..].trim_start();
+        if !after.starts_with('=') {
+            continue;
+        }
+        let value_start = tag.len() - after.len() + 1;
+        let value = tag[value_start..].trim_start();
+        let raw = match value.chars().next()? {
+            q @ ('"' | '\'') => value[1..].split(q).next()?,
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '>')
+                .next()?,
+        };
+        return Some(decode_entities(raw));
+    }
+    None
+}
+
+/// Decode the named entities common in mail bodies and all numeric ones
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        // Entities are short, so only look a few bytes ahead for the ';'
+        let end = rest.bytes().take(11).position(|b| b == b';');
+        let decoded = end.and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                "ndash" => Some('–'),
+                "mdash" => Some('—'),
+                "hellip" => Some('…'),
+                "lsquo" => Some('‘'),
+                "rsquo" => Some('’'),
+                "ldquo" => Some('“'),
+                "rdquo" => Some('”'),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|n| n.parse().ok()))
+                    .and_then(char::from_u32),
+            }?;
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entities() {
+        assert_eq!(
+            decode_entities("Tom &amp; Jerry &lt;3 &#233;&#xE9; &hellip;"),
+            "Tom & Jerry <3 éé …"
+        );
+        assert_eq!(
+            decode_entities("AT&T; &bogus; & done"),
+            "AT&T; &bogus; & done"
+        );
+        // A lone '&' far from any ';' is left alone
+        let long = format!("a & b{};", "x".repeat(1000));
+        assert_eq!(decode_entities(&long), long);
+    }
+
+    #[test]
+    fn links_become_footnotes() {
+        let text = to_text(
+            r#"<p>See <a href="https://example.com/a?x=1&amp;y=2">the docs</a> or
+            <a href="https://example.com">https://example.com</a>.</p>"#,
+        );
+        assert_eq!(
+            text,
+            "See the docs[1] or https://example.com.\n\n[1] https://example.com/a?x=1&y=2"
+        );
+    }
+
+    #[test]
+    fn lists() {
+        let text = to_text("<p>Items:</p><ul><li>One</li><li>Two <b>bold</b></li></ul>");
+        assert_eq!(text, "Items:\n\n* One\n* Two bold");
+    }
+
+    #[test]
+    fn nested_blocks() {
+        let text = to_text(
+            "<div><div><p>First</p></div><blockquote><div>Quoted<br>line</div></blockquote>\
+             <style>p { color: red }</style><div>Last</div></div>",
+        );
+        assert_eq!(text, "First\n\nQuoted\nline\n\nLast");
+    }
+}
@@ -1,67 +1,245 @@
-use crate::browser::{connect_or_start_browser, find_outlook_page};
+use crate::browser::{connect_or_start_browser, evaluate, evaluate_with_args, find_outlook_page};
+use crate::config::Config;
+use crate::settings::Settings;
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-pub async fn inspect_dom(port: u16) -> Result<String> {
-    let browser = connect_or_start_browser(port).await?;
-    let page = find_outlook_page(&browser).await?;
-
-    let script = r#"
-        (() => {
-            const info = {};
-
-            // Find message reading pane
-            const readingPane = document.querySelector('[aria-label*="Message body"], [class*="ReadingPane"], [class*="readingPane"], div[role="document"]');
-            if (readingPane) {
-                info.readingPane = {
-                    tag: readingPane.tagName,
-                    classes: readingPane.className,
-                    ariaLabel: readingPane.getAttribute('aria-label'),
-                    textPreview: readingPane.innerText?.substring(0, 200)
-                };
+/// Raw markup of the reading pane and the first few list items
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RawDom {
+    #[serde(rename = "readingPane")]
+    pub reading_pane: Option<String>,
+    #[serde(rename = "listItems")]
+    pub list_items: Vec<String>,
+}
+
+/// Elements `inspect_dom` and the reading pane fallback treat as a possible subject
+const SUBJECT_PROBE: &str = r#"h1, h2, [role="heading"], [class*="subject"], [class*="Subject"]"#;
+
+/// Elements `inspect_dom` and the reading pane fallback treat as a possible message body
+const BODY_PROBE: &str = r#"[aria-label*="Message body"], [class*="UniqueMessageBody"], [class*="messageBody"], [class*="ItemContent"], div[role="document"], div[dir="ltr"]"#;
+
+/// Read the open message with the loose probes `inspect_dom` reports on:
+/// the first heading for the subject and the largest body-like block for the body
+/// Used by `get_message` for whichever field the configured selectors miss.
+pub fn reading_pane_fallback_js() -> Result<String> {
+    Ok(format!(
+        r#"
+    (() => {{
+        const root = document.querySelector('[role="main"]') || document.body;
+        const inList = el => el.closest({item}) || el.closest('[role="listbox"], [role="navigation"]');
+
+        let subject = null;
+        for (const el of root.querySelectorAll({subject_probe})) {{
+            const text = el.getAttribute('title') || el.textContent?.trim();
+            if (text && !inList(el)) {{
+                subject = text;
+                break;
+            }}
+        }}
+
+        let bodyEl = null;
+        let longest = 0;
+        root.querySelectorAll({body_probe}).forEach(el => {{
+            if (inList(el)) return;
+            const length = el.innerText?.trim().length || 0;
+            if (length > longest) {{
+                longest = length;
+                bodyEl = el;
+            }}
+        }});
+
+        return JSON.stringify({{
+            subject,
+            body: bodyEl?.innerText?.trim() || null,
+            bodyHtml: bodyEl?.innerHTML || null
+        }});
+    }})()
+"#,
+        item = crate::browser::message_item_js(),
+        subject_probe = serde_json::to_string(SUBJECT_PROBE)?,
+        body_probe = serde_json::to_string(BODY_PROBE)?
+    ))
+}
+
+/// Subject and body as found by `reading_pane_fallback_js`
+#[derive(Debug, Deserialize)]
+pub struct FallbackRead {
+    pub subject: Option<String>,
+    pub body: Option<String>,
+    #[serde(rename = "bodyHtml")]
+    pub body_html: Option<String>,
+}
+
+/// Number of message list items included in a raw dump
+const RAW_LIST_ITEMS: usize = 3;
+
+/// Number of elements described when probing a custom selector
+const SELECTOR_MATCHES: usize = 20;
+
+/// Describe the elements Outlook's selectors are built from
+/// With `selector`, describe the elements matching it instead of the built-in heuristics.
+pub async fn inspect_dom(config: &Config, selector: Option<&str>) -> Result<String> {
+    let settings = Arc::new(Settings::new(config));
+    crate::settings::scope(settings, async {
+        let browser = connect_or_start_browser(config).await?;
+        let page = find_outlook_page(&browser, config).await?;
+
+        if let Some(selector) = selector {
+            let script = format!(
+                r#"
+                (() => {{
+                    let matches;
+                    try {{
+                        matches = Array.from(document.querySelectorAll({selector}));
+                    }} catch (e) {{
+                        return JSON.stringify({{ error: e.message }});
+                    }}
+                    return JSON.stringify({{
+                        selector: {selector},
+                        count: matches.length,
+                        elements: matches.slice(0, {limit}).map(el => ({{
+                            tag: el.tagName,
+                            classes: el.className,
+                            id: el.id || null,
+                            role: el.getAttribute('role'),
+                            ariaLabel: el.getAttribute('aria-label'),
+                            title: el.getAttribute('title'),
+                            text: el.textContent?.trim()?.substring(0, 100)
+                        }}))
+                    }}, null, 2);
+                }})()
+            "#,
+                selector = serde_json::to_string(selector)?,
+                limit = SELECTOR_MATCHES
+            );
+            let result = evaluate(&page, "inspect_dom:selector", script).await?;
+            let info = result.into_value::<String>().unwrap_or_default();
+            if let Some(error) = serde_json::from_str::<serde_json::Value>(&info)?
+                .get("error")
+                .and_then(|e| e.as_str())
+            {
+                anyhow::bail!("Invalid selector {:?}: {}", selector, error);
             }
+            return Ok(info);
+        }
 
-            // Find subject in reading pane
-            const subjects = document.querySelectorAll('h1, h2, [role="heading"], [class*="subject"], [class*="Subject"]');
-            info.subjects = Array.from(subjects).slice(0, 5).map(el => ({
-                tag: el.tagName,
-                classes: el.className,
-                text: el.textContent?.trim()?.substring(0, 100)
-            }));
-
-            // Find sender info
-            const senders = document.querySelectorAll('[class*="sender"], [class*="Sender"], [class*="From"], [class*="from"]');
-            info.senders = Array.from(senders).slice(0, 5).map(el => ({
-                tag: el.tagName,
-                classes: el.className,
-                text: el.textContent?.trim()?.substring(0, 100)
-            }));
-
-            // Find message body candidates
-            const bodies = document.querySelectorAll('[class*="UniqueMessageBody"], [class*="messageBody"], [class*="ItemContent"], div[dir="ltr"]');
-            info.bodies = Array.from(bodies).slice(0, 3).map(el => ({
-                tag: el.tagName,
-                classes: el.className,
-                textPreview: el.innerText?.substring(0, 200)
-            }));
-
-            // Find message list items
-            const listItems = document.querySelectorAll('[data-convid], [role="option"], [class*="listItem"]');
-            info.listItemCount = listItems.length;
-            if (listItems.length > 0) {
-                const first = listItems[0];
-                info.firstListItem = {
-                    tag: first.tagName,
-                    classes: first.className,
-                    dataConvid: first.getAttribute('data-convid'),
-                    html: first.outerHTML?.substring(0, 500)
-                };
+        let script = r#"
+            (subjectProbe, bodyProbe, item) => {
+                const info = {};
+
+                // Find message reading pane
+                const readingPane = document.querySelector('[aria-label*="Message body"], [class*="ReadingPane"], [class*="readingPane"], div[role="document"]');
+                if (readingPane) {
+                    info.readingPane = {
+                        tag: readingPane.tagName,
+                        classes: readingPane.className,
+                        ariaLabel: readingPane.getAttribute('aria-label'),
+                        textPreview: readingPane.innerText?.substring(0, 200)
+                    };
+                }
+
+                // Find subject in reading pane
+                const subjects = document.querySelectorAll(subjectProbe);
+                info.subjects = Array.from(subjects).slice(0, 5).map(el => ({
+                    tag: el.tagName,
+                    classes: el.className,
+                    text: el.textContent?.trim()?.substring(0, 100)
+                }));
+
+                // Find sender info
+                const senders = document.querySelectorAll('[class*="sender"], [class*="Sender"], [class*="From"], [class*="from"]');
+                info.senders = Array.from(senders).slice(0, 5).map(el => ({
+                    tag: el.tagName,
+                    classes: el.className,
+                    text: el.textContent?.trim()?.substring(0, 100)
+                }));
+
+                // Find message body candidates
+                const bodies = document.querySelectorAll(bodyProbe);
+                info.bodies = Array.from(bodies).slice(0, 3).map(el => ({
+                    tag: el.tagName,
+                    classes: el.className,
+                    textPreview: el.innerText?.substring(0, 200)
+                }));
+
+                // Find message list items
+                const listItems = document.querySelectorAll(item + ', [role="option"], [class*="listItem"]');
+                info.listItemCount = listItems.length;
+                if (listItems.length > 0) {
+                    const first = listItems[0];
+                    info.firstListItem = {
+                        tag: first.tagName,
+                        classes: first.className,
+                        dataConvid: first.getAttribute('data-convid'),
+                        html: first.outerHTML?.substring(0, 500)
+                    };
+                }
+
+                return JSON.stringify(info, null, 2);
             }
+        "#;
+
+        let args = [
+            SUBJECT_PROBE.into(),
+            BODY_PROBE.into(),
+            config.selectors.message_item().into(),
+        ];
+        let result = evaluate_with_args(&page, "inspect_dom", script, &args).await?;
+        let info = result.into_value::<String>().unwrap_or_default();
+        Ok(info)
+    })
+    .await
+}
+
+/// Put each tag on its own line so the markup can be read and diffed
+fn pretty_html(html: &str) -> String {
+    html.replace("><", ">\n<")
+}
+
+/// Cut `text` to at most `max_len` characters, noting how much was dropped
+fn truncate_html(text: &str, max_len: usize) -> String {
+    let len = text.chars().count();
+    if len <= max_len {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_len).collect();
+    format!("{}\n... ({} more characters)", kept, len - max_len)
+}
+
+/// Dump the reading pane's outerHTML and the first few list items, for when the
+/// curated heuristics in `inspect_dom` stop finding things after a DOM change
+/// Each dump is pretty-printed one tag per line and cut to `max_len` characters.
+pub async fn inspect_raw(config: &Config, max_len: usize) -> Result<RawDom> {
+    let settings = Arc::new(Settings::new(config));
+    crate::settings::scope(settings, async {
+        let browser = connect_or_start_browser(config).await?;
+        let page = find_outlook_page(&browser, config).await?;
 
-            return JSON.stringify(info, null, 2);
-        })()
-    "#;
+        let script = format!(
+            r#"
+        (() => {{
+            const readingPane = document.querySelector({reading_pane});
+            const items = Array.from(document.querySelectorAll({item})).slice(0, {items});
+            return JSON.stringify({{
+                readingPane: readingPane?.outerHTML ?? null,
+                listItems: items.map(el => el.outerHTML),
+            }});
+        }})()
+    "#,
+            reading_pane = serde_json::to_string(config.selectors.reading_pane())?,
+            item = serde_json::to_string(config.selectors.message_item())?,
+            items = RAW_LIST_ITEMS
+        );
 
-    let result = page.evaluate(script).await?;
-    let info = result.into_value::<String>().unwrap_or_default();
-    Ok(info)
+        let result = evaluate(&page, "inspect_raw", script).await?;
+        let raw = result.into_value::<String>().unwrap_or_default();
+        let mut dom: RawDom = serde_json::from_str(&raw)?;
+        let tidy = |html: &str| truncate_html(&pretty_html(html), max_len);
+        dom.reading_pane = dom.reading_pane.as_deref().map(tidy);
+        dom.list_items = dom.list_items.iter().map(|html| tidy(html)).collect();
+        Ok(dom)
+    })
+    .await
 }
@@ -1,6 +1,21 @@
 pub mod api;
 pub mod browser;
+pub mod cache;
+pub mod completions;
+pub mod compose;
 pub mod config;
+pub mod doctor;
+pub mod error;
+pub mod html;
 pub mod inspect;
 pub mod list;
+pub mod log;
 pub mod menu;
+pub mod metrics;
+pub mod output;
+pub mod progress;
+pub mod retry;
+pub mod settings;
+pub mod stats;
+pub mod strings;
+pub mod template;
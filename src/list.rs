@@ -1,8 +1,14 @@
-use crate::api::Message;
-use crate::browser::{connect_or_start_browser, find_outlook_page, navigate_to_inbox};
+use crate::api::{MailFolder, Message};
+use crate::browser::{
+    Folder, connect_or_start_browser, ensure_signed_in, evaluate, evaluate_json, find_outlook_page,
+    navigate_to_inbox,
+};
+use crate::config::Config;
+use crate::error::Error;
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 
-/// JavaScript function to extract labels from an element
+/// JavaScript functions to extract labels and their colors from an element
 const EXTRACT_LABELS_JS: &str = r#"
     function extractLabels(el) {
         const labels = [];
@@ -21,8 +27,167 @@ const EXTRACT_LABELS_JS: &str = r#"
         }
         return labels;
     }
+
+    // Map each label to its swatch color, read from the category tag's background
+    function extractLabelColors(el) {
+        const colors = {};
+        const isColor = c => c && c !== 'transparent' && c !== 'rgba(0, 0, 0, 0)';
+        el.querySelectorAll('[title^="Search for all messages with the category "]').forEach(tag => {
+            const label = tag.getAttribute('title').replace('Search for all messages with the category ', '');
+            const swatch = tag.querySelector('[style*="background"]') || tag;
+            const color = swatch.style.backgroundColor || getComputedStyle(swatch).backgroundColor;
+            if (label && isColor(color)) colors[label] = color;
+        });
+        el.querySelectorAll('button[aria-label^="Remove "]').forEach(btn => {
+            const label = btn.getAttribute('aria-label').replace('Remove ', '');
+            const tag = btn.parentElement;
+            const color = tag && getComputedStyle(tag).backgroundColor;
+            if (label && !colors[label] && isColor(color)) colors[label] = color;
+        });
+        return colors;
+    }
 "#;
 
+/// Cache file holding recently listed messages, used to re-find stale IDs
+const SEEN_CACHE: &str = "seen-messages";
+const SEEN_CACHE_LIMIT: usize = 1000;
+
+/// Identifying details of a listed message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SeenMessage {
+    immutable_id: Option<String>,
+    subject: Option<String>,
+    from: Option<String>,
+    #[serde(default)]
+    date: Option<String>,
+    /// Increases with each listing, so the oldest entries can be dropped first
+    listing: u64,
+}
+
+/// Listed messages by ID
+type SeenMessages = std::collections::HashMap<String, SeenMessage>;
+
+/// Record the result of a listing command so its IDs can be resolved later
+/// Called once per command rather than for every extraction, which also runs while
+/// resolving IDs and checking results.
+fn remember_messages(messages: &[Message]) {
+    let mut seen: SeenMessages = crate::cache::load(crate::cache::Location::Cache, SEEN_CACHE);
+    let listing = seen.values().map(|s| s.listing).max().unwrap_or(0) + 1;
+    for m in messages {
+        seen.insert(
+            m.id.clone(),
+            SeenMessage {
+                immutable_id: m.immutable_id.clone(),
+                subject: m.subject.clone(),
+                from: m.from.clone(),
+                date: m.date.clone(),
+                listing,
+            },
+        );
+    }
+    if seen.len() > SEEN_CACHE_LIMIT {
+        let mut listings: Vec<u64> = seen.values().map(|s| s.listing).collect();
+        listings.sort_unstable();
+        let cutoff = listings[seen.len() - SEEN_CACHE_LIMIT];
+        seen.retain(|_, s| s.listing >= cutoff);
+    }
+    if let Err(e) = crate::cache::save(crate::cache::Location::Cache, SEEN_CACHE, &seen) {
+        crate::debug!("failed to save message cache: {:#}", e);
+    }
+}
+
+/// Listed messages that could be the remembered `known` one
+/// A matching immutable ID settles it; otherwise subject and sender must match, and the
+/// date too when both sides have one.
+fn stale_matches<'a>(known: &SeenMessage, current: &'a [Message]) -> Vec<&'a Message> {
+    if known.immutable_id.is_some() {
+        let by_id: Vec<&Message> = current
+            .iter()
+            .filter(|m| m.immutable_id == known.immutable_id)
+            .collect();
+        if !by_id.is_empty() {
+            return by_id;
+        }
+    }
+    if known.subject.is_none() {
+        return Vec::new();
+    }
+    current
+        .iter()
+        .filter(|m| m.subject == known.subject && m.from == known.from)
+        .filter(|m| match (&m.date, &known.date) {
+            (Some(date), Some(known)) => date == known,
+            _ => true,
+        })
+        .collect()
+}
+
+/// Map a possibly stale ID (convid or immutable ID) to the convid currently in the list
+/// Falls back to the message from the last listing with the same subject, sender and date.
+/// Returns `id` unchanged when it was never listed, and errors when the remembered
+/// message is gone or several listed messages fit it.
+pub async fn resolve_message(page: &chromiumoxide::Page, id: &str) -> Result<String> {
+    let script = format!(
+        r#"
+        (() => {{
+            const id = {id};
+            for (const item of document.querySelectorAll({item})) {{
+                if (item.getAttribute('data-convid') === id) return id;
+            }}
+            for (const item of document.querySelectorAll({item})) {{
+                const itemIdEl = item.hasAttribute('data-item-id') ? item : item.querySelector('[data-item-id]');
+                if (itemIdEl?.getAttribute('data-item-id') === id) return item.getAttribute('data-convid');
+            }}
+            return null;
+        }})()
+    "#,
+        id = serde_json::to_string(id)?,
+        item = crate::browser::message_item_js()
+    );
+
+    let result = evaluate(page, "resolve_message", script).await?;
+    if let Some(found) = result.into_value::<Option<String>>().unwrap_or(None) {
+        return Ok(found);
+    }
+
+    let seen: SeenMessages = crate::cache::load(crate::cache::Location::Cache, SEEN_CACHE);
+    let Some(known) = seen.get(id).or_else(|| {
+        seen.values()
+            .find(|s| s.immutable_id.as_deref() == Some(id))
+    }) else {
+        return Ok(id.to_string());
+    };
+
+    let current = extract_message_list(page, u32::MAX).await?;
+    match stale_matches(known, &current)[..] {
+        [m] => {
+            crate::debug!("resolved stale id {} to {}", id, m.id);
+            Ok(m.id.clone())
+        }
+        [] => Err(Error::MessageNotFound { id: id.to_string() }.into()),
+        ref several => anyhow::bail!(
+            "Message {} is no longer listed and {} listed messages share its subject and sender; list again for a current ID",
+            id,
+            several.len()
+        ),
+    }
+}
+
+/// Current state of one message in the list, or None when it isn't listed
+pub async fn find_listed(page: &chromiumoxide::Page, id: &str) -> Result<Option<Message>> {
+    let messages = extract_message_list(page, u32::MAX).await?;
+    Ok(messages.into_iter().find(|m| m.id == id))
+}
+
+/// Messages in the current view that pass `filter`, in list order
+pub async fn find_matching(
+    page: &chromiumoxide::Page,
+    filter: &crate::api::ListFilter,
+) -> Result<Vec<Message>> {
+    let messages = extract_message_list(page, u32::MAX).await?;
+    Ok(messages.into_iter().filter(|m| m.matches(filter)).collect())
+}
+
 /// Extract messages from the current page view
 async fn extract_message_list(page: &chromiumoxide::Page, max: u32) -> Result<Vec<Message>> {
     let script = format!(
@@ -30,11 +195,14 @@ async fn extract_message_list(page: &chromiumoxide::Page, max: u32) -> Result<Ve
         (() => {{
             {extract_labels}
             const messages = [];
-            const items = document.querySelectorAll('[data-convid]');
+            const items = document.querySelectorAll({item});
             items.forEach(item => {{
                 const id = item.getAttribute('data-convid');
+                const itemIdEl = item.hasAttribute('data-item-id') ? item : item.querySelector('[data-item-id]');
+                const immutableId = itemIdEl?.getAttribute('data-item-id') || null;
                 const ariaLabel = item.getAttribute('aria-label') || '';
                 const labels = extractLabels(item);
+                const labelColors = extractLabelColors(item);
 
                 // Extract from DOM elements using stable patterns
                 let from = '';
@@ -49,7 +217,23 @@ async fn extract_message_list(page: &chromiumoxide::Page, max: u32) -> Result<Ve
 
                 // Subject and preview: find text spans that aren't the sender
                 const allSpans = item.querySelectorAll('span[title]');
+
+                // Date: the time/date span carries the full timestamp in its title
+                let date = '';
+                let dateSpan = null;
                 for (const span of allSpans) {{
+                    const text = span.textContent?.trim() || '';
+                    if (/^\d{{1,2}}:\d{{2}}$/.test(text) ||
+                        /^\w{{3}} \d{{1,2}}[\/.-]\d{{1,2}}$/.test(text) ||
+                        /^\d{{1,4}}[\/.-]\d{{1,2}}[\/.-]\d{{1,4}}$/.test(text)) {{
+                        date = span.getAttribute('title') || text;
+                        dateSpan = span;
+                        break;
+                    }}
+                }}
+
+                for (const span of allSpans) {{
+                    if (span === dateSpan) continue;
                     const title = span.getAttribute('title') || '';
                     const text = span.textContent?.trim() || '';
                     // Skip sender (has @ in title) and empty spans
@@ -91,69 +275,686 @@ async fn extract_message_list(page: &chromiumoxide::Page, max: u32) -> Result<Ve
                 // Check for Unread marker
                 const isUnread = ariaLabel.toLowerCase().includes('unread');
 
+                // Pinned items carry a pin icon or mention it in the aria-label
+                const isPinned = ariaLabel.toLowerCase().includes('pinned') ||
+                    item.querySelector('[data-icon-name*="Pin"], [aria-label="Pinned"], [title="Pinned"]') !== null;
+
+                // Flagged items offer "Unflag" instead of "Flag"
+                const isFlagged = ariaLabel.toLowerCase().includes('flagged') ||
+                    item.querySelector('[data-icon-name="FlagFilled"], [title^="Unflag"], [aria-label^="Unflag"]') !== null;
+
+                // Conversation rows show a thread-count badge like "(3)" and say so in the aria-label
+                let messageCount = null;
+                const countMatch = ariaLabel.match(/(\d+)\s+(messages|items)\b/i);
+                if (countMatch) {{
+                    messageCount = parseInt(countMatch[1], 10);
+                }} else {{
+                    for (const span of textSpans) {{
+                        const badge = span.textContent?.trim().match(/^\((\d+)\)$/);
+                        if (badge) {{
+                            messageCount = parseInt(badge[1], 10);
+                            break;
+                        }}
+                    }}
+                }}
+                if (messageCount !== null && messageCount < 2) messageCount = null;
+
                 if (id) {{
-                    messages.push({{ id, subject, from, preview, labels, isUnread }});
+                    messages.push({{ id, immutableId, subject, from, date, preview, labels, labelColors, isUnread, isPinned, isFlagged, messageCount }});
                 }}
             }});
             return JSON.stringify(messages);
         }})()
     "#,
-        extract_labels = EXTRACT_LABELS_JS
+        extract_labels = EXTRACT_LABELS_JS,
+        item = crate::browser::message_item_js()
     );
 
-    let result = page.evaluate(script).await?;
-    let messages_str = result.into_value::<String>().unwrap_or_default();
-    let mut parsed: Vec<Message> = serde_json::from_str(&messages_str).unwrap_or_default();
+    let mut parsed: Vec<Message> = evaluate_json(page, "extract_message_list", &script).await?;
     parsed.truncate(max as usize);
     Ok(parsed)
 }
 
-pub async fn list_messages(port: u16, max: u32) -> Result<Vec<Message>> {
-    let browser = connect_or_start_browser(port).await?;
-    let page = find_outlook_page(&browser).await?;
+/// Marker error for a list that hasn't rendered yet
+#[derive(Debug)]
+struct EmptyList;
+
+impl std::fmt::Display for EmptyList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "message list is empty")
+    }
+}
+
+impl std::error::Error for EmptyList {}
+
+/// Receives each batch of messages as a listing collects them (see `list_messages_each`)
+pub type Sink<'a> = &'a mut (dyn FnMut(&[Message]) -> Result<()> + Send);
+
+/// Extract messages, retrying while the list is still empty right after navigation
+/// Each message is tagged with `folder`, the folder being shown.
+async fn extract_message_list_with_retry(
+    page: &chromiumoxide::Page,
+    folder: Folder,
+    max: u32,
+) -> Result<Vec<Message>> {
+    extract_message_list_into(page, folder, max, &mut |_| Ok(())).await
+}
+
+/// `extract_message_list_with_retry`, handing each batch to `sink` as it is collected
+async fn extract_message_list_into(
+    page: &chromiumoxide::Page,
+    folder: Folder,
+    max: u32,
+    sink: Sink<'_>,
+) -> Result<Vec<Message>> {
+    let result = crate::retry::with_retry(|| async {
+        let messages = extract_message_list(page, max).await?;
+        if messages.is_empty() {
+            return Err(EmptyList.into());
+        }
+        Ok(messages)
+    })
+    .await;
+
+    let mut messages = match result {
+        Err(e) if e.is::<EmptyList>() => Vec::new(),
+        other => other?,
+    };
+    tag_folder(&mut messages, folder);
+    sink(&messages)?;
+    if !messages.is_empty() && (messages.len() as u32) < max && max != u32::MAX {
+        messages = scroll_for_more(page, messages, folder, max, sink).await?;
+    }
+    Ok(messages)
+}
+
+fn tag_folder(messages: &mut [Message], folder: Folder) {
+    for msg in messages {
+        msg.folder = Some(folder.display_name().to_string());
+    }
+}
+
+/// How long to wait for Outlook to load the next page after a scroll
+const SCROLL_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// JavaScript function finding the scrollable element that holds the message list
+fn find_list_scroller_js() -> String {
+    format!(
+        r#"
+    function findListScroller() {{
+        let el = document.querySelector({item})?.parentElement;
+        while (el && !(el.scrollHeight > el.clientHeight &&
+                       /auto|scroll/.test(getComputedStyle(el).overflowY))) {{
+            el = el.parentElement;
+        }}
+        return el;
+    }}
+"#,
+        item = crate::browser::message_item_js()
+    )
+}
+
+/// Scroll the message list to the bottom so Outlook loads the next page
+/// Returns false when the list is already at the bottom or isn't scrollable.
+async fn scroll_message_list(page: &chromiumoxide::Page) -> Result<bool> {
+    let script = format!(
+        r#"
+        (() => {{
+            {find_scroller}
+            const el = findListScroller();
+            if (!el) return false;
+            const before = el.scrollTop;
+            el.scrollTop = el.scrollHeight;
+            return el.scrollTop > before;
+        }})()
+    "#,
+        find_scroller = find_list_scroller_js()
+    );
+    let result = evaluate(page, "scroll_message_list", script).await?;
+    Ok(result.into_value::<bool>().unwrap_or(false))
+}
+
+/// Bring a message row back into the page, scrolling the list from the top if Outlook's
+/// virtualized list has dropped it after an earlier scroll
+/// Returns false when the row can't be found anywhere in the list.
+pub async fn scroll_into_view(page: &chromiumoxide::Page, id: &str) -> Result<bool> {
+    // "found" when the row is on the page, otherwise whether the list moved
+    let step_script = |from_top: bool| -> Result<String> {
+        Ok(format!(
+            r#"
+            (() => {{
+                {find_scroller}
+                const row = document.querySelector({selector});
+                if (row) {{
+                    row.scrollIntoView({{ block: 'nearest' }});
+                    return 'found';
+                }}
+                const el = findListScroller();
+                if (!el) return 'stuck';
+                const before = el.scrollTop;
+                el.scrollTop = {from_top} ? 0 : before + el.clientHeight;
+                return el.scrollTop !== before ? 'moved' : 'stuck';
+            }})()
+        "#,
+            find_scroller = find_list_scroller_js(),
+            selector = serde_json::to_string(&crate::browser::message_selector(id))?,
+            from_top = from_top
+        ))
+    };
+
+    // Back to the top first, then down a screen at a time
+    let limit = crate::settings::current().max_scroll.max(1) * 4;
+    for step in 0..=limit {
+        let result = evaluate(page, "scroll_into_view", step_script(step == 0)?).await?;
+        match result.into_value::<String>().unwrap_or_default().as_str() {
+            "found" => return Ok(true),
+            "moved" => {
+                crate::browser::wait_for_idle(page, SCROLL_IDLE_TIMEOUT).await?;
+            }
+            _ if step == 0 => {}
+            _ => return Ok(false),
+        }
+    }
+    Ok(false)
+}
+
+/// Keep scrolling the list until it holds `max` messages
+/// Each scroll's new messages go to `sink`, and a "Listing N/M" counter shows progress.
+/// Stops early when a scroll brings in nothing new or after `--max-scroll` scrolls, and
+/// says so on stderr, since the list is then shorter than asked for.
+async fn scroll_for_more(
+    page: &chromiumoxide::Page,
+    mut messages: Vec<Message>,
+    folder: Folder,
+    max: u32,
+    sink: Sink<'_>,
+) -> Result<Vec<Message>> {
+    let limit = crate::settings::current().max_scroll;
+    let mut seen: std::collections::HashSet<String> =
+        messages.iter().map(|m| m.id.clone()).collect();
+    let mut scrolls = 0;
+    let mut progress = crate::progress::Progress::new("Listing", max as usize);
+    progress.set(messages.len());
+
+    while (messages.len() as u32) < max {
+        if scrolls == limit {
+            crate::progress::notice(format_args!(
+                "Reached {} of requested {} messages after {} scrolls; raise --max-scroll to load more",
+                messages.len(),
+                max,
+                scrolls
+            ));
+            break;
+        }
+        if !scroll_message_list(page).await? {
+            break;
+        }
+        scrolls += 1;
+        crate::browser::wait_for_idle(page, SCROLL_IDLE_TIMEOUT).await?;
+
+        // Outlook virtualizes the list, so earlier rows may have been dropped from the page
+        let mut more: Vec<Message> = extract_message_list(page, u32::MAX)
+            .await?
+            .into_iter()
+            .filter(|m| seen.insert(m.id.clone()))
+            .collect();
+        more.truncate(max as usize - messages.len());
+        crate::debug!("scroll {}: {} new messages", scrolls, more.len());
+        if more.is_empty() {
+            crate::progress::notice(format_args!(
+                "Reached {} of requested {} messages; scrolling loaded no more",
+                messages.len(),
+                max
+            ));
+            break;
+        }
+        tag_folder(&mut more, folder);
+        sink(&more)?;
+        messages.extend(more);
+        progress.set(messages.len());
+    }
+
+    Ok(messages)
+}
+
+/// Which side of the Focused Inbox split to list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum InboxView {
+    Focused,
+    Other,
+    /// Focused and Other merged
+    All,
+}
+
+/// Click the "Focused" or "Other" pivot above the inbox list
+/// Returns false when the pivots aren't shown (Focused Inbox is turned off)
+pub async fn select_inbox_pivot(page: &chromiumoxide::Page, name: &str) -> Result<bool> {
+    let script = format!(
+        r#"
+        (() => {{
+            const name = {name};
+            const tabs = document.querySelectorAll('[role="tab"], button[name]');
+            for (const tab of tabs) {{
+                const text = (tab.getAttribute('name') || tab.textContent || '').trim().toLowerCase();
+                if (text !== name) continue;
+                if (tab.getAttribute('aria-selected') !== 'true') tab.click();
+                return true;
+            }}
+            return false;
+        }})()
+    "#,
+        name = serde_json::to_string(&name.to_lowercase())?
+    );
+
+    let result = evaluate(page, "select_inbox_pivot", script).await?;
+    let found = result.into_value::<bool>().unwrap_or(false);
+    crate::debug!("select_inbox_pivot {}: found={}", name, found);
+    if found {
+        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+    }
+    Ok(found)
+}
+
+/// List inbox messages from the Focused pivot, the Other pivot, or both
+/// `All` lists Focused then Other, dropping duplicates, and is cut to `max` overall.
+/// The inbox is left on Focused afterwards.
+pub async fn list_messages(config: &Config, max: u32, view: InboxView) -> Result<Vec<Message>> {
+    list_messages_each(config, max, view, &mut |_| Ok(())).await
+}
+
+/// `list_messages`, handing each batch to `sink` as it is collected, so output can start
+/// before a long scroll finishes
+/// Batches carry no duplicates and never add up to more than `max`.
+pub async fn list_messages_each(
+    config: &Config,
+    max: u32,
+    view: InboxView,
+    sink: Sink<'_>,
+) -> Result<Vec<Message>> {
+    let mut emitted = std::collections::HashSet::new();
+    let mut sink = |batch: &[Message]| {
+        let fresh: Vec<Message> = batch
+            .iter()
+            .filter(|m| emitted.len() < max as usize && emitted.insert(m.id.clone()))
+            .cloned()
+            .collect();
+        if fresh.is_empty() {
+            return Ok(());
+        }
+        sink(&fresh)
+    };
+    let sink: Sink<'_> = &mut sink;
+
+    let browser = connect_or_start_browser(config).await?;
+    ensure_signed_in(&browser, config).await?;
+    let page = find_outlook_page(&browser, config).await?;
     navigate_to_inbox(&page).await?;
-    extract_message_list(&page, max).await
+
+    let focused_inbox = select_inbox_pivot(&page, "focused").await?;
+    if !focused_inbox {
+        // Without the split, the one list already holds everything
+        if view == InboxView::Other {
+            anyhow::bail!("Focused Inbox is turned off; all messages are in the inbox list");
+        }
+        let messages = extract_message_list_into(&page, Folder::Inbox, max, sink).await?;
+        remember_messages(&messages);
+        return Ok(messages);
+    }
+
+    let mut messages = match view {
+        InboxView::Other => Vec::new(),
+        _ => extract_message_list_into(&page, Folder::Inbox, max, sink).await?,
+    };
+    if view != InboxView::Focused {
+        select_inbox_pivot(&page, "other").await?;
+        let other = extract_message_list_into(&page, Folder::Inbox, max, sink).await;
+        select_inbox_pivot(&page, "focused").await?;
+
+        let mut seen: std::collections::HashSet<String> =
+            messages.iter().map(|m| m.id.clone()).collect();
+        messages.extend(other?.into_iter().filter(|m| seen.insert(m.id.clone())));
+        messages.truncate(max as usize);
+    }
+    remember_messages(&messages);
+    Ok(messages)
 }
 
-pub async fn list_spam(port: u16, max: u32) -> Result<Vec<Message>> {
+pub async fn list_spam(config: &Config, max: u32) -> Result<Vec<Message>> {
     use crate::browser::navigate_to_junk;
 
-    let browser = connect_or_start_browser(port).await?;
-    let page = find_outlook_page(&browser).await?;
+    let browser = connect_or_start_browser(config).await?;
+    ensure_signed_in(&browser, config).await?;
+    let page = find_outlook_page(&browser, config).await?;
     navigate_to_junk(&page).await?;
-    extract_message_list(&page, max).await
+    let messages = extract_message_list_with_retry(&page, Folder::Junk, max).await?;
+    remember_messages(&messages);
+    Ok(messages)
+}
+
+/// Show `folder` and extract its messages, waiting for the list to render
+pub async fn extract_folder(
+    page: &chromiumoxide::Page,
+    folder: Folder,
+    max: u32,
+) -> Result<Vec<Message>> {
+    crate::browser::navigate_to_folder(page, folder).await?;
+    extract_message_list_with_retry(page, folder, max).await
+}
+
+/// List the messages in a folder
+pub async fn list_folder(config: &Config, folder: Folder, max: u32) -> Result<Vec<Message>> {
+    let browser = connect_or_start_browser(config).await?;
+    ensure_signed_in(&browser, config).await?;
+    let page = find_outlook_page(&browser, config).await?;
+    let messages = extract_folder(&page, folder, max).await?;
+    remember_messages(&messages);
+    Ok(messages)
+}
+
+/// List unread messages in a folder
+/// Filters after extraction, so `max` applies to unread messages only
+pub async fn list_unread(config: &Config, folder: Folder, max: u32) -> Result<Vec<Message>> {
+    use crate::browser::navigate_to_folder;
+
+    let browser = connect_or_start_browser(config).await?;
+    ensure_signed_in(&browser, config).await?;
+    let page = find_outlook_page(&browser, config).await?;
+    navigate_to_folder(&page, folder).await?;
+
+    let mut messages = extract_message_list_with_retry(&page, folder, u32::MAX).await?;
+    messages.retain(|m| m.is_unread);
+    messages.truncate(max as usize);
+    remember_messages(&messages);
+    Ok(messages)
+}
+
+/// Pick an option from the message list's Filter menu
+async fn set_list_filter(page: &chromiumoxide::Page, option: &str) -> Result<()> {
+    use crate::browser::click_element;
+    use crate::menu::click_menu_item;
+
+    click_element(
+        page,
+        r#"button[aria-label^="Filter"], button[title^="Filter"]"#,
+        Some(500),
+    )
+    .await?;
+    click_menu_item(page, option, Some(1500)).await
+}
+
+/// List flagged (follow-up) messages in the inbox
+/// Uses Outlook's Flagged filter when available and always filters the result,
+/// so a filter that can't be applied just means scanning the whole inbox
+pub async fn list_flagged(config: &Config, max: u32) -> Result<Vec<Message>> {
+    let browser = connect_or_start_browser(config).await?;
+    ensure_signed_in(&browser, config).await?;
+    let page = find_outlook_page(&browser, config).await?;
+    navigate_to_inbox(&page).await?;
+
+    let filtered = match set_list_filter(&page, "flagged").await {
+        Ok(()) => true,
+        Err(e) => {
+            crate::debug!("list_flagged: filter unavailable, post-filtering: {}", e);
+            crate::menu::close_menus(&page).await?;
+            false
+        }
+    };
+
+    let result = extract_message_list_with_retry(&page, Folder::Inbox, u32::MAX).await;
+
+    // Leave the inbox unfiltered for other commands
+    if filtered && let Err(e) = set_list_filter(&page, "all").await {
+        crate::debug!("list_flagged: could not reset filter: {}", e);
+    }
+
+    let mut messages = result?;
+    messages.retain(|m| m.is_flagged);
+    messages.truncate(max as usize);
+    remember_messages(&messages);
+    Ok(messages)
+}
+
+/// Connect to the Outlook tab and show the inbox
+async fn open_inbox(
+    config: &Config,
+) -> Result<(std::sync::Arc<chromiumoxide::Browser>, chromiumoxide::Page)> {
+    let browser = connect_or_start_browser(config).await?;
+    ensure_signed_in(&browser, config).await?;
+    let page = find_outlook_page(&browser, config).await?;
+    navigate_to_inbox(&page).await?;
+    Ok((browser, page))
+}
+
+/// Poll the inbox every `interval` and call `on_new` for each message not seen before
+/// Messages present on the first poll are treated as already seen.
+/// The browser connection is kept across polls and re-established if the tab goes away.
+pub async fn watch_messages<F>(
+    config: &Config,
+    interval: std::time::Duration,
+    mut on_new: F,
+) -> Result<()>
+where
+    F: FnMut(&Message) -> Result<()>,
+{
+    let mut seen: Option<std::collections::HashSet<String>> = None;
+    let mut conn = Some(open_inbox(config).await?);
+
+    loop {
+        let (browser, page) = match conn.take() {
+            Some(c) => c,
+            None => match open_inbox(config).await {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("Reconnect failed, retrying: {:#}", e);
+                    tokio::time::sleep(interval).await;
+                    continue;
+                }
+            },
+        };
+
+        // The tab may have been navigated away from Outlook or closed
+        let still_outlook = tokio::time::timeout(std::time::Duration::from_secs(2), page.url())
+            .await
+            .ok()
+            .and_then(|r| r.ok())
+            .flatten()
+            .is_some_and(|u| config.is_outlook_url(&u));
+        let messages = if still_outlook && navigate_to_inbox(&page).await.is_ok() {
+            extract_message_list(&page, u32::MAX).await.ok()
+        } else {
+            None
+        };
+
+        match messages {
+            Some(mut messages) => {
+                let first_poll = seen.is_none();
+                let seen = seen.get_or_insert_with(Default::default);
+                for msg in &mut messages {
+                    msg.folder = Some(Folder::Inbox.display_name().to_string());
+                    if seen.insert(msg.id.clone()) && !first_poll {
+                        on_new(msg)?;
+                    }
+                }
+                conn = Some((browser, page));
+            }
+            None => eprintln!("Lost the Outlook tab, reconnecting..."),
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Read the unread badge shown next to a folder in the folder pane
+async fn folder_unread_badge(page: &chromiumoxide::Page, folder: Folder) -> Result<Option<usize>> {
+    let script = format!(
+        r#"
+        (() => {{
+            const name = {name};
+            const items = document.querySelectorAll('[role="treeitem"]');
+            for (const item of items) {{
+                const title = item.getAttribute('title') || item.textContent?.trim() || '';
+                if (!title.startsWith(name)) continue;
+                // The badge is a span holding only a number
+                const spans = Array.from(item.querySelectorAll('span'));
+                const badge = spans.reverse().find(s => /^\d+$/.test(s.textContent?.trim() || ''));
+                return badge ? parseInt(badge.textContent.trim(), 10) : 0;
+            }}
+            return null;
+        }})()
+    "#,
+        name = serde_json::to_string(folder.display_name())?
+    );
+
+    let result = evaluate(page, "folder_unread_badge", script).await?;
+    Ok(result.into_value::<Option<usize>>().unwrap_or(None))
+}
+
+/// Folders in the folder pane, in pane order, with subfolders after their parent
+/// Favorites repeat folders from the main tree, so that section is skipped, as are the
+/// account and group headings.
+pub async fn list_folders(config: &Config) -> Result<Vec<MailFolder>> {
+    let browser = connect_or_start_browser(config).await?;
+    ensure_signed_in(&browser, config).await?;
+    let page = find_outlook_page(&browser, config).await?;
+    folder_tree(&page).await
+}
+
+/// Read the folder pane of an open Outlook page (see `list_folders`)
+pub async fn folder_tree(page: &chromiumoxide::Page) -> Result<Vec<MailFolder>> {
+    let script = r#"
+        (() => {
+            const folders = [];
+            // Names of the enclosing folders, indexed by tree level
+            const ancestors = [];
+            let inFavorites = false;
+            document.querySelectorAll('[role="treeitem"]').forEach(item => {
+                // The name span carries a title; the badge next to it only holds a number
+                const named = item.querySelector('span[title]');
+                const name = (named?.getAttribute('title') || item.getAttribute('title')
+                    || (item.textContent || '').trim().replace(/\s*\d+$/, '')).trim();
+                if (!name) return;
+                const level = parseInt(item.getAttribute('aria-level') || '1', 10);
+
+                const lower = name.toLowerCase();
+                if (level === 1 && (lower === 'favorites' || lower === 'groups' || name.includes('@'))) {
+                    inFavorites = lower === 'favorites';
+                    ancestors.length = 0;
+                    return;
+                }
+                if (inFavorites) return;
+
+                ancestors.length = level;
+                ancestors[level] = name;
+                const parent = ancestors.slice(0, level).filter(Boolean).pop() || null;
+
+                // The accessible name spells out what the badge counts, e.g. "Drafts 3 items"
+                const described = (item.getAttribute('aria-label') || item.getAttribute('title') || '').toLowerCase();
+                const unreadMatch = described.match(/(\d+)\s+unread/);
+                const totalMatch = described.match(/(\d+)\s+items?/);
+                const badge = Array.from(item.querySelectorAll('span'))
+                    .reverse()
+                    .find(s => /^\d+$/.test(s.textContent?.trim() || ''));
+                const badgeCount = badge ? parseInt(badge.textContent.trim(), 10) : null;
+
+                const unread = unreadMatch ? parseInt(unreadMatch[1], 10) : (totalMatch ? null : badgeCount);
+                const total = totalMatch ? parseInt(totalMatch[1], 10) : null;
+                folders.push({ name, parent, unread, total });
+            });
+            return JSON.stringify(folders);
+        })()
+    "#;
+    evaluate_json(page, "folder_tree", script).await
+}
+
+/// Count messages in a folder, optionally only unread ones
+pub async fn count_messages(config: &Config, folder: Folder, unread_only: bool) -> Result<usize> {
+    use crate::browser::navigate_to_folder;
+
+    let browser = connect_or_start_browser(config).await?;
+    ensure_signed_in(&browser, config).await?;
+    let page = find_outlook_page(&browser, config).await?;
+
+    if unread_only && let Some(count) = folder_unread_badge(&page, folder).await? {
+        return Ok(count);
+    }
+
+    // Fall back to counting the rendered list items
+    navigate_to_folder(&page, folder).await?;
+    let messages = extract_message_list(&page, u32::MAX).await?;
+    Ok(messages
+        .iter()
+        .filter(|msg| !unread_only || msg.is_unread)
+        .count())
 }
 
-pub async fn get_message(port: u16, id: &str) -> Result<Message> {
+/// JavaScript function finding the reading pane element holding the open message's header
+/// Walks up from the body to the first ancestor that also holds the sender, so queries
+/// in it skip the message list and the rest of the page.
+const FIND_PANE_HEADER_JS: &str = r#"
+    function findPaneHeader(bodySelector, senderSelector) {
+        let el = document.querySelector(bodySelector)?.parentElement;
+        while (el && !el.querySelector(senderSelector)) {
+            el = el.parentElement;
+        }
+        return el || document.querySelector('[role="main"]') || document.body;
+    }
+"#;
+
+pub async fn get_message(config: &Config, id: &str) -> Result<Message> {
     use crate::browser::click_element;
 
-    let browser = connect_or_start_browser(port).await?;
-    let page = find_outlook_page(&browser).await?;
+    let browser = connect_or_start_browser(config).await?;
+    ensure_signed_in(&browser, config).await?;
+    let page = find_outlook_page(&browser, config).await?;
 
+    let id = &resolve_message(&page, id).await?;
     let selector = crate::browser::message_selector(id);
     click_element(&page, &selector, Some(2000)).await?;
 
+    // The header collapses long recipient lists behind a "+N" / show-all button
+    let expand_script = format!(
+        r#"
+        (() => {{
+            {find_header}
+            const header = findPaneHeader({body}, {sender});
+            const button = header.querySelector('button[aria-label*="recipients" i], button[aria-label*="show all" i]');
+            if (!button) return false;
+            button.click();
+            return true;
+        }})()
+    "#,
+        find_header = FIND_PANE_HEADER_JS,
+        body = serde_json::to_string(config.selectors.reading_pane())?,
+        sender = serde_json::to_string(config.selectors.sender())?
+    );
+    let result = evaluate(&page, "get_message:expand_recipients", expand_script).await?;
+    if result.into_value::<bool>().unwrap_or(false) {
+        tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+    }
+
     let read_script = format!(
         r#"
         (() => {{
             {extract_labels}
             const labels = extractLabels(document);
+            const labelColors = extractLabelColors(document);
 
             // Get subject - prefer title attribute for full text
             let subject = '';
-            const subjectEl = document.querySelector('.allowTextSelection, [class*="SubjectLine"], [class*="JdFsz"]');
+            const subjectEl = document.querySelector({subject});
             if (subjectEl) {{
                 subject = subjectEl.getAttribute('title') || subjectEl.textContent?.trim() || '';
             }}
 
             // From
             let from = '';
-            const senderEl = document.querySelector('[class*="Sender"], [class*="sender"], [class*="From"], button[class*="Persona"]');
+            const senderEl = document.querySelector({sender});
             if (senderEl) {{
                 from = senderEl.textContent?.trim();
             }}
             if (!from) {{
-                const selected = document.querySelector('[data-convid][aria-selected="true"]');
+                const selected = document.querySelector(':is(' + {item} + ')[aria-selected="true"]');
                 if (selected) {{
                     const label = selected.getAttribute('aria-label') || '';
                     const match = label.match(/^([^<]+?)(?:\s+(?:Re:|Fw:|New\s|Your\s|Microsoft|Amazon))/i);
@@ -161,22 +962,213 @@ pub async fn get_message(port: u16, id: &str) -> Result<Message> {
                 }}
             }}
 
+            // To/Cc: personas on the header row whose leading label reads "To:" or "Cc:"
+            {find_header}
+            const header = findPaneHeader({body}, {sender});
+            const recipients = (label) => {{
+                const names = [];
+                header.querySelectorAll('div, span, label').forEach(el => {{
+                    if (el.childElementCount !== 0) return;
+                    if (el.textContent?.trim().replace(/:$/, '').toLowerCase() !== label) return;
+                    el.parentElement?.querySelectorAll('button[class*="Persona"], span[class*="Persona"], [title*="@"]').forEach(p => {{
+                        const name = p.textContent?.trim() || p.getAttribute('title');
+                        if (name && !names.includes(name)) names.push(name);
+                    }});
+                }});
+                return names;
+            }};
+            const to = recipients('to');
+            const cc = recipients('cc');
+
+            // Date: sent/received timestamp in the reading pane header
+            const dateEl = document.querySelector({date});
+            const date = dateEl?.getAttribute('title') || dateEl?.textContent?.trim() || null;
+
             // Body
-            const bodyEl = document.querySelector('div[role="document"]');
+            const bodyEl = document.querySelector({body});
             const body = bodyEl?.innerText?.trim();
+            const bodyHtml = bodyEl?.innerHTML;
 
             // Get ID from selected item
-            const selected = document.querySelector('[data-convid][aria-selected="true"]');
+            const selected = document.querySelector(':is(' + {item} + ')[aria-selected="true"]');
             const id = selected?.getAttribute('data-convid') || '';
 
-            return JSON.stringify({{ id, subject, from, body, labels, isUnread: false }});
+            return JSON.stringify({{ id, subject, from, to, cc, date, body, bodyHtml, labels, labelColors, isUnread: false }});
         }})()
     "#,
-        extract_labels = EXTRACT_LABELS_JS
+        extract_labels = EXTRACT_LABELS_JS,
+        find_header = FIND_PANE_HEADER_JS,
+        item = serde_json::to_string(config.selectors.message_item())?,
+        subject = serde_json::to_string(config.selectors.subject())?,
+        sender = serde_json::to_string(config.selectors.sender())?,
+        date = serde_json::to_string(config.selectors.date())?,
+        body = serde_json::to_string(config.selectors.reading_pane())?
     );
 
-    let result = page.evaluate(read_script).await?;
-    let message_str = result.into_value::<String>().unwrap_or_default();
-    let message: Message = serde_json::from_str(&message_str).context("Failed to parse message")?;
-    Ok(message)
+    let mut msg: Message = evaluate_json(&page, "get_message:read", &read_script)
+        .await
+        .context("Failed to parse message")?;
+
+    let blank = |field: &Option<String>| field.as_deref().is_none_or(|s| s.trim().is_empty());
+    let (no_subject, no_body) = (blank(&msg.subject), blank(&msg.body));
+    if no_subject || no_body {
+        crate::debug!(
+            "get_message: selectors found no {}; using fallback heuristics",
+            match (no_subject, no_body) {
+                (true, true) => "subject or body",
+                (true, false) => "subject",
+                _ => "body",
+            }
+        );
+        let fallback: crate::inspect::FallbackRead = evaluate_json(
+            &page,
+            "get_message:fallback",
+            &crate::inspect::reading_pane_fallback_js()?,
+        )
+        .await?;
+        if no_subject {
+            msg.subject = fallback.subject;
+        }
+        if no_body {
+            msg.body = fallback.body;
+            msg.body_html = fallback.body_html;
+        }
+    } else {
+        crate::debug!("get_message: read with the configured selectors");
+    }
+    Ok(msg)
+}
+
+/// Get every message in a conversation, oldest first
+/// Collapsed items and "See more messages" are expanded before reading
+pub async fn get_thread(config: &Config, id: &str) -> Result<Vec<Message>> {
+    use crate::browser::click_element;
+
+    let browser = connect_or_start_browser(config).await?;
+    ensure_signed_in(&browser, config).await?;
+    let page = find_outlook_page(&browser, config).await?;
+
+    let id = &resolve_message(&page, id).await?;
+    let selector = crate::browser::message_selector(id);
+    click_element(&page, &selector, Some(2000)).await?;
+
+    // Expanding one item can reveal more collapsed ones, so repeat until nothing changes
+    let expand_script = r#"
+        (() => {
+            let clicked = 0;
+            document.querySelectorAll('button').forEach(btn => {
+                const text = (btn.getAttribute('aria-label') || btn.textContent || '').toLowerCase();
+                if (text.includes('see more messages') || text.includes('expand conversation')) {
+                    btn.click();
+                    clicked++;
+                }
+            });
+            const pane = document.querySelector('[data-app-section="ConversationContainer"], [role="main"]');
+            pane?.querySelectorAll('[aria-expanded="false"][tabindex]').forEach(item => {
+                item.click();
+                clicked++;
+            });
+            return clicked;
+        })()
+    "#;
+    for _ in 0..5 {
+        let result = evaluate(&page, "get_thread:expand", expand_script).await?;
+        if result.into_value::<u32>().unwrap_or(0) == 0 {
+            break;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(800)).await;
+    }
+
+    let read_script = format!(
+        r#"
+        (() => {{
+            const selected = document.querySelector(':is(' + {item} + ')[aria-selected="true"]');
+            const id = selected?.getAttribute('data-convid') || '';
+            const subjectEl = document.querySelector({subject});
+            const subject = subjectEl?.getAttribute('title') || subjectEl?.textContent?.trim() || '';
+
+            const messages = [];
+            document.querySelectorAll({body}).forEach(bodyEl => {{
+                // Walk up to the item that holds this body's header
+                let item = bodyEl.parentElement;
+                while (item && !item.querySelector({sender})) {{
+                    item = item.parentElement;
+                }}
+                const senderEl = item?.querySelector({sender});
+                const dateEl = item?.querySelector({date});
+                messages.push({{
+                    id,
+                    subject,
+                    from: senderEl?.textContent?.trim() || null,
+                    date: dateEl?.getAttribute('title') || dateEl?.textContent?.trim() || null,
+                    body: bodyEl.innerText?.trim(),
+                    bodyHtml: bodyEl.innerHTML,
+                    isUnread: false,
+                }});
+            }});
+            return JSON.stringify(messages);
+        }})()
+    "#,
+        item = serde_json::to_string(config.selectors.message_item())?,
+        subject = serde_json::to_string(config.selectors.subject())?,
+        sender = serde_json::to_string(config.selectors.sender())?,
+        date = serde_json::to_string(config.selectors.date())?,
+        body = serde_json::to_string(config.selectors.reading_pane())?
+    );
+
+    let mut messages: Vec<Message> = evaluate_json(&page, "get_thread:read", &read_script)
+        .await
+        .context("Failed to parse thread")?;
+
+    // Outlook shows the newest reply first by default
+    messages.reverse();
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listed(id: &str, subject: &str, date: &str) -> Message {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "subject": subject,
+            "from": "News <news@example.com>",
+            "date": date,
+        }))
+        .unwrap()
+    }
+
+    fn seen(subject: &str, date: Option<&str>) -> SeenMessage {
+        SeenMessage {
+            immutable_id: None,
+            subject: Some(subject.into()),
+            from: Some("News <news@example.com>".into()),
+            date: date.map(str::to_string),
+            listing: 1,
+        }
+    }
+
+    #[test]
+    fn stale_id_needs_a_single_match() {
+        let current = [
+            listed("a", "Weekly digest", "Mon 10/13/2025 9:00 AM"),
+            listed("b", "Weekly digest", "Mon 10/6/2025 9:00 AM"),
+            listed("c", "Other", "Mon 10/6/2025 9:00 AM"),
+        ];
+        // Same subject and sender twice: without a date it is ambiguous
+        assert_eq!(
+            stale_matches(&seen("Weekly digest", None), &current).len(),
+            2
+        );
+        let dated = seen("Weekly digest", Some("Mon 10/6/2025 9:00 AM"));
+        let ids: Vec<&str> = stale_matches(&dated, &current)
+            .iter()
+            .map(|m| m.id.as_str())
+            .collect();
+        assert_eq!(ids, ["b"]);
+        // A different date rules the subject match out
+        let gone = seen("Other", Some("Mon 9/29/2025 9:00 AM"));
+        assert!(stale_matches(&gone, &current).is_empty());
+    }
 }
@@ -0,0 +1,33 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+/// Set the log level: 0 = quiet, 1 = debug, 2+ = trace
+pub fn set_verbosity(level: u8) {
+    VERBOSITY.store(level, Ordering::Relaxed);
+}
+
+/// Check if messages at `level` should be printed
+pub fn enabled(level: u8) -> bool {
+    VERBOSITY.load(Ordering::Relaxed) >= level
+}
+
+/// Log a debug message to stderr (shown with -v)
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled(1) {
+            eprintln!("[debug] {}", format_args!($($arg)*));
+        }
+    };
+}
+
+/// Log a trace message to stderr (shown with -vv)
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled(2) {
+            eprintln!("[trace] {}", format_args!($($arg)*));
+        }
+    };
+}
@@ -1,6 +1,16 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use outlook_web::{api::Client, browser, config};
+use clap::{CommandFactory, Parser, Subcommand};
+use outlook_web::{
+    api::{
+        Client, Direction, Draft, InboxView, ListFilter, MailFolder, Message, MessageStatus,
+        Opened, SnoozeTime,
+    },
+    browser,
+    browser::Folder,
+    completions, config,
+    error::{self, Error},
+    output, stats,
+};
 
 #[derive(Parser)]
 #[command(name = "outlook-web")]
@@ -10,14 +20,114 @@ struct Cli {
     #[arg(long, global = true)]
     json: bool,
 
+    /// Output message lists as an aligned table
+    #[arg(long, global = true, conflicts_with_all = ["json", "ndjson"])]
+    table: bool,
+
+    /// Output message lists as CSV
+    #[arg(long, global = true, conflicts_with_all = ["json", "ndjson", "table"])]
+    csv: bool,
+
+    /// Output message lists as newline-delimited JSON (one object per line)
+    #[arg(long, global = true, conflicts_with = "json")]
+    ndjson: bool,
+
+    /// Only include these message fields in JSON output (e.g. id,subject,from)
+    #[arg(long, global = true, value_delimiter = ',')]
+    fields: Vec<String>,
+
+    /// Never color terminal output (NO_COLOR is honored too)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Log browser actions to stderr (-vv also logs raw script results)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Print nothing on success except the data asked for; errors are still shown
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Abort the command after this many seconds
+    #[arg(long, global = true)]
+    timeout: Option<u64>,
+
     /// Chrome debugging port (default: 9222)
     #[arg(long, global = true)]
     port: Option<u16>,
 
+    /// Connect to a remote browser at this DevTools URL (ws://, wss://, http://, https://)
+    #[arg(long, global = true, value_name = "URL")]
+    endpoint: Option<String>,
+
+    /// Scroll the message list at most N times to reach -n (default 50)
+    #[arg(long, global = true, value_name = "N")]
+    max_scroll: Option<u32>,
+
+    /// Wait at least MS milliseconds between mutating operations (default 0)
+    #[arg(long, global = true, value_name = "MS")]
+    pace: Option<u64>,
+
+    /// Index of the Outlook tab to use when several are open (see `test`)
+    #[arg(long, global = true)]
+    tab: Option<usize>,
+
+    /// Use the Outlook tab signed in as this email address
+    #[arg(long, global = true)]
+    account: Option<String>,
+
+    /// Start the browser headless (needs a signed-in --profile)
+    #[arg(long, global = true)]
+    headless: bool,
+
+    /// Never start a browser; fail if none is reachable
+    #[arg(long, global = true)]
+    no_launch: bool,
+
+    /// Browser profile directory to launch with
+    #[arg(long, global = true)]
+    profile: Option<std::path::PathBuf>,
+
+    /// Use the named profile from the config file's `profiles`
+    #[arg(long, global = true, value_name = "NAME")]
+    profile_name: Option<String>,
+
+    /// Wait up to SECS (default 300) for sign-in to finish before running the command
+    #[arg(
+        long,
+        global = true,
+        value_name = "SECS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "300"
+    )]
+    wait_for_login: Option<u64>,
+
+    /// Unix socket of a running `serve`; commands are sent there instead of run here
+    #[arg(long, global = true)]
+    socket: Option<std::path::PathBuf>,
+
+    /// Config file to use (default: $OUTLOOK_WEB_CONFIG, then the user config directory)
+    #[arg(long, global = true)]
+    config: Option<std::path::PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ExportFormat {
+    /// RFC 822 message (reconstructed unless --native)
+    Eml,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum BatchAction {
+    Archive,
+    Delete,
+    MarkRead,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// List inbox messages
@@ -25,6 +135,42 @@ enum Commands {
         /// Maximum number of messages
         #[arg(short = 'n', long, default_value = "20")]
         max: u32,
+        /// Sort the list by this field instead of page order
+        #[arg(long, value_enum)]
+        sort: Option<output::SortField>,
+        /// Reverse the sort order
+        #[arg(long, requires = "sort")]
+        reverse: bool,
+        /// Only show messages whose sender contains this text (case-insensitive)
+        #[arg(long)]
+        from: Option<String>,
+        /// Only show messages whose subject contains this text (case-insensitive)
+        #[arg(long)]
+        subject: Option<String>,
+        /// List the "Other" side of the Focused Inbox
+        #[arg(long)]
+        other: bool,
+        /// List Focused and Other together
+        #[arg(long, conflicts_with = "other")]
+        all: bool,
+        /// Print the messages in groups under a header with their count
+        #[arg(long, value_enum)]
+        group_by: Option<output::GroupBy>,
+    },
+    /// List unread messages
+    Unread {
+        /// Maximum number of messages
+        #[arg(short = 'n', long, default_value = "20")]
+        max: u32,
+        /// Folder to list
+        #[arg(long, value_enum, default_value = "inbox")]
+        folder: Folder,
+    },
+    /// List flagged (follow-up) messages
+    Flagged {
+        /// Maximum number of messages
+        #[arg(short = 'n', long, default_value = "20")]
+        max: u32,
     },
     /// List junk/spam folder messages
     ListSpam {
@@ -32,8 +178,109 @@ enum Commands {
         #[arg(short = 'n', long, default_value = "20")]
         max: u32,
     },
-    /// Read a specific message by ID
+    /// Watch the inbox and print new messages as they arrive
+    Watch {
+        /// Seconds between polls
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..), default_value = "30")]
+        interval: u64,
+        /// Shell command to run per new message (fields in OUTLOOK_* env vars)
+        #[arg(long)]
+        exec: Option<String>,
+    },
+    /// Summarize the inbox: unread count, top senders, labels
+    Stats {
+        /// Maximum number of messages to look at
+        #[arg(short = 'n', long, default_value = "200")]
+        max: u32,
+        /// Number of top senders to show
+        #[arg(long, default_value = "10")]
+        top: usize,
+    },
+    /// Serve inbox totals, unread and per-label counts for Prometheus at /metrics
+    Metrics {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:9898")]
+        listen: String,
+        /// Maximum number of messages to look at per scrape
+        #[arg(short = 'n', long, default_value = "200")]
+        max: u32,
+        /// Seconds to reuse a scrape before reading the inbox again
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..), default_value = "60")]
+        interval: u64,
+    },
+    /// Count messages in a folder
+    Count {
+        /// Folder to count
+        #[arg(value_enum, default_value = "inbox")]
+        folder: Folder,
+        /// Only count unread messages
+        #[arg(long)]
+        unread: bool,
+    },
+    /// Check whether a message is in the current folder, and its read/flag/label state
+    Status {
+        /// Message ID
+        id: String,
+    },
+    /// Read a specific message by ID, or the listed message matching --subject/--from
     Read {
+        /// Message ID
+        #[arg(required_unless_present_any = ["subject", "from"])]
+        id: Option<String>,
+        /// Read the listed message whose subject contains this text (case-insensitive)
+        #[arg(long, conflicts_with = "id")]
+        subject: Option<String>,
+        /// Read the listed message whose sender contains this text (case-insensitive)
+        #[arg(long, conflicts_with = "id")]
+        from: Option<String>,
+        /// Read the first match instead of failing when several messages match
+        #[arg(long)]
+        first: bool,
+        /// Print the HTML body instead of plain text
+        #[arg(long)]
+        html: bool,
+        /// Convert the HTML body to clean text, with links as numbered footnotes
+        #[arg(long, conflicts_with = "html")]
+        text: bool,
+        /// Mark the message unread again afterwards if it was unread (opening it marks it read)
+        #[arg(long)]
+        keep_unread: bool,
+    },
+    /// Read the message after this one in the list (Outlook's Ctrl+.)
+    Next {
+        /// Message ID of the current message
+        id: String,
+        /// Print the HTML body instead of plain text
+        #[arg(long)]
+        html: bool,
+        /// Convert the HTML body to clean text, with links as numbered footnotes
+        #[arg(long, conflicts_with = "html")]
+        text: bool,
+        /// Mark the message unread again afterwards if it was unread (opening it marks it read)
+        #[arg(long)]
+        keep_unread: bool,
+    },
+    /// Read the message before this one in the list (Outlook's Ctrl+,)
+    Prev {
+        /// Message ID of the current message
+        id: String,
+        /// Print the HTML body instead of plain text
+        #[arg(long)]
+        html: bool,
+        /// Convert the HTML body to clean text, with links as numbered footnotes
+        #[arg(long, conflicts_with = "html")]
+        text: bool,
+        /// Mark the message unread again afterwards if it was unread (opening it marks it read)
+        #[arg(long)]
+        keep_unread: bool,
+    },
+    /// Read every message in a conversation, oldest first
+    Thread {
+        /// Message ID
+        id: String,
+    },
+    /// Print a message's internet headers (or full source, depending on the account)
+    Headers {
         /// Message ID
         id: String,
     },
@@ -52,12 +299,20 @@ enum Commands {
         /// Message ID
         id: String,
     },
+    /// Block a message's sender and move their mail to junk
+    Block {
+        /// Message ID
+        id: String,
+    },
     /// Add label/category to message
     Label {
         /// Message ID
         id: String,
         /// Label to add
         label: String,
+        /// Also accept the one label whose name starts with LABEL
+        #[arg(long)]
+        prefix: bool,
     },
     /// Remove label/category from message
     Unlabel {
@@ -65,14 +320,65 @@ enum Commands {
         id: String,
         /// Label to remove
         label: String,
+        /// Also accept the one label whose name starts with LABEL
+        #[arg(long)]
+        prefix: bool,
+    },
+    /// List the folders in the folder pane, with unread and item counts
+    ListFolders,
+    /// Create a folder, at the top of the mailbox or inside --parent
+    CreateFolder {
+        /// Folder name
+        name: String,
+        /// Existing folder to create it in
+        #[arg(long, value_name = "FOLDER")]
+        parent: Option<String>,
     },
     /// List available labels/categories
-    Labels,
+    Labels {
+        /// Re-scrape the list instead of using the cache
+        #[arg(long)]
+        refresh: bool,
+    },
     /// Move message from Junk to Inbox
     Unspam {
         /// Message ID
         id: String,
     },
+    /// Move message from Deleted Items back to Inbox
+    Restore {
+        /// Message ID
+        id: String,
+    },
+    /// Snooze a message until later
+    Snooze {
+        /// Message ID
+        id: String,
+        /// later-today, tomorrow, this-weekend, next-week, or YYYY-MM-DD
+        #[arg(long, default_value = "tomorrow")]
+        when: SnoozeTime,
+    },
+    /// Pin message to the top of the list
+    Pin {
+        /// Message ID
+        id: String,
+    },
+    /// Unpin message
+    Unpin {
+        /// Message ID
+        id: String,
+    },
+    /// Move an inbox message to Focused (or Other), teaching Focused Inbox
+    Focus {
+        /// Message ID
+        id: String,
+        /// Move it to Other instead
+        #[arg(long)]
+        other: bool,
+        /// Also move future messages from the sender
+        #[arg(long)]
+        always: bool,
+    },
     /// Mark message as read
     MarkRead {
         /// Message ID
@@ -83,133 +389,927 @@ enum Commands {
         /// Message ID
         id: String,
     },
+    /// Group the message list into conversations (--on) or show single messages (--off)
+    ConversationView {
+        /// Show conversations
+        #[arg(long, conflicts_with = "off", required_unless_present = "off")]
+        on: bool,
+        /// Show individual messages
+        #[arg(long)]
+        off: bool,
+    },
+    /// Mark all messages in a folder as read
+    MarkAllRead {
+        /// Folder to mark
+        #[arg(value_enum, default_value = "inbox")]
+        folder: Folder,
+    },
+    /// Archive the messages in a folder that have already been read
+    ArchiveRead {
+        /// Folder to clean up
+        #[arg(value_enum, default_value = "inbox")]
+        folder: Folder,
+        /// Maximum number of messages to look at
+        #[arg(short = 'n', long, default_value = "200")]
+        max: u32,
+        /// Only archive messages at least this many days old
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u32>,
+        /// List what would be archived without archiving it
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Remove all labels from message
     ClearLabels {
         /// Message ID
         id: String,
     },
-    /// Unsubscribe from a mailing list (opens unsubscribe link)
+    /// Unsubscribe from a mailing list (one-click POST, Outlook banner, or opens link)
     Unsubscribe {
         /// Message ID
         id: String,
     },
-    /// Sync labels: show categories used on messages but not in master list
-    SyncLabels,
+    /// Permanently delete everything in the Junk folder
+    EmptyJunk {
+        /// Confirm the deletion
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Permanently delete everything in Deleted Items
+    EmptyTrash {
+        /// Confirm the deletion
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Export a message to a file
+    Export {
+        /// Message ID
+        id: String,
+        /// Output file (or directory with --native)
+        #[arg(long)]
+        out: std::path::PathBuf,
+        /// Export format
+        #[arg(long, value_enum, default_value = "eml")]
+        format: ExportFormat,
+        /// Use Outlook's own Download action for the original message
+        #[arg(long)]
+        native: bool,
+    },
+    /// Append a folder's messages to an mbox file, skipping ones already in it
+    /// Reconstructed from the page like `export`, so attachments and most headers are lost.
+    ExportMbox {
+        /// Folder to export
+        #[arg(value_enum, default_value = "inbox")]
+        folder: Folder,
+        /// Mbox file to append to
+        #[arg(long)]
+        out: std::path::PathBuf,
+        /// Maximum number of messages to export
+        #[arg(short = 'n', long, default_value = "1000")]
+        max: u32,
+    },
+    /// List links in a message body
+    Links {
+        /// Message ID
+        id: String,
+        /// Include mailto: and javascript: links
+        #[arg(long)]
+        all: bool,
+    },
+    /// Apply one action to several messages
+    Batch {
+        /// Action to apply
+        #[arg(value_enum)]
+        action: BatchAction,
+        /// Message IDs (read from stdin, one per line, when omitted)
+        ids: Vec<String>,
+        /// Multi-select the messages and act once instead of one at a time
+        #[arg(long)]
+        select: bool,
+        /// Delete without the confirmation prompt
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Move several messages to a folder in one action
+    MoveMany {
+        /// Destination folder
+        #[arg(value_enum)]
+        folder: Folder,
+        /// Message IDs
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// Create a new label (category)
+    CreateLabel {
+        /// Label name
+        name: String,
+        /// Category color, as named in Outlook (e.g. red, blue)
+        #[arg(long)]
+        color: Option<String>,
+    },
+    /// Delete a label (category) entirely
+    DeleteLabel {
+        /// Label name
+        #[arg(value_name = "LABEL")]
+        name: String,
+        /// Confirm the deletion
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Sync labels: show categories in Outlook but not in the configured master list
+    SyncLabels {
+        /// Add the missing categories to the master list in the config file
+        #[arg(long)]
+        add: bool,
+    },
+    /// Save a message as a draft without sending it
+    Draft {
+        /// Recipient address (repeatable)
+        #[arg(long)]
+        to: Vec<String>,
+        /// Subject line
+        #[arg(long)]
+        subject: String,
+        /// Message body (read from stdin when omitted)
+        #[arg(long, conflicts_with = "template")]
+        body: Option<String>,
+        /// Use a saved template from the config as the body
+        #[arg(long)]
+        template: Option<String>,
+        /// Value for a template placeholder, as key=value (repeatable)
+        #[arg(long = "var", value_parser = parse_var, requires = "template")]
+        vars: Vec<(String, String)>,
+        /// File to attach (repeatable)
+        #[arg(long = "attach")]
+        attachments: Vec<std::path::PathBuf>,
+        /// Show the filled-in draft and ask on the terminal before saving it
+        #[arg(long)]
+        review: bool,
+    },
+    /// List messages in the Drafts folder
+    Drafts {
+        /// Maximum number of messages
+        #[arg(short = 'n', long, default_value = "20")]
+        max: u32,
+    },
+    /// Look up people's email addresses by name (via compose autocomplete)
+    Contact {
+        /// Name or partial address to search for
+        query: String,
+    },
+    /// Undo the last archive/delete/move while Outlook's notification is showing
+    Undo,
+    /// Send keyboard shortcuts to Outlook (e.g. `key r`, `key Ctrl+Shift+v`)
+    Key {
+        /// Keys to press in order, with optional Ctrl+/Shift+/Alt+/Meta+ prefixes
+        #[arg(required = true)]
+        keys: Vec<String>,
+        /// Select this message first so the shortcut acts on it
+        #[arg(long)]
+        to_message: Option<String>,
+    },
+    /// Show a folder or message in the Outlook tab and bring it to the front
+    Open {
+        /// inbox, junk, drafts, ..., a folder name, or a message ID
+        target: String,
+    },
+    /// Save a PNG screenshot of the Outlook tab
+    Screenshot {
+        /// Output file
+        #[arg(long, default_value = "outlook.png")]
+        out: std::path::PathBuf,
+        /// Capture the full page instead of the visible viewport
+        #[arg(long)]
+        full: bool,
+    },
+    /// Keep one browser connection open and run commands sent to --socket
+    Serve,
     /// Test connection to browser
     Test,
+    /// Check the browser, debug port, Outlook tab and sign-in, and report what's wrong
+    Doctor,
     /// Inspect DOM to find selectors
-    Inspect,
+    Inspect {
+        /// Describe elements matching this CSS selector instead of the built-in probes
+        #[arg(conflicts_with = "raw")]
+        selector: Option<String>,
+        /// Dump the raw HTML of the reading pane and first list items instead
+        #[arg(long)]
+        raw: bool,
+        /// Maximum characters per dumped element with --raw
+        #[arg(long, default_value = "20000", requires = "raw")]
+        max_len: usize,
+    },
     /// Configure settings
     Config {
         /// Set default port
         #[arg(long)]
         port: Option<u16>,
+        /// Set the Outlook URL opened when starting a browser
+        #[arg(long)]
+        url: Option<String>,
+        /// Set extra hostnames recognised as Outlook tabs (repeatable)
+        #[arg(long = "host")]
+        hosts: Vec<String>,
+        /// Set which Focused Inbox pivot `list` shows by default
+        #[arg(long, value_enum)]
+        inbox_view: Option<InboxView>,
+        /// Delete the config file, restoring every default
+        #[arg(long, conflicts_with_all = ["port", "url", "hosts", "inbox_view", "show"])]
+        reset: bool,
+        /// Print the resolved config, defaults included, as JSON
+        #[arg(long)]
+        show: bool,
+    },
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate the script for
+        #[arg(value_enum)]
+        shell: completions::Shell,
+    },
+    /// Print live folder or label names, one per line, for completion scripts
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        #[arg(value_enum)]
+        what: completions::Dynamic,
     },
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     let cli = Cli::parse();
-    let cfg = config::load_config()?;
-    let port = cli.port.unwrap_or_else(|| cfg.port());
+    let json = cli.json || cli.ndjson;
+    let timeout = cli.timeout;
+
+    let result = match timeout {
+        Some(secs) => {
+            match tokio::time::timeout(std::time::Duration::from_secs(secs), run(cli)).await {
+                Ok(result) => result,
+                Err(_) => Err(Error::Timeout {
+                    what: "Command".to_string(),
+                    secs,
+                }
+                .into()),
+            }
+        }
+        None => run(cli).await,
+    };
+
+    if let Err(e) = result {
+        if e.is::<Reported>() {
+            std::process::exit(error::EXIT_FAILURE);
+        }
+        if json {
+            println!(
+                "{}",
+                serde_json::json!({ "ok": false, "error": format!("{:#}", e) })
+            );
+        } else {
+            eprintln!("Error: {:?}", e);
+        }
+        std::process::exit(exit_code(&e));
+    }
+}
+
+/// A failure the command has already reported in its own output
+/// `main` exits with a failure code for it without printing anything more.
+#[derive(Debug)]
+struct Reported;
+
+impl std::fmt::Display for Reported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("failure already reported")
+    }
+}
+
+impl std::error::Error for Reported {}
+
+/// Exit code for a failed run, from the first typed error in its chain
+/// `Error::Other` wrappers are looked through, so a wrapped timeout still exits with 124.
+fn exit_code(e: &anyhow::Error) -> i32 {
+    Error::find(e).map_or(error::EXIT_FAILURE, error::exit_code)
+}
+
+/// Load the config file and apply the global flags that override it
+fn load_config(cli: &Cli, path: &std::path::Path) -> Result<config::Config> {
+    let mut cfg = config::load_config(path)?;
+    if let Some(name) = &cli.profile_name {
+        cfg.apply_profile(name)?;
+    }
+    if let Some(port) = cli.port {
+        // A port on the command line means a local browser, even if the config has an endpoint
+        cfg.port = Some(port);
+        cfg.endpoint = None;
+    }
+    if let Some(endpoint) = &cli.endpoint {
+        cfg.endpoint = Some(endpoint.clone());
+    }
+    if cli.headless {
+        cfg.headless = true;
+    }
+    if cli.no_launch {
+        cfg.no_launch = true;
+    }
+    if let Some(profile) = &cli.profile {
+        cfg.profile = Some(profile.clone());
+    }
+    if cli.max_scroll.is_some() {
+        cfg.max_scroll = cli.max_scroll;
+    }
+    if cli.pace.is_some() {
+        cfg.pace_ms = cli.pace;
+    }
+    if cli.tab.is_some() {
+        cfg.tab = cli.tab;
+    }
+    if let Some(account) = &cli.account {
+        cfg.account = Some(account.clone());
+    }
+    Ok(cfg)
+}
+
+/// Print folders as an indented tree with their counts
+fn print_folders(folders: &[MailFolder]) {
+    // Depth of each folder, found through the already-printed parents
+    let mut depths: Vec<(&str, usize)> = Vec::new();
+    for folder in folders {
+        let depth = folder
+            .parent
+            .as_deref()
+            .and_then(|p| depths.iter().rev().find(|(name, _)| *name == p))
+            .map_or(0, |(_, d)| d + 1);
+        depths.push((&folder.name, depth));
+
+        let mut counts = Vec::new();
+        if let Some(unread) = folder.unread.filter(|n| *n > 0) {
+            counts.push(format!("{} unread", unread));
+        }
+        if let Some(total) = folder.total {
+            counts.push(format!("{} items", total));
+        }
+        let indent = "  ".repeat(depth);
+        if counts.is_empty() {
+            println!("{}{}", indent, folder.name);
+        } else {
+            println!("{}{} ({})", indent, folder.name, counts.join(", "));
+        }
+    }
+}
+
+/// Parse a `--var key=value` argument
+fn parse_var(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got {:?}", s))?;
+    Ok((key.trim().to_string(), value.to_string()))
+}
+
+/// Print the result of an action: `text` normally, or `result` plus `"ok": true` as JSON
+fn report(json: bool, mut result: serde_json::Value, text: impl std::fmt::Display) {
+    if json {
+        result["ok"] = true.into();
+        println!("{}", result);
+    } else if !output::quiet() {
+        println!("{}", text);
+    }
+}
+
+/// Print a message as read by `read`: headers, then the body (HTML with `html`)
+fn print_message(json: bool, fields: &[String], msg: &Message, html: bool) -> Result<()> {
+    if json {
+        println!("{}", output::project(msg, fields)?);
+    } else {
+        println!("From: {}", msg.from.as_deref().unwrap_or("Unknown"));
+        if !msg.to.is_empty() {
+            println!("To: {}", msg.to.join(", "));
+        }
+        if !msg.cc.is_empty() {
+            println!("Cc: {}", msg.cc.join(", "));
+        }
+        println!(
+            "Subject: {}",
+            msg.subject.as_deref().unwrap_or("(no subject)")
+        );
+        if !msg.labels.is_empty() {
+            let labels: Vec<String> = msg
+                .labels
+                .iter()
+                .map(|l| match msg.label_colors.get(l) {
+                    Some(color) => format!("{} ({})", l, color),
+                    None => l.clone(),
+                })
+                .collect();
+            println!("Labels: {}", labels.join(", "));
+        }
+        println!("---");
+        let body = if html { &msg.body_html } else { &msg.body };
+        println!("{}", body.as_deref().unwrap_or(""));
+    }
+    Ok(())
+}
+
+/// Print a message list in the output mode selected by the global flags
+fn print_messages(cli: &Cli, messages: &[Message], empty: &str) -> Result<()> {
+    if cli.ndjson {
+        print_ndjson(&project(cli, messages)?)?;
+    } else if cli.csv {
+        print!("{}", output::to_csv(messages)?);
+    } else if cli.table && !messages.is_empty() {
+        print!(
+            "{}",
+            output::format_table(messages, output::terminal_width(), output::use_color())
+        );
+    } else if cli.json {
+        println!("{}", serde_json::to_string(&project(cli, messages)?)?);
+    } else if messages.is_empty() {
+        println!("{}", empty);
+    } else {
+        let color = output::use_color();
+        for msg in messages {
+            println!("{}", output::format_line(msg, color));
+        }
+    }
+    Ok(())
+}
+
+/// Print grouped messages: one object per group as JSON, otherwise a header per group
+fn print_groups(cli: &Cli, groups: &[(String, Vec<Message>)]) -> Result<()> {
+    if cli.json || cli.ndjson {
+        let values = groups
+            .iter()
+            .map(|(group, messages)| {
+                Ok(serde_json::json!({
+                    "group": group,
+                    "count": messages.len(),
+                    "messages": project(cli, messages)?,
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if cli.ndjson {
+            print_ndjson(&values)?;
+        } else {
+            println!("{}", serde_json::to_string(&values)?);
+        }
+        return Ok(());
+    }
+
+    if groups.is_empty() {
+        println!("No messages found. Make sure Outlook inbox is visible.");
+    }
+    for (i, (group, messages)) in groups.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{} ({})", group, messages.len());
+        print_messages(cli, messages, "")?;
+    }
+    Ok(())
+}
+
+/// Serialize messages for JSON output, keeping only the `--fields` selection
+fn project(cli: &Cli, messages: &[Message]) -> Result<Vec<serde_json::Value>> {
+    messages
+        .iter()
+        .map(|msg| output::project(msg, &cli.fields))
+        .collect()
+}
+
+/// Print one JSON object per line, flushing after each so consumers can stream
+fn print_ndjson<T: serde::Serialize>(items: &[T]) -> Result<()> {
+    use std::io::Write;
+
+    let mut out = std::io::stdout().lock();
+    for item in items {
+        serde_json::to_writer(&mut out, item)?;
+        writeln!(out)?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Run a shell command for a message, passing its fields as OUTLOOK_* env vars
+fn run_exec(cmd: &str, msg: &outlook_web::api::Message) -> Result<()> {
+    let mut command = if cfg!(windows) {
+        let mut c = std::process::Command::new("cmd");
+        c.arg("/C");
+        c
+    } else {
+        let mut c = std::process::Command::new("sh");
+        c.arg("-c");
+        c
+    };
+
+    let status = command
+        .arg(cmd)
+        .env("OUTLOOK_ID", &msg.id)
+        .env("OUTLOOK_FROM", msg.from.as_deref().unwrap_or(""))
+        .env("OUTLOOK_SUBJECT", msg.subject.as_deref().unwrap_or(""))
+        .env("OUTLOOK_PREVIEW", msg.preview.as_deref().unwrap_or(""))
+        .env("OUTLOOK_LABELS", msg.labels.join(","))
+        .env("OUTLOOK_UNREAD", msg.is_unread.to_string())
+        .status()?;
+
+    if !status.success() {
+        eprintln!("Command exited with {} for {}", status, msg.id);
+    }
+    Ok(())
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    outlook_web::log::set_verbosity(cli.verbose);
+    if cli.no_color {
+        output::disable_color();
+    }
+    if cli.json || cli.ndjson || cli.quiet {
+        outlook_web::progress::disable();
+    }
+    if cli.quiet {
+        output::set_quiet();
+    }
+    output::validate_fields(&cli.fields)?;
+    let config_path = config::config_path(cli.config.as_deref())?;
+
+    // Needs neither the config nor a browser
+    if let Commands::Completions { shell } = cli.command {
+        print!("{}", completions::generate(shell, Cli::command()));
+        return Ok(());
+    }
+
+    // Handled before loading so a config file that fails to parse can still be reset
+    if let Commands::Config { reset: true, .. } = cli.command {
+        config::reset_config(&config_path)?;
+        report(
+            cli.json,
+            serde_json::json!({ "action": "config-reset" }),
+            "Config reset to defaults",
+        );
+        return Ok(());
+    }
+
+    // Thin client: let a running `serve` do the work over its socket
+    if let Some(socket) = &cli.socket
+        && !matches!(cli.command, Commands::Serve)
+    {
+        return forward(socket).await;
+    }
+
+    let cfg = load_config(&cli, &config_path)?;
+    // `config` edits the file and `doctor` should report sign-in status, not wait on it
+    if let Some(secs) = cli.wait_for_login
+        && !matches!(cli.command, Commands::Config { .. } | Commands::Doctor)
+    {
+        browser::wait_for_login(&cfg, std::time::Duration::from_secs(secs)).await?;
+    }
 
     match cli.command {
-        Commands::Config { port: new_port } => {
-            let mut cfg = config::load_config()?;
+        Commands::Config {
+            port: new_port,
+            url,
+            hosts,
+            inbox_view,
+            reset: _,
+            show,
+        } => {
+            let mut cfg = config::load_config(&config_path)?;
+            let changed =
+                new_port.is_some() || url.is_some() || !hosts.is_empty() || inbox_view.is_some();
             if let Some(p) = new_port {
                 cfg.port = Some(p);
-                config::save_config(&cfg)?;
-                println!("Port set to: {}", p);
-            } else {
+                if !cli.json && !cli.quiet {
+                    println!("Port set to: {}", p);
+                }
+            }
+            if let Some(url) = url {
+                if !cli.json && !cli.quiet {
+                    println!("Outlook URL set to: {}", url);
+                }
+                cfg.outlook_url = Some(url);
+            }
+            if !hosts.is_empty() {
+                if !cli.json && !cli.quiet {
+                    println!("Outlook hosts set to: {}", hosts.join(", "));
+                }
+                cfg.outlook_hosts = hosts;
+            }
+            if let Some(view) = inbox_view {
+                if !cli.json && !cli.quiet {
+                    println!("Inbox view set to: {:?}", view);
+                }
+                cfg.inbox_view = Some(view);
+            }
+            if changed {
+                config::save_config(&config_path, &cfg)?;
+            }
+            if show {
+                // Saved above, so the profile only shapes what is printed
+                if let Some(name) = &cli.profile_name {
+                    cfg.apply_profile(name)?;
+                }
+                println!("{}", serde_json::to_string_pretty(&cfg.resolved())?);
+            } else if cli.json {
+                report(true, serde_json::to_value(&cfg)?, "");
+            } else if !changed {
                 println!("Current settings:");
                 println!("  port: {}", cfg.port());
+                if let Some(endpoint) = &cfg.endpoint {
+                    println!("  endpoint: {}", endpoint);
+                }
+                println!("  url: {}", cfg.outlook_url());
+                println!(
+                    "  hosts: {}",
+                    cfg.outlook_hosts().collect::<Vec<_>>().join(", ")
+                );
+                println!("  headless: {}", cfg.headless);
+                println!("  no_launch: {}", cfg.no_launch);
+                println!("  inbox_view: {:?}", cfg.inbox_view());
+                println!("  retry_attempts: {}", cfg.retry_attempts());
+                println!("  retry_delay_ms: {}", cfg.retry_delay().as_millis());
+                println!("  max_scroll: {}", cfg.max_scroll());
+                println!("  pace_ms: {}", cfg.pace().as_millis());
+                if let Some(profile) = &cfg.profile {
+                    println!("  profile: {}", profile.display());
+                }
+                println!("  selectors:");
+                println!("    message_item: {}", cfg.selectors.message_item());
+                println!("    reading_pane: {}", cfg.selectors.reading_pane());
+                println!("    subject: {}", cfg.selectors.subject());
+                println!("    sender: {}", cfg.selectors.sender());
+                println!("    date: {}", cfg.selectors.date());
             }
         }
-        Commands::List { max } => {
-            let client = Client::new(port);
-            let messages = client.list_messages(max).await?;
-
-            if cli.json {
-                println!("{}", serde_json::to_string(&messages)?);
-            } else if messages.is_empty() {
-                println!("No messages found. Make sure Outlook inbox is visible.");
-            } else {
-                for msg in &messages {
-                    let from = msg.from.as_deref().unwrap_or("Unknown");
-                    let subject = msg.subject.as_deref().unwrap_or("(no subject)");
-                    let unread = if msg.is_unread { "*" } else { " " };
-                    let labels = if msg.labels.is_empty() {
-                        String::new()
-                    } else {
-                        format!(" [{}]", msg.labels.join(", "))
-                    };
-                    println!("{}{} | {} | {}{}", unread, msg.id, from, subject, labels);
-                }
+        Commands::List {
+            max,
+            sort,
+            reverse,
+            ref from,
+            ref subject,
+            other,
+            all,
+            group_by,
+        } => {
+            if group_by.is_some() && cli.csv {
+                anyhow::bail!("--group-by can't be combined with --csv");
+            }
+            let view = list_view(other, all, cfg.inbox_view());
+            let client = Client::new(cfg);
+            let filter = ListFilter {
+                from: from.clone(),
+                subject: subject.clone(),
+            };
+            // Unsorted NDJSON can go out as each scroll collects it
+            if cli.ndjson && sort.is_none() && group_by.is_none() {
+                client
+                    .list_messages_each(max, view, |batch| {
+                        let matching: Vec<Message> = batch
+                            .iter()
+                            .filter(|m| m.matches(&filter))
+                            .cloned()
+                            .collect();
+                        print_ndjson(&project(&cli, &matching)?)
+                    })
+                    .await?;
+                return Ok(());
+            }
+            let messages = list_filtered(&client, max, view, &filter, sort, reverse).await?;
+            if let Some(by) = group_by {
+                return print_groups(&cli, &output::group_messages(&messages, by));
             }
+            print_messages(
+                &cli,
+                &messages,
+                "No messages found. Make sure Outlook inbox is visible.",
+            )?;
+        }
+        Commands::Unread { max, folder } => {
+            let client = Client::new(cfg);
+            let messages = client.list_unread(folder, max).await?;
+            print_messages(&cli, &messages, "No unread messages.")?;
+        }
+        Commands::Flagged { max } => {
+            let client = Client::new(cfg);
+            let messages = client.list_flagged(max).await?;
+            print_messages(&cli, &messages, "No flagged messages.")?;
         }
         Commands::ListSpam { max } => {
-            let client = Client::new(port);
+            let client = Client::new(cfg);
             let messages = client.list_spam(max).await?;
-
+            print_messages(&cli, &messages, "No spam messages found.")?;
+        }
+        Commands::Watch { interval, exec } => {
+            let client = Client::new(cfg);
+            let json = cli.json || cli.ndjson;
+            client
+                .watch(std::time::Duration::from_secs(interval), |msg| {
+                    if json {
+                        print_ndjson(&[output::project(msg, &cli.fields)?])?;
+                    } else {
+                        let from = msg.from.as_deref().unwrap_or("Unknown");
+                        let subject = msg.subject.as_deref().unwrap_or("(no subject)");
+                        println!("{} | {} | {}", msg.id, from, subject);
+                    }
+                    if let Some(cmd) = &exec {
+                        run_exec(cmd, msg)?;
+                    }
+                    Ok(())
+                })
+                .await?;
+        }
+        Commands::Stats { max, top } => {
+            let view = cfg.inbox_view();
+            let client = Client::new(cfg);
+            let messages = client.list_messages(max, view).await?;
+            let summary = stats::summarize(&messages, top);
             if cli.json {
-                println!("{}", serde_json::to_string(&messages)?);
-            } else if messages.is_empty() {
-                println!("No spam messages found.");
+                println!("{}", serde_json::to_string(&summary)?);
             } else {
-                for msg in &messages {
-                    let from = msg.from.as_deref().unwrap_or("Unknown");
-                    let subject = msg.subject.as_deref().unwrap_or("(no subject)");
-                    println!("{} | {} | {}", msg.id, from, subject);
+                println!("{}", summary);
+            }
+        }
+        Commands::Metrics {
+            ref listen,
+            max,
+            interval,
+        } => {
+            let view = cfg.inbox_view();
+            let client = Client::new(cfg);
+            outlook_web::metrics::serve(
+                client,
+                view,
+                listen,
+                max,
+                std::time::Duration::from_secs(interval),
+            )
+            .await?;
+        }
+        Commands::Count { folder, unread } => {
+            let client = Client::new(cfg);
+            let count = client.count(folder, unread).await?;
+            report(cli.json, serde_json::json!({ "count": count }), count);
+        }
+        Commands::Status { id } => {
+            let client = Client::new(cfg);
+            let status = client.message_status(&id).await?;
+            if cli.json {
+                println!("{}", serde_json::to_string(&status)?);
+            } else {
+                match &status {
+                    MessageStatus::Present(msg) => {
+                        let mut flags = vec![if msg.is_unread { "unread" } else { "read" }];
+                        if msg.is_flagged {
+                            flags.push("flagged");
+                        }
+                        if msg.is_pinned {
+                            flags.push("pinned");
+                        }
+                        println!("Present ({})", flags.join(", "));
+                        println!("{}", output::format_line(msg, false));
+                    }
+                    MessageStatus::Absent { id } => println!("Absent: {}", id),
                 }
             }
         }
-        Commands::Read { id } => {
-            let client = Client::new(port);
-            let msg = client.get_message(&id).await?;
+        Commands::Read {
+            id,
+            subject,
+            from,
+            first,
+            html,
+            text,
+            keep_unread,
+        } => {
+            let client = Client::new(cfg);
+            let id = read_target(&client, id, ListFilter { from, subject }, first).await?;
+            let msg = read_message(&client, &id, None, keep_unread, text).await?;
+            print_message(cli.json, &cli.fields, &msg, html)?;
+        }
+        Commands::Next {
+            ref id,
+            html,
+            text,
+            keep_unread,
+        }
+        | Commands::Prev {
+            ref id,
+            html,
+            text,
+            keep_unread,
+        } => {
+            let direction = if matches!(cli.command, Commands::Next { .. }) {
+                Direction::Next
+            } else {
+                Direction::Previous
+            };
+            let client = Client::new(cfg);
+            let msg = read_message(&client, id, Some(direction), keep_unread, text).await?;
+            print_message(cli.json, &cli.fields, &msg, html)?;
+        }
+        Commands::Headers { id } => {
+            let client = Client::new(cfg);
+            let headers = client.get_headers(&id).await?;
+            if cli.json {
+                println!("{}", serde_json::json!({ "id": id, "headers": headers }));
+            } else {
+                println!("{}", headers);
+            }
+        }
+        Commands::Thread { ref id } => {
+            let client = Client::new(cfg);
+            let messages = client.get_thread(id).await?;
 
             if cli.json {
-                println!("{}", serde_json::to_string(&msg)?);
+                println!("{}", serde_json::to_string(&project(&cli, &messages)?)?);
             } else {
-                println!("From: {}", msg.from.as_deref().unwrap_or("Unknown"));
-                println!(
-                    "Subject: {}",
-                    msg.subject.as_deref().unwrap_or("(no subject)")
-                );
-                if !msg.labels.is_empty() {
-                    println!("Labels: {}", msg.labels.join(", "));
+                if let Some(subject) = messages.first().and_then(|m| m.subject.as_deref()) {
+                    println!("Subject: {}", subject);
+                }
+                for msg in &messages {
+                    println!("---");
+                    println!("From: {}", msg.from.as_deref().unwrap_or("Unknown"));
+                    if let Some(date) = &msg.date {
+                        println!("Date: {}", date);
+                    }
+                    println!();
+                    println!("{}", msg.body.as_deref().unwrap_or(""));
                 }
-                println!("---");
-                println!("{}", msg.body.as_deref().unwrap_or(""));
             }
         }
-        Commands::Archive { id } => {
-            let client = Client::new(port);
-            client.archive(&id).await?;
-            println!("Archived: {}", id);
-        }
-        Commands::Delete { id } => {
-            let client = Client::new(port);
-            client.trash(&id).await?;
-            println!("Deleted: {}", id);
+        Commands::Block { id } => {
+            let client = Client::new(cfg);
+            client.block_sender(&id).await?;
+            report(
+                cli.json,
+                serde_json::json!({ "action": "block", "id": id }),
+                format!("Blocked sender of: {}", id),
+            );
         }
         Commands::Spam { id } => {
-            let client = Client::new(port);
+            let client = Client::new(cfg);
             client.mark_spam(&id).await?;
-            println!("Marked as spam: {}", id);
+            report(
+                cli.json,
+                serde_json::json!({ "action": "spam", "id": id }),
+                format!("Marked as spam: {}", id),
+            );
         }
-        Commands::Label { id, label } => {
-            let client = Client::new(port);
-            client.add_label(&id, &label).await?;
-            println!("Added label '{}' to: {}", label, id);
+        Commands::Archive { .. }
+        | Commands::Delete { .. }
+        | Commands::MarkRead { .. }
+        | Commands::MarkUnread { .. }
+        | Commands::Label { .. } => {
+            let client = Client::new(cfg);
+            let (result, text) = message_action(&client, &cli.command).await?;
+            report(cli.json, result, text);
         }
-        Commands::Unlabel { id, label } => {
-            let client = Client::new(port);
-            client.remove_label(&id, &label).await?;
-            println!("Removed label '{}' from: {}", label, id);
+        Commands::Unlabel { id, label, prefix } => {
+            let client = Client::new(cfg);
+            if prefix {
+                client.remove_label_by_prefix(&id, &label).await?;
+            } else {
+                client.remove_label(&id, &label).await?;
+            }
+            report(
+                cli.json,
+                serde_json::json!({ "action": "unlabel", "id": id, "label": label }),
+                format!("Removed label '{}' from: {}", label, id),
+            );
         }
-        Commands::Labels => {
-            let client = Client::new(port);
-            let labels = client.list_labels().await?;
+        Commands::ListFolders => {
+            let client = Client::new(cfg);
+            let folders = client.list_folders().await?;
+            if cli.json {
+                println!("{}", serde_json::to_string(&folders)?);
+            } else {
+                print_folders(&folders);
+            }
+        }
+        Commands::CreateFolder { name, parent } => {
+            let client = Client::new(cfg);
+            let created = client.create_folder(&name, parent.as_deref()).await?;
+            let place = parent
+                .as_deref()
+                .map_or(String::new(), |p| format!(" in {}", p));
+            report(
+                cli.json,
+                serde_json::json!({
+                    "action": "create-folder",
+                    "folder": name,
+                    "parent": parent,
+                    "created": created,
+                }),
+                if created {
+                    format!("Created folder: {}{}", name, place)
+                } else {
+                    format!("Folder already exists: {}{}", name, place)
+                },
+            );
+        }
+        Commands::Labels { refresh } => {
+            let client = Client::new(cfg);
+            let labels = if refresh {
+                client.list_labels().await?
+            } else {
+                client.labels_cached().await?
+            };
             if cli.json {
                 println!("{}", serde_json::to_string(&labels)?);
             } else {
@@ -219,81 +1319,623 @@ async fn main() -> Result<()> {
             }
         }
         Commands::Unspam { id } => {
-            let client = Client::new(port);
+            let client = Client::new(cfg);
             client.unspam(&id).await?;
-            println!("Moved to inbox: {}", id);
+            report(
+                cli.json,
+                serde_json::json!({ "action": "unspam", "id": id }),
+                format!("Moved to inbox: {}", id),
+            );
         }
-        Commands::MarkRead { id } => {
-            let client = Client::new(port);
-            client.mark_read(&id).await?;
-            println!("Marked as read: {}", id);
+        Commands::Restore { id } => {
+            let client = Client::new(cfg);
+            client.restore(&id).await?;
+            report(
+                cli.json,
+                serde_json::json!({ "action": "restore", "id": id }),
+                format!("Restored to inbox: {}", id),
+            );
         }
-        Commands::MarkUnread { id } => {
-            let client = Client::new(port);
-            client.mark_unread(&id).await?;
-            println!("Marked as unread: {}", id);
+        Commands::Snooze { id, when } => {
+            let client = Client::new(cfg);
+            client.snooze(&id, &when).await?;
+            report(
+                cli.json,
+                serde_json::json!({ "action": "snooze", "id": id }),
+                format!("Snoozed: {}", id),
+            );
+        }
+        Commands::Pin { id } => {
+            let client = Client::new(cfg);
+            client.set_pinned(&id, true).await?;
+            report(
+                cli.json,
+                serde_json::json!({ "action": "pin", "id": id }),
+                format!("Pinned: {}", id),
+            );
+        }
+        Commands::Unpin { id } => {
+            let client = Client::new(cfg);
+            client.set_pinned(&id, false).await?;
+            report(
+                cli.json,
+                serde_json::json!({ "action": "unpin", "id": id }),
+                format!("Unpinned: {}", id),
+            );
+        }
+        Commands::Focus { id, other, always } => {
+            let client = Client::new(cfg);
+            client.set_focused(&id, !other, always).await?;
+            let side = if other { "Other" } else { "Focused" };
+            report(
+                cli.json,
+                serde_json::json!({
+                    "action": "focus",
+                    "id": id,
+                    "inbox": side.to_lowercase(),
+                    "always": always,
+                }),
+                if always {
+                    format!("Moved to {} (always for this sender): {}", side, id)
+                } else {
+                    format!("Moved to {}: {}", side, id)
+                },
+            );
+        }
+        Commands::ArchiveRead {
+            folder,
+            max,
+            older_than,
+            dry_run,
+        } => {
+            let client = Client::new(cfg);
+            let messages = client
+                .archive_read(folder, max, older_than, dry_run)
+                .await?;
+            if cli.json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "action": "archive-read",
+                        "folder": folder.display_name(),
+                        "dryRun": dry_run,
+                        "count": messages.len(),
+                        "messages": project(&cli, &messages)?,
+                    })
+                );
+            } else {
+                if dry_run {
+                    for msg in &messages {
+                        println!("{}", output::format_line(msg, false));
+                    }
+                }
+                let verb = if dry_run { "Would archive" } else { "Archived" };
+                if !cli.quiet {
+                    println!(
+                        "{} {} read message(s) from {}",
+                        verb,
+                        messages.len(),
+                        folder.display_name()
+                    );
+                }
+            }
+        }
+        Commands::ConversationView { on, off: _ } => {
+            let client = Client::new(cfg);
+            let changed = client.set_conversation_view(on).await?;
+            let view = if on { "conversations" } else { "messages" };
+            report(
+                cli.json,
+                serde_json::json!({ "action": "conversation-view", "view": view, "changed": changed }),
+                match (changed, on) {
+                    (true, true) => "Message list now shows conversations",
+                    (true, false) => "Message list now shows individual messages",
+                    (false, true) => "Message list already shows conversations",
+                    (false, false) => "Message list already shows individual messages",
+                },
+            );
+        }
+        Commands::MarkAllRead { folder } => {
+            let client = Client::new(cfg);
+            let count = client.mark_all_read(folder).await?;
+            report(
+                cli.json,
+                serde_json::json!({
+                    "action": "mark-all-read",
+                    "folder": folder.display_name(),
+                    "count": count,
+                }),
+                format!(
+                    "Marked {} messages as read in {}",
+                    count,
+                    folder.display_name()
+                ),
+            );
         }
         Commands::ClearLabels { id } => {
-            let client = Client::new(port);
+            let client = Client::new(cfg);
             client.clear_labels(&id).await?;
-            println!("Cleared labels from: {}", id);
+            report(
+                cli.json,
+                serde_json::json!({ "action": "clear-labels", "id": id }),
+                format!("Cleared labels from: {}", id),
+            );
         }
         Commands::Unsubscribe { id } => {
-            let client = Client::new(port);
-            if let Some(url) = client.get_unsubscribe_url(&id).await? {
-                println!("Opening unsubscribe link: {}", url);
-                open::that(&url)?;
+            let client = Client::new(cfg);
+            let mut unsub = client
+                .get_unsubscribe(&id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("No unsubscribe link found in message"))?;
+            match unsub.url.clone() {
+                Some(url) if unsub.one_click => {
+                    outlook_web::api::one_click_unsubscribe(&url).await?;
+                    report(
+                        cli.json,
+                        serde_json::json!({ "action": "unsubscribe", "id": id, "unsubscribe": unsub }),
+                        format!("Unsubscribed (one-click): {}", url),
+                    );
+                }
+                _ if unsub.outlook_banner => {
+                    if !client.unsubscribe(&id).await? {
+                        anyhow::bail!(
+                            "Outlook's unsubscribe banner disappeared before it was clicked"
+                        );
+                    }
+                    unsub.handled_by_outlook = true;
+                    report(
+                        cli.json,
+                        serde_json::json!({ "action": "unsubscribe", "id": id, "unsubscribe": unsub }),
+                        "Unsubscribed via Outlook",
+                    );
+                }
+                Some(url) => {
+                    open::that(&url)?;
+                    report(
+                        cli.json,
+                        serde_json::json!({ "action": "unsubscribe", "id": id, "unsubscribe": unsub }),
+                        format!("Opened unsubscribe link: {}", url),
+                    );
+                }
+                None => anyhow::bail!("No unsubscribe link found in message"),
+            }
+        }
+        Commands::EmptyJunk { yes } => {
+            empty_folder(cfg, Folder::Junk, yes, cli.json).await?;
+        }
+        Commands::EmptyTrash { yes } => {
+            empty_folder(cfg, Folder::Deleted, yes, cli.json).await?;
+        }
+        Commands::Export {
+            id,
+            out,
+            format: ExportFormat::Eml,
+            native,
+        } => {
+            let client = Client::new(cfg);
+            if native {
+                let path = client.download_eml(&id, &out).await?;
+                report(
+                    cli.json,
+                    serde_json::json!({ "action": "export", "id": id, "path": path, "native": true }),
+                    format!("Downloaded {} to {}", id, path.display()),
+                );
+            } else {
+                client.export_eml(&id, &out).await?;
+                report(
+                    cli.json,
+                    serde_json::json!({ "action": "export", "id": id, "path": out, "native": false }),
+                    format!("Exported {} to {}", id, out.display()),
+                );
+            }
+        }
+        Commands::ExportMbox { folder, out, max } => {
+            let client = Client::new(cfg);
+            let export = client.export_mbox(folder, max, &out).await?;
+            let mut text = format!(
+                "Exported {} message(s) from {} to {} ({} already there)",
+                export.written,
+                folder.display_name(),
+                out.display(),
+                export.skipped
+            );
+            if !export.failed.is_empty() {
+                text.push_str(&format!("; {} could not be read", export.failed.len()));
+            }
+            let mut result = serde_json::to_value(&export)?;
+            result["action"] = "export-mbox".into();
+            result["folder"] = folder.display_name().into();
+            result["path"] = serde_json::to_value(&out)?;
+            report(cli.json, result, text);
+        }
+        Commands::Links { id, all } => {
+            let client = Client::new(cfg);
+            let links = client.get_links(&id, all).await?;
+            if cli.json {
+                println!("{}", serde_json::to_string(&links)?);
+            } else if links.is_empty() {
+                println!("No links found.");
+            } else {
+                for link in &links {
+                    if link.text.is_empty() {
+                        println!("{}", link.href);
+                    } else {
+                        println!("{} | {}", link.text, link.href);
+                    }
+                }
+            }
+        }
+        Commands::Batch {
+            action,
+            mut ids,
+            select,
+            yes,
+        } => {
+            if ids.is_empty() {
+                ids = std::io::stdin()
+                    .lines()
+                    .map(|line| Ok(line?.trim().to_string()))
+                    .filter(|line| !matches!(line, Ok(l) if l.is_empty()))
+                    .collect::<Result<_>>()?;
+            }
+            if ids.is_empty() {
+                anyhow::bail!("No message IDs given (pass them as arguments or on stdin)");
+            }
+            let client = Client::new(cfg);
+            if matches!(action, BatchAction::Delete) && !yes {
+                confirm_batch(&client, "delete", &ids).await?;
+            }
+            let name = match action {
+                BatchAction::Archive => "archive",
+                BatchAction::Delete => "delete",
+                BatchAction::MarkRead => "mark-read",
+            };
+            if select {
+                match action {
+                    BatchAction::Archive => client.archive_many(&ids).await?,
+                    BatchAction::Delete => client.trash_many(&ids).await?,
+                    BatchAction::MarkRead => client.mark_read_many(&ids).await?,
+                }
             } else {
-                anyhow::bail!("No unsubscribe link found in message");
+                let mut progress = outlook_web::progress::Progress::new(name, ids.len());
+                for id in &ids {
+                    match action {
+                        BatchAction::Archive => client.archive(id).await?,
+                        BatchAction::Delete => client.trash(id).await?,
+                        BatchAction::MarkRead => client.mark_read(id).await?,
+                    }
+                    progress.inc();
+                }
+            }
+            report(
+                cli.json,
+                serde_json::json!({ "action": name, "ids": ids, "count": ids.len() }),
+                format!("{}: {} message(s)", name, ids.len()),
+            );
+        }
+        Commands::MoveMany { folder, ids } => {
+            let client = Client::new(cfg);
+            client.move_many(&ids, folder).await?;
+            report(
+                cli.json,
+                serde_json::json!({ "action": "move", "folder": folder.display_name(), "ids": ids }),
+                format!(
+                    "Moved {} message(s) to {}",
+                    ids.len(),
+                    folder.display_name()
+                ),
+            );
+        }
+        Commands::CreateLabel { name, color } => {
+            let client = Client::new(cfg);
+            let created = client.create_label(&name, color.as_deref()).await?;
+            report(
+                cli.json,
+                serde_json::json!({ "action": "create-label", "label": name, "created": created }),
+                if created {
+                    format!("Created label: {}", name)
+                } else {
+                    format!("Label already exists: {}", name)
+                },
+            );
+        }
+        Commands::DeleteLabel { name, yes } => {
+            if !yes {
+                anyhow::bail!(
+                    "Refusing to delete label {} without --yes (it is removed from every message)",
+                    name
+                );
             }
+            let client = Client::new(cfg);
+            client.delete_label(&name).await?;
+            report(
+                cli.json,
+                serde_json::json!({ "action": "delete-label", "label": name }),
+                format!("Deleted label: {}", name),
+            );
         }
-        Commands::SyncLabels => {
-            let client = Client::new(port);
-            let known_labels = client.list_labels().await?;
+        Commands::SyncLabels { add } => {
             let known_set: std::collections::HashSet<String> =
-                known_labels.into_iter().map(|l| l.to_lowercase()).collect();
+                cfg.labels.iter().map(|l| l.to_lowercase()).collect();
 
-            // Scan messages for categories
-            let messages = client.list_messages(100).await?;
-            let mut found: std::collections::HashSet<String> = std::collections::HashSet::new();
+            // Categories defined in Outlook plus any seen on recent messages
+            let view = cfg.inbox_view();
+            let client = Client::new(cfg);
+            let mut discovered = client.list_labels().await?;
+            for msg in client.list_messages(100, view).await? {
+                discovered.extend(msg.labels);
+            }
 
-            for msg in &messages {
-                for label in &msg.labels {
-                    if !known_set.contains(&label.to_lowercase()) {
-                        found.insert(label.clone());
-                    }
+            let mut missing: Vec<String> = Vec::new();
+            for label in discovered {
+                let lower = label.to_lowercase();
+                if !known_set.contains(&lower) && !missing.iter().any(|m| m.to_lowercase() == lower)
+                {
+                    missing.push(label);
                 }
             }
 
-            if found.is_empty() {
+            if add && !missing.is_empty() {
+                // Reload so per-run CLI overrides aren't written to the config file
+                let mut stored = config::load_config(&config_path)?;
+                stored.labels.extend(missing.iter().cloned());
+                config::save_config(&config_path, &stored)?;
+            }
+
+            if cli.json {
+                report(
+                    true,
+                    serde_json::json!({ "missing": missing, "added": add }),
+                    "",
+                );
+            } else if missing.is_empty() {
                 println!("All categories are in the known list.");
+            } else if add {
+                println!("Added to known labels:");
+                for label in &missing {
+                    println!("  + {}", label);
+                }
             } else {
-                println!("Categories found on messages but not in known list:");
-                for label in found {
+                println!("Categories in Outlook but not in the known list:");
+                for label in &missing {
                     println!("  - {}", label);
                 }
-                println!("\nAdd these to list_labels() in src/api.rs");
+                println!("\nRun `outlook-web sync-labels --add` to add them");
             }
         }
+        Commands::Draft {
+            to,
+            subject,
+            body,
+            template,
+            vars,
+            attachments,
+            review,
+        } => {
+            let body = match (body, template) {
+                (Some(body), _) => body,
+                (None, Some(name)) => {
+                    let vars = vars.into_iter().collect();
+                    outlook_web::template::render(outlook_web::template::load(&cfg, &name)?, &vars)?
+                }
+                (None, None) => std::io::read_to_string(std::io::stdin())?,
+            };
+            let client = Client::new(cfg);
+            let draft = Draft {
+                to,
+                subject,
+                body,
+                attachments,
+            };
+            let id = if review {
+                client
+                    .save_draft_with_review(&draft, |resolved| {
+                        review_draft(resolved, &draft.subject, &draft.body, &draft.attachments)
+                    })
+                    .await?
+            } else {
+                client.save_draft(&draft).await?
+            };
+            report(
+                cli.json,
+                serde_json::json!({ "action": "draft", "id": id, "subject": draft.subject }),
+                format!("Saved draft: {}", id),
+            );
+        }
+        Commands::Drafts { max } => {
+            let client = Client::new(cfg);
+            let messages = client.list_drafts(max).await?;
+            print_messages(&cli, &messages, "No drafts.")?;
+        }
+        Commands::Contact { query } => {
+            let client = Client::new(cfg);
+            let contacts = client.find_contact(&query).await?;
+            if cli.json {
+                println!("{}", serde_json::to_string(&contacts)?);
+            } else if contacts.is_empty() {
+                println!("No contacts found.");
+            } else {
+                for contact in &contacts {
+                    match &contact.name {
+                        Some(name) => println!("{} <{}>", name, contact.email),
+                        None => println!("{}", contact.email),
+                    }
+                }
+            }
+        }
+        Commands::Undo => {
+            let client = Client::new(cfg);
+            client.undo().await?;
+            report(cli.json, serde_json::json!({ "action": "undo" }), "Undone");
+        }
+        Commands::Key { keys, to_message } => {
+            let client = Client::new(cfg);
+            client.send_keys(&keys, to_message.as_deref()).await?;
+            report(
+                cli.json,
+                serde_json::json!({ "action": "key", "keys": keys, "id": to_message }),
+                format!("Sent {}", keys.join(" ")),
+            );
+        }
+        Commands::Open { target } => {
+            let client = Client::new(cfg);
+            let opened = client.open(&target).await?;
+            let text = match &opened {
+                Opened::Folder { name } => format!("Opened folder: {}", name),
+                Opened::Message { id } => format!("Opened message: {}", id),
+            };
+            let mut result = serde_json::to_value(&opened)?;
+            result["action"] = "open".into();
+            report(cli.json, result, text);
+        }
+        Commands::Screenshot { out, full } => {
+            let client = Client::new(cfg);
+            client.screenshot(&out, full).await?;
+            report(
+                cli.json,
+                serde_json::json!({ "action": "screenshot", "path": out, "full": full }),
+                format!("Saved screenshot to {}", out.display()),
+            );
+        }
         Commands::Test => {
-            test_connection(port).await?;
+            test_connection(&cfg, cli.json).await?;
+        }
+        Commands::Serve => {
+            let Some(socket) = &cli.socket else {
+                anyhow::bail!("serve needs --socket <path> to listen on");
+            };
+            serve(socket).await?;
         }
-        Commands::Inspect => {
-            inspect_dom(port).await?;
+        Commands::Doctor => {
+            doctor(&cfg, cli.json).await?;
         }
+        Commands::Completions { .. } => unreachable!("handled before loading the config"),
+        Commands::Complete { what } => {
+            let client = Client::new(cfg);
+            let names = match what {
+                completions::Dynamic::Folders => client
+                    .list_folders()
+                    .await?
+                    .into_iter()
+                    .map(|f| f.name)
+                    .collect(),
+                completions::Dynamic::Labels => client.labels_cached().await?,
+            };
+            for name in names {
+                println!("{}", name);
+            }
+        }
+        Commands::Inspect {
+            selector,
+            raw,
+            max_len,
+        } => {
+            if raw {
+                inspect_raw(&cfg, max_len, cli.json).await?;
+            } else {
+                inspect_dom(&cfg, selector.as_deref(), cli.json).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Number of messages `confirm_batch` shows before summarizing the rest
+const CONFIRM_PREVIEW: usize = 5;
+
+/// Show what a destructive batch will touch and require "yes" typed on the terminal
+/// Reads the answer from the terminal, not stdin, since stdin may have carried the IDs.
+async fn confirm_batch(client: &Client, action: &str, ids: &[String]) -> Result<()> {
+    let preview = &ids[..ids.len().min(CONFIRM_PREVIEW)];
+    let listed = client.find_listed(preview).await?;
+
+    eprintln!("About to {} {} message(s):", action, ids.len());
+    for (id, msg) in preview.iter().zip(&listed) {
+        let subject = msg
+            .as_ref()
+            .and_then(|m| m.subject.as_deref())
+            .unwrap_or("(not in the current list)");
+        eprintln!("  {} | {}", id, subject);
+    }
+    if ids.len() > preview.len() {
+        eprintln!("  ... and {} more", ids.len() - preview.len());
     }
 
+    if !confirm_on_tty("Type yes to continue: ")? {
+        anyhow::bail!("Aborted; nothing was changed");
+    }
     Ok(())
 }
 
-async fn test_connection(port: u16) -> Result<()> {
-    let browser_instance = browser::connect_or_start_browser(port).await?;
+/// Print a filled-in draft to stderr and ask whether to save it
+fn review_draft(
+    recipients: &[String],
+    subject: &str,
+    body: &str,
+    attachments: &[std::path::PathBuf],
+) -> Result<bool> {
+    if recipients.is_empty() {
+        eprintln!("To: (no recipients resolved)");
+    } else {
+        eprintln!("To: {}", recipients.join(", "));
+    }
+    eprintln!("Subject: {}", subject);
+    for path in attachments {
+        eprintln!("Attachment: {}", path.display());
+    }
+    eprintln!("\n{}\n", body);
+    confirm_on_tty("Type yes to save this draft: ")
+}
+
+/// Prompt on the controlling terminal and report whether the answer was "yes"
+fn confirm_on_tty(prompt: &str) -> Result<bool> {
+    use std::io::{BufRead, Write};
+
+    let tty_path = if cfg!(windows) { "CONIN$" } else { "/dev/tty" };
+    let tty = std::fs::File::open(tty_path)
+        .map_err(|_| anyhow::anyhow!("No terminal to confirm on; pass --yes to skip the prompt"))?;
+
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::BufReader::new(tty).read_line(&mut answer)?;
+    Ok(answer.trim().eq_ignore_ascii_case("yes"))
+}
+
+async fn empty_folder(cfg: config::Config, folder: Folder, yes: bool, json: bool) -> Result<()> {
+    if !yes {
+        anyhow::bail!(
+            "Refusing to empty {} without --yes (this permanently deletes messages)",
+            folder.display_name()
+        );
+    }
+
+    let client = Client::new(cfg);
+    let count = client.empty_folder(folder).await?;
+    let text = match count {
+        Some(count) => format!("Emptied {} ({} messages)", folder.display_name(), count),
+        None => format!("Emptied {}", folder.display_name()),
+    };
+    report(
+        json,
+        serde_json::json!({ "action": "empty", "folder": folder.display_name(), "count": count }),
+        text,
+    );
+    Ok(())
+}
+
+async fn test_connection(cfg: &config::Config, json: bool) -> Result<()> {
+    let browser_instance = browser::connect_or_start_browser(cfg).await?;
     let pages = browser_instance.pages().await?;
 
-    println!("Connected to browser successfully!");
-    println!("Found {} pages:", pages.len());
+    if !json {
+        println!("Connected to browser successfully!");
+        println!("Found {} pages:", pages.len());
+    }
 
     let timeout = std::time::Duration::from_secs(2);
+    let mut page_info = Vec::new();
     for page in &pages {
         let url_result = tokio::time::timeout(timeout, page.url()).await;
         let url = match url_result {
@@ -302,7 +1944,7 @@ async fn test_connection(port: u16) -> Result<()> {
             Ok(Err(_)) => "(error getting url)".to_string(),
             Err(_) => "(timeout getting url)".to_string(),
         };
-        let is_outlook = url.contains("outlook");
+        let is_outlook = cfg.is_outlook_url(&url);
         let marker = if is_outlook { " <-- Outlook" } else { "" };
         let title = tokio::time::timeout(timeout, page.evaluate("document.title"))
             .await
@@ -310,29 +1952,496 @@ async fn test_connection(port: u16) -> Result<()> {
             .and_then(|r| r.ok())
             .and_then(|r| r.into_value::<String>().ok())
             .unwrap_or_else(|| "(no title)".to_string());
-        println!("  {} [{}]{}", title, url, marker);
+        if !json {
+            println!("  {} [{}]{}", title, url, marker);
+        }
+        page_info.push(serde_json::json!({ "title": title, "url": url, "outlook": is_outlook }));
     }
 
-    match browser::find_outlook_page(&browser_instance).await {
-        Ok(page) => {
-            let url = tokio::time::timeout(timeout, page.url())
+    let outlook_url = match browser::find_outlook_page(&browser_instance, cfg).await {
+        Ok(page) => Some(
+            tokio::time::timeout(timeout, page.url())
                 .await
                 .ok()
                 .and_then(|r| r.ok())
                 .flatten()
-                .unwrap_or_default();
-            println!("\nOutlook tab found: {}", url);
+                .unwrap_or_default(),
+        ),
+        Err(_) => None,
+    };
+
+    // Number the Outlook tabs so one can be picked with --tab
+    let mut tabs = Vec::new();
+    for page in browser::find_outlook_pages(&browser_instance, cfg).await? {
+        let url = tokio::time::timeout(timeout, page.url())
+            .await
+            .ok()
+            .and_then(|r| r.ok())
+            .flatten()
+            .unwrap_or_default();
+        let account = browser::page_account(&page).await.ok().flatten();
+        tabs.push(serde_json::json!({ "url": url, "account": account }));
+    }
+    if !json && tabs.len() > 1 {
+        println!("\nOutlook tabs (select with --tab or --account):");
+        for (i, tab) in tabs.iter().enumerate() {
+            let account = tab["account"].as_str().unwrap_or("unknown account");
+            println!(
+                "  [{}] {} ({})",
+                i,
+                tab["url"].as_str().unwrap_or(""),
+                account
+            );
         }
-        Err(_) => {
-            println!("\nNo Outlook tab found. Open Outlook in the browser.");
+    }
+
+    let text = match &outlook_url {
+        Some(url) => format!("\nOutlook tab found: {}", url),
+        None => "\nNo Outlook tab found. Open Outlook in the browser.".to_string(),
+    };
+    report(
+        json,
+        serde_json::json!({ "pages": page_info, "outlook_tabs": tabs, "outlook_tab": outlook_url }),
+        text,
+    );
+
+    Ok(())
+}
+
+/// Send this invocation's arguments to a `serve` process and print its JSON result
+#[cfg(unix)]
+async fn forward(socket: &std::path::Path) -> Result<()> {
+    use anyhow::Context;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let stream = tokio::net::UnixStream::connect(socket)
+        .await
+        .with_context(|| format!("No server on {} (start one with `serve`)", socket.display()))?;
+    let (read, mut write) = stream.into_split();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut request = serde_json::to_vec(&serde_json::json!({ "args": args }))?;
+    request.push(b'\n');
+    write.write_all(&request).await?;
+
+    let line = BufReader::new(read)
+        .lines()
+        .next_line()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Server closed the connection without answering"))?;
+    let response: serde_json::Value = serde_json::from_str(&line)?;
+    if response["ok"] != true {
+        anyhow::bail!("{}", response["error"].as_str().unwrap_or("request failed"));
+    }
+    println!("{}", response["result"]);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn forward(_socket: &std::path::Path) -> Result<()> {
+    anyhow::bail!("--socket is only supported on Unix")
+}
+
+/// Listen on `socket` for newline-delimited JSON requests `{"args": [...]}`, one command each
+/// Requests run one at a time over a single kept-alive browser connection, and each gets a
+/// `{"ok": true, "result": ...}` or `{"ok": false, "error": ...}` line back.
+#[cfg(unix)]
+async fn serve(socket: &std::path::Path) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    // A socket file left by a previous server would make bind fail; remove it only when
+    // it is a socket nobody answers on
+    if let Ok(meta) = std::fs::symlink_metadata(socket) {
+        use std::os::unix::fs::FileTypeExt;
+        if !meta.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a socket", socket.display());
         }
+        if std::os::unix::net::UnixStream::connect(socket).is_ok() {
+            anyhow::bail!(
+                "Another server is already listening on {}",
+                socket.display()
+            );
+        }
+        std::fs::remove_file(socket)?;
     }
+    let listener = tokio::net::UnixListener::bind(socket)?;
+    browser::keep_connection();
+    eprintln!("Listening on {}", socket.display());
+
+    // Every request drives the same tab, so they must not interleave
+    let lock = std::sync::Arc::new(tokio::sync::Mutex::new(()));
+    let result = loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => break Err(e.into()),
+            },
+            _ = tokio::signal::ctrl_c() => break Ok(()),
+        };
+        let lock = lock.clone();
+        tokio::spawn(async move {
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let response = {
+                    let _guard = lock.lock().await;
+                    match handle_request(&line).await {
+                        Ok(result) => serde_json::json!({ "ok": true, "result": result }),
+                        Err(e) => serde_json::json!({ "ok": false, "error": format!("{:#}", e) }),
+                    }
+                };
+                let mut out = response.to_string();
+                out.push('\n');
+                if write.write_all(out.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+        });
+    };
+
+    let _ = std::fs::remove_file(socket);
+    result
+}
+
+#[cfg(not(unix))]
+async fn serve(_socket: &std::path::Path) -> Result<()> {
+    anyhow::bail!("serve is only supported on Unix")
+}
+
+/// Parse one `serve` request as a command line and run it, returning its JSON result
+#[cfg(unix)]
+async fn handle_request(line: &str) -> Result<serde_json::Value> {
+    #[derive(serde::Deserialize)]
+    struct Request {
+        args: Vec<String>,
+    }
+
+    let request: Request = serde_json::from_str(line)?;
+    let cli = Cli::try_parse_from(std::iter::once("outlook-web".to_string()).chain(request.args))
+        .map_err(|e| anyhow::anyhow!("{}", e.render().to_string().trim()))?;
+    output::validate_fields(&cli.fields)?;
+    let cfg = load_config(&cli, &config::config_path(cli.config.as_deref())?)?;
+    let fields = cli.fields.clone();
+    let project = |messages: &[Message]| -> Result<serde_json::Value> {
+        let values = messages
+            .iter()
+            .map(|m| output::project(m, &fields))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(values.into())
+    };
 
+    let inbox_view = cfg.inbox_view();
+    let client = Client::new(cfg);
+    match cli.command {
+        Commands::List {
+            max,
+            sort,
+            reverse,
+            from,
+            subject,
+            other,
+            all,
+            group_by: None,
+        } => {
+            let view = list_view(other, all, inbox_view);
+            let filter = ListFilter { from, subject };
+            project(&list_filtered(&client, max, view, &filter, sort, reverse).await?)
+        }
+        Commands::Unread { max, folder } => project(&client.list_unread(folder, max).await?),
+        Commands::Flagged { max } => project(&client.list_flagged(max).await?),
+        Commands::Count { folder, unread } => {
+            Ok(serde_json::json!({ "count": client.count(folder, unread).await? }))
+        }
+        Commands::Stats { max, top } => {
+            let view = inbox_view;
+            let messages = client.list_messages(max, view).await?;
+            Ok(serde_json::to_value(stats::summarize(&messages, top))?)
+        }
+        Commands::Status { id } => Ok(serde_json::to_value(client.message_status(&id).await?)?),
+        Commands::Read {
+            id,
+            subject,
+            from,
+            first,
+            text,
+            keep_unread,
+            ..
+        } => {
+            let id = read_target(&client, id, ListFilter { from, subject }, first).await?;
+            output::project(
+                &read_message(&client, &id, None, keep_unread, text).await?,
+                &fields,
+            )
+        }
+        Commands::Next {
+            ref id,
+            text,
+            keep_unread,
+            ..
+        }
+        | Commands::Prev {
+            ref id,
+            text,
+            keep_unread,
+            ..
+        } => {
+            let direction = if matches!(cli.command, Commands::Next { .. }) {
+                Direction::Next
+            } else {
+                Direction::Previous
+            };
+            output::project(
+                &read_message(&client, id, Some(direction), keep_unread, text).await?,
+                &fields,
+            )
+        }
+        Commands::Archive { .. }
+        | Commands::Delete { .. }
+        | Commands::MarkRead { .. }
+        | Commands::MarkUnread { .. }
+        | Commands::Label { .. } => Ok(message_action(&client, &cli.command).await?.0),
+        _ => anyhow::bail!(
+            "Not available through serve; supported: list, unread, flagged, count, stats, \
+            status, read, next, prev, archive, delete, mark-read, mark-unread, label"
+        ),
+    }
+}
+
+/// The inbox pivot `list` shows: Other or All when asked for, else the configured default
+fn list_view(other: bool, all: bool, default: InboxView) -> InboxView {
+    if other {
+        InboxView::Other
+    } else if all {
+        InboxView::All
+    } else {
+        default
+    }
+}
+
+/// Messages for `list`: the inbox pivot, narrowed by `filter` and optionally sorted
+async fn list_filtered(
+    client: &Client,
+    max: u32,
+    view: InboxView,
+    filter: &ListFilter,
+    sort: Option<output::SortField>,
+    reverse: bool,
+) -> Result<Vec<Message>> {
+    let mut messages = client.list_messages(max, view).await?;
+    messages.retain(|msg| msg.matches(filter));
+    if let Some(field) = sort {
+        output::sort_messages(&mut messages, field, reverse);
+    }
+    Ok(messages)
+}
+
+/// The message `read` should open: `id` if given, else the one listed message matching `filter`
+/// Several matches are an error listing them, unless `first` picks the first.
+async fn read_target(
+    client: &Client,
+    id: Option<String>,
+    filter: ListFilter,
+    first: bool,
+) -> Result<String> {
+    if let Some(id) = id {
+        return Ok(id);
+    }
+    let matches = client.find_messages(&filter).await?;
+    match matches.as_slice() {
+        [] => anyhow::bail!("No listed message matches"),
+        [only] => Ok(only.id.clone()),
+        [head, ..] if first => Ok(head.id.clone()),
+        several => {
+            let candidates: Vec<String> = several
+                .iter()
+                .map(|m| output::format_line(m, false))
+                .collect();
+            anyhow::bail!(
+                "{} messages match; narrow the search, pass --first, or read one by ID:\n{}",
+                several.len(),
+                candidates.join("\n")
+            );
+        }
+    }
+}
+
+/// Read `id`, or with `direction` the message next to it, for `read`, `next` and `prev`
+/// With `text`, the body is rebuilt from the HTML with links as footnotes.
+async fn read_message(
+    client: &Client,
+    id: &str,
+    direction: Option<Direction>,
+    keep_unread: bool,
+    text: bool,
+) -> Result<Message> {
+    let mut msg = match direction {
+        Some(direction) => client.read_adjacent(id, direction, keep_unread).await?,
+        None if keep_unread => client.peek_message(id).await?,
+        None => client.get_message(id).await?,
+    };
+    if text && let Some(body_html) = &msg.body_html {
+        msg.body = Some(outlook_web::html::to_text(body_html));
+    }
+    Ok(msg)
+}
+
+/// Run one of the single-message actions (archive, delete, mark-read, mark-unread, label)
+/// Returns the JSON result and the line printed for it.
+async fn message_action(
+    client: &Client,
+    command: &Commands,
+) -> Result<(serde_json::Value, String)> {
+    Ok(match command {
+        Commands::Archive { id } => {
+            client.archive(id).await?;
+            (
+                serde_json::json!({ "action": "archive", "id": id }),
+                format!("Archived: {}", id),
+            )
+        }
+        Commands::Delete { id } => {
+            client.trash(id).await?;
+            (
+                serde_json::json!({ "action": "delete", "id": id }),
+                format!("Deleted: {}", id),
+            )
+        }
+        Commands::MarkRead { id } => {
+            client.mark_read(id).await?;
+            (
+                serde_json::json!({ "action": "mark-read", "id": id }),
+                format!("Marked as read: {}", id),
+            )
+        }
+        Commands::MarkUnread { id } => {
+            client.mark_unread(id).await?;
+            (
+                serde_json::json!({ "action": "mark-unread", "id": id }),
+                format!("Marked as unread: {}", id),
+            )
+        }
+        Commands::Label { id, label, prefix } => {
+            if *prefix {
+                client.add_label_by_prefix(id, label).await?;
+            } else {
+                client.add_label(id, label).await?;
+            }
+            (
+                serde_json::json!({ "action": "label", "id": id, "label": label }),
+                format!("Added label '{}' to: {}", label, id),
+            )
+        }
+        _ => unreachable!("not a single-message action"),
+    })
+}
+
+async fn doctor(cfg: &config::Config, json: bool) -> Result<()> {
+    let checks = outlook_web::doctor::diagnose(cfg).await;
+    let failed = checks.iter().filter(|c| c.critical && !c.ok).count();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "ok": failed == 0, "checks": checks })
+        );
+        // The checklist already carries the failure; don't print a second error object
+        if failed > 0 {
+            return Err(Reported.into());
+        }
+        return Ok(());
+    }
+
+    for check in &checks {
+        let mark = match (check.ok, check.critical) {
+            (true, _) => "ok  ",
+            (false, true) => "FAIL",
+            (false, false) => "warn",
+        };
+        println!("[{}] {:<12} {}", mark, check.name, check.detail);
+    }
+    if failed > 0 {
+        anyhow::bail!(
+            "{} critical check{} failed",
+            failed,
+            if failed == 1 { "" } else { "s" }
+        );
+    }
+    println!("\nAll critical checks passed.");
+    Ok(())
+}
+
+async fn inspect_raw(cfg: &config::Config, max_len: usize, json: bool) -> Result<()> {
+    let dom = outlook_web::inspect::inspect_raw(cfg, max_len).await?;
+    if json {
+        report(true, serde_json::json!({ "raw": dom }), "");
+        return Ok(());
+    }
+    println!("=== Reading pane ===");
+    println!(
+        "{}",
+        dom.reading_pane
+            .as_deref()
+            .unwrap_or("(no reading pane open)")
+    );
+    for (i, item) in dom.list_items.iter().enumerate() {
+        println!();
+        println!("=== List item {} ===", i + 1);
+        println!("{}", item);
+    }
     Ok(())
 }
 
-async fn inspect_dom(port: u16) -> Result<()> {
-    let info = outlook_web::inspect::inspect_dom(port).await?;
-    println!("{}", info);
+async fn inspect_dom(cfg: &config::Config, selector: Option<&str>, json: bool) -> Result<()> {
+    let info = outlook_web::inspect::inspect_dom(cfg, selector).await?;
+    if json {
+        // Already JSON; parse so it gets the same "ok" envelope as other commands
+        let info: serde_json::Value = serde_json::from_str(&info)?;
+        report(true, serde_json::json!({ "inspect": info }), "");
+    } else {
+        println!("{}", info);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_code_unwrapped() {
+        assert_eq!(exit_code(&Error::NotAuthenticated.into()), 3);
+        assert_eq!(
+            exit_code(
+                &Error::Timeout {
+                    what: "Command".into(),
+                    secs: 5
+                }
+                .into()
+            ),
+            124
+        );
+        assert_eq!(exit_code(&anyhow::anyhow!("boom")), 1);
+    }
+
+    #[test]
+    fn exit_code_wrapped() {
+        // What a `Client` method returns: the typed error under context, inside `Other`
+        let inner = anyhow::Error::from(Error::Timeout {
+            what: "Script".into(),
+            secs: 30,
+        })
+        .context("Failed to parse message");
+        let outer = anyhow::Error::from(Error::Other(inner)).context("read");
+        assert_eq!(exit_code(&outer), 124);
+
+        let signed_out = anyhow::Error::from(Error::Other(Error::NotAuthenticated.into()));
+        assert_eq!(exit_code(&signed_out), 3);
+
+        let missing =
+            anyhow::Error::from(Error::MessageNotFound { id: "x".into() }).context("archive");
+        assert_eq!(exit_code(&missing), 5);
+
+        let other = anyhow::Error::from(Error::msg("no such folder")).context("open");
+        assert_eq!(exit_code(&other), 1);
+    }
+}
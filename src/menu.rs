@@ -1,4 +1,6 @@
-use anyhow::Result;
+use crate::browser::{evaluate, evaluate_json, evaluate_with_args};
+use crate::strings::localize;
+use anyhow::{Context, Result};
 use chromiumoxide::Page;
 use chromiumoxide::cdp::browser_protocol::input::{
     DispatchMouseEventParams, DispatchMouseEventType, MouseButton,
@@ -6,92 +8,134 @@ use chromiumoxide::cdp::browser_protocol::input::{
 
 /// Open context menu on an element with retry logic for slower computers
 pub async fn open_context_menu(page: &Page, selector: &str) -> Result<()> {
-    const MAX_ATTEMPTS: u32 = 3;
-
-    for attempt in 1..=MAX_ATTEMPTS {
+    crate::retry::with_retry(|| async {
         right_click_element(page, selector, Some(500)).await?;
 
-        if is_context_menu_open(page).await? {
-            return Ok(());
-        }
-
-        if attempt < MAX_ATTEMPTS {
-            tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+        if !is_context_menu_open(page).await? {
+            anyhow::bail!("Context menu didn't open");
         }
-    }
-
-    anyhow::bail!("Context menu didn't open after {} attempts", MAX_ATTEMPTS)
+        Ok(())
+    })
+    .await
 }
 
 /// Open context menu at coordinates with retry logic for slower computers
 pub async fn open_context_menu_at(page: &Page, x: f64, y: f64) -> Result<()> {
-    const MAX_ATTEMPTS: u32 = 3;
-
-    for attempt in 1..=MAX_ATTEMPTS {
+    crate::retry::with_retry(|| async {
         right_click(page, x, y, Some(500)).await?;
 
-        if is_context_menu_open(page).await? {
-            return Ok(());
+        if !is_context_menu_open(page).await? {
+            anyhow::bail!("Context menu didn't open");
         }
-
-        if attempt < MAX_ATTEMPTS {
-            tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
-        }
-    }
-
-    anyhow::bail!("Context menu didn't open after {} attempts", MAX_ATTEMPTS)
+        Ok(())
+    })
+    .await
 }
 
-/// Right-click on an element by selector
-pub async fn right_click_element(page: &Page, selector: &str, sleep_ms: Option<u64>) -> Result<()> {
+/// Center of the first element matching `selector`, in viewport coordinates
+async fn element_center(page: &Page, selector: &str) -> Result<(f64, f64)> {
     let script = format!(
         r#"
         (() => {{
-            const item = document.querySelector('{}');
+            const item = document.querySelector({});
             if (!item) return false;
             const rect = item.getBoundingClientRect();
             return {{ x: rect.x + rect.width / 2, y: rect.y + rect.height / 2 }};
         }})()
         "#,
-        selector
+        serde_json::to_string(selector)?
     );
 
-    let result = page.evaluate(script).await?;
+    let result = evaluate(page, "element_center", script).await?;
     let pos: Option<serde_json::Value> = result.into_value().ok();
 
-    let (x, y) = pos
-        .and_then(|p| {
-            let x = p.get("x").and_then(|v| v.as_f64())?;
-            let y = p.get("y").and_then(|v| v.as_f64())?;
-            Some((x, y))
-        })
-        .ok_or_else(|| anyhow::anyhow!("Element not found: {}", selector))?;
+    pos.and_then(|p| {
+        let x = p.get("x").and_then(|v| v.as_f64())?;
+        let y = p.get("y").and_then(|v| v.as_f64())?;
+        Some((x, y))
+    })
+    .ok_or_else(|| anyhow::anyhow!("Element not found: {}", selector))
+}
 
+/// Right-click on an element by selector
+pub async fn right_click_element(page: &Page, selector: &str, sleep_ms: Option<u64>) -> Result<()> {
+    let (x, y) = element_center(page, selector).await?;
     right_click(page, x, y, sleep_ms).await
 }
 
+/// Left-click on an element by selector with real CDP mouse events
+/// Unlike `browser::click_element`, the click is trusted, for handlers that ignore synthetic ones
+pub async fn left_click_element(page: &Page, selector: &str, sleep_ms: Option<u64>) -> Result<()> {
+    let (x, y) = element_center(page, selector).await?;
+    mouse_click(page, x, y, MouseButton::Left, sleep_ms).await
+}
+
 /// Click on a menu item by text (partial match, case-insensitive)
 pub async fn click_menu_item(page: &Page, text: &str, sleep_ms: Option<u64>) -> Result<()> {
-    let script = format!(
-        r#"
-        (() => {{
+    if !try_click_menu_item(page, text, sleep_ms).await? {
+        anyhow::bail!("Menu item not found: {}", text);
+    }
+    Ok(())
+}
+
+/// `click_menu_item` that returns false when no item matches
+/// Errors only when the page couldn't be queried, so callers can tell "not there" apart.
+pub async fn try_click_menu_item(page: &Page, text: &str, sleep_ms: Option<u64>) -> Result<bool> {
+    let script = r#"
+        (text) => {
             const items = document.querySelectorAll('[role="menuitem"], [role="menuitemcheckbox"]');
-            for (const item of items) {{
+            for (const item of items) {
                 const itemText = item.textContent?.toLowerCase() || '';
-                if (itemText.includes('{}')) {{
+                if (itemText.includes(text)) {
                     item.click();
                     return true;
-                }}
-            }}
+                }
+            }
             return false;
-        }})()
-        "#,
-        text.to_lowercase()
-    );
+        }
+        "#;
+
+    let result =
+        evaluate_with_args(page, "click_menu_item", script, &[localize(text).into()]).await?;
+    let clicked = result.into_value::<bool>().unwrap_or(false);
+
+    crate::debug!("click_menu_item {:?}: clicked={}", text, clicked);
+    if !clicked {
+        return Ok(false);
+    }
+
+    let ms = sleep_ms.unwrap_or(300);
+    tokio::time::sleep(tokio::time::Duration::from_millis(ms)).await;
 
-    let result = page.evaluate(script).await?;
+    Ok(true)
+}
+
+/// Click the menu item whose whole text is `text` (case-insensitive)
+/// For items that are a prefix of others, like "Move to Other" and "Always move to Other".
+pub async fn click_menu_item_exact(page: &Page, text: &str, sleep_ms: Option<u64>) -> Result<()> {
+    let script = r#"
+        (text) => {
+            const items = document.querySelectorAll('[role="menuitem"], [role="menuitemcheckbox"]');
+            for (const item of items) {
+                if ((item.textContent?.trim().toLowerCase() || '') === text) {
+                    item.click();
+                    return true;
+                }
+            }
+            return false;
+        }
+        "#;
+
+    let result = evaluate_with_args(
+        page,
+        "click_menu_item_exact",
+        script,
+        &[text.to_lowercase().into()],
+    )
+    .await?;
     let clicked = result.into_value::<bool>().unwrap_or(false);
 
+    crate::debug!("click_menu_item_exact {:?}: clicked={}", text, clicked);
     if !clicked {
         anyhow::bail!("Menu item not found: {}", text);
     }
@@ -102,27 +146,123 @@ pub async fn click_menu_item(page: &Page, text: &str, sleep_ms: Option<u64>) ->
     Ok(())
 }
 
-/// Check if the category submenu is open and the specific category is visible
-pub async fn is_category_visible(page: &Page, label: &str) -> Result<bool> {
+/// JavaScript function to find a category menu item by name
+/// Tries an exact match, then case-insensitive, then a unique prefix; a prefix shared by
+/// several categories is reported as ambiguous rather than picking one
+const FIND_CATEGORY_JS: &str = r#"
+    function findCategory(label, prefix) {
+        const items = Array.from(document.querySelectorAll('[role="menuitemcheckbox"], [role="menuitem"]'));
+        // Strip the leading color icon glyph from the menu text
+        const nameOf = item => (item.textContent?.trim() || '').replace(/^[^\p{L}\p{N}]+/u, '');
+        const lower = label.toLowerCase();
+
+        const found = items.find(item => nameOf(item).toLowerCase() === lower);
+        if (found) return { status: 'found', item: found, candidates: [nameOf(found)] };
+        if (!prefix) return { status: 'not_found', item: null, candidates: [] };
+
+        // Only real categories (checkbox items) take part in prefix matching
+        const prefixed = items
+            .filter(item => item.getAttribute('role') === 'menuitemcheckbox')
+            .filter(item => nameOf(item).toLowerCase().startsWith(lower));
+        if (prefixed.length === 1) return { status: 'found', item: prefixed[0], candidates: [nameOf(prefixed[0])] };
+        if (prefixed.length > 1) return { status: 'ambiguous', item: null, candidates: prefixed.map(nameOf) };
+        return { status: 'not_found', item: null, candidates: [] };
+    }
+"#;
+
+/// Check if the category submenu is open and a category matching `label` is visible
+/// Names match exactly, ignoring case, or with `prefix` also by a unique prefix.
+/// An ambiguous match counts as visible so that `click_category` can report it
+pub async fn is_category_visible(page: &Page, label: &str, prefix: bool) -> Result<bool> {
     let script = format!(
         r#"
-        (() => {{
-            const items = document.querySelectorAll('[role="menuitemcheckbox"], [role="menuitem"]');
-            for (const item of items) {{
-                if (item.textContent?.endsWith('{}')) {{
-                    return true;
-                }}
-            }}
-            return false;
-        }})()
+        (label, prefix) => {{
+            {find_category}
+            return findCategory(label, prefix).status !== 'not_found';
+        }}
         "#,
-        label
+        find_category = FIND_CATEGORY_JS
     );
 
-    let result = page.evaluate(script).await?;
+    let result = evaluate_with_args(
+        page,
+        "is_category_visible",
+        &script,
+        &[label.into(), prefix.into()],
+    )
+    .await?;
     Ok(result.into_value::<bool>().unwrap_or(false))
 }
 
+/// Outcome of a `context_menu_action`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionStatus {
+    /// Clicked this item (the submenu item, when there was a submenu)
+    Clicked(String),
+    /// The context menu had none of these items
+    MissingItem(Vec<String>),
+    /// The submenu opened but had none of these items
+    MissingSubmenuItem(Vec<String>),
+}
+
+impl ActionStatus {
+    /// The clicked item, or an error naming what was missing
+    pub fn ensure(self) -> Result<String> {
+        match self {
+            ActionStatus::Clicked(item) => Ok(item),
+            ActionStatus::MissingItem(items) => {
+                anyhow::bail!("Menu item not found: {}", items.join(" / "))
+            }
+            ActionStatus::MissingSubmenuItem(items) => {
+                anyhow::bail!("Submenu item not found: {}", items.join(" / "))
+            }
+        }
+    }
+}
+
+/// Right-click a message, click the first of `items` the menu offers, then the first of
+/// `submenu` if given
+/// Menus are closed again when an item is missing, so the caller can try something else.
+pub async fn context_menu_action(
+    page: &Page,
+    id: &str,
+    items: &[&str],
+    submenu: Option<&[&str]>,
+) -> Result<ActionStatus> {
+    open_context_menu(page, &crate::browser::message_selector(id)).await?;
+
+    // Give a submenu time to open before looking for its items
+    let sleep = submenu.map(|_| 500);
+    let Some(clicked) = click_first(page, items, sleep).await? else {
+        close_menus(page).await?;
+        return Ok(ActionStatus::MissingItem(to_strings(items)));
+    };
+    let Some(submenu) = submenu else {
+        return Ok(ActionStatus::Clicked(clicked));
+    };
+    match click_first(page, submenu, None).await? {
+        Some(clicked) => Ok(ActionStatus::Clicked(clicked)),
+        None => {
+            close_menus(page).await?;
+            Ok(ActionStatus::MissingSubmenuItem(to_strings(submenu)))
+        }
+    }
+}
+
+/// Click the first of `items` present in the open menu, returning which one
+async fn click_first(page: &Page, items: &[&str], sleep_ms: Option<u64>) -> Result<Option<String>> {
+    for item in items {
+        if try_click_menu_item(page, item, sleep_ms).await? {
+            return Ok(Some(item.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+fn to_strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
 /// Check if a context menu is open (any menu with menuitems)
 pub async fn is_context_menu_open(page: &Page) -> Result<bool> {
     let script = r#"
@@ -132,7 +272,7 @@ pub async fn is_context_menu_open(page: &Page) -> Result<bool> {
         })()
     "#;
 
-    let result = page.evaluate(script).await?;
+    let result = evaluate(page, "is_context_menu_open", script).await?;
     Ok(result.into_value::<bool>().unwrap_or(false))
 }
 
@@ -144,31 +284,52 @@ pub async fn close_menus(page: &Page) -> Result<()> {
         tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
     }
     // Click somewhere neutral to deselect any focused elements
-    page.evaluate("document.body.click()").await?;
+    evaluate(page, "close_menus:body_click", "document.body.click()").await?;
     tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
     Ok(())
 }
 
 /// Check if the Categorize button is visible in the context menu
 pub async fn is_categorize_button_visible(page: &Page) -> Result<bool> {
-    let script = r#"
-        (() => {
+    let script = format!(
+        r#"
+        (() => {{
             const items = document.querySelectorAll('[role="menuitem"]');
-            for (const item of items) {
-                if (item.textContent?.toLowerCase().includes('categor')) {
+            for (const item of items) {{
+                if (item.textContent?.toLowerCase().includes({text})) {{
                     return true;
-                }
-            }
+                }}
+            }}
             return false;
-        })()
-    "#;
+        }})()
+    "#,
+        text = serde_json::to_string(&localize("categor"))?
+    );
 
-    let result = page.evaluate(script).await?;
+    let result = evaluate(page, "is_categorize_button_visible", script).await?;
     Ok(result.into_value::<bool>().unwrap_or(false))
 }
 
 /// Right-click at the specified coordinates using CDP
 pub async fn right_click(page: &Page, x: f64, y: f64, sleep_ms: Option<u64>) -> Result<()> {
+    crate::debug!("right_click at ({:.0}, {:.0})", x, y);
+    mouse_click(page, x, y, MouseButton::Right, sleep_ms).await
+}
+
+/// Left-click at the specified coordinates using CDP
+pub async fn left_click(page: &Page, x: f64, y: f64, sleep_ms: Option<u64>) -> Result<()> {
+    crate::debug!("left_click at ({:.0}, {:.0})", x, y);
+    mouse_click(page, x, y, MouseButton::Left, sleep_ms).await
+}
+
+/// Click `button` at the specified coordinates using CDP
+async fn mouse_click(
+    page: &Page,
+    x: f64,
+    y: f64,
+    button: MouseButton,
+    sleep_ms: Option<u64>,
+) -> Result<()> {
     // Move mouse to position
     let move_params = DispatchMouseEventParams::builder()
         .r#type(DispatchMouseEventType::MouseMoved)
@@ -178,23 +339,23 @@ pub async fn right_click(page: &Page, x: f64, y: f64, sleep_ms: Option<u64>) ->
         .unwrap();
     page.execute(move_params).await?;
 
-    // Press right mouse button
+    // Press the mouse button
     let down_params = DispatchMouseEventParams::builder()
         .r#type(DispatchMouseEventType::MousePressed)
         .x(x)
         .y(y)
-        .button(MouseButton::Right)
+        .button(button.clone())
         .click_count(1)
         .build()
         .unwrap();
     page.execute(down_params).await?;
 
-    // Release right mouse button
+    // Release the mouse button
     let up_params = DispatchMouseEventParams::builder()
         .r#type(DispatchMouseEventType::MouseReleased)
         .x(x)
         .y(y)
-        .button(MouseButton::Right)
+        .button(button)
         .click_count(1)
         .build()
         .unwrap();
@@ -208,19 +369,81 @@ pub async fn right_click(page: &Page, x: f64, y: f64, sleep_ms: Option<u64>) ->
 
 /// Get the center position of a message element
 pub async fn get_message_position(page: &Page, id: &str) -> Result<Option<(f64, f64)>> {
+    let script = r#"
+        (selector) => {
+            const item = document.querySelector(selector);
+            if (!item) return null;
+            const rect = item.getBoundingClientRect();
+            return { x: rect.x + rect.width / 2, y: rect.y + rect.height / 2 };
+        }
+        "#;
+
+    let selector = crate::browser::message_selector(id);
+    let result =
+        evaluate_with_args(page, "get_message_position", script, &[selector.into()]).await?;
+    let pos: Option<serde_json::Value> = result.into_value().ok();
+
+    Ok(pos.and_then(|p| {
+        let x = p.get("x").and_then(|v| v.as_f64())?;
+        let y = p.get("y").and_then(|v| v.as_f64())?;
+        Some((x, y))
+    }))
+}
+
+/// Get the center position of a folder in the folder pane
+/// Only the mailbox tree is searched; the Favorites copy of a folder is skipped, as it
+/// offers a different context menu.
+pub async fn get_folder_position(page: &Page, name: &str) -> Result<Option<(f64, f64)>> {
     let script = format!(
         r#"
         (() => {{
-            const item = document.querySelector('[data-convid="{}"]');
+            const name = {name};
+            const titleOf = item => item.querySelector('span[title]')?.getAttribute('title')
+                || item.getAttribute('title') || item.textContent?.trim() || '';
+            let inFavorites = false;
+            const items = Array.from(document.querySelectorAll('[role="treeitem"]')).filter(item => {{
+                if ((item.getAttribute('aria-level') || '1') === '1') {{
+                    inFavorites = titleOf(item).trim().toLowerCase() === 'favorites';
+                }}
+                return !inFavorites;
+            }});
+            // An exact name beats a longer one that merely starts with it
+            const item = items.find(item => titleOf(item) === name)
+                || items.find(item => titleOf(item).startsWith(name));
             if (!item) return null;
             const rect = item.getBoundingClientRect();
             return {{ x: rect.x + rect.width / 2, y: rect.y + rect.height / 2 }};
         }})()
         "#,
-        id
+        name = serde_json::to_string(name)?
     );
 
-    let result = page.evaluate(script).await?;
+    let result = evaluate(page, "get_folder_position", script).await?;
+    let pos: Option<serde_json::Value> = result.into_value().ok();
+
+    Ok(pos.and_then(|p| {
+        let x = p.get("x").and_then(|v| v.as_f64())?;
+        let y = p.get("y").and_then(|v| v.as_f64())?;
+        Some((x, y))
+    }))
+}
+
+/// Get the center position of the mailbox root (the account heading) in the folder pane
+pub async fn get_folder_root_position(page: &Page) -> Result<Option<(f64, f64)>> {
+    let script = r#"
+        (() => {
+            for (const item of document.querySelectorAll('[role="treeitem"][aria-level="1"]')) {
+                const title = item.getAttribute('title') || item.textContent?.trim() || '';
+                if (title.includes('@')) {
+                    const rect = item.getBoundingClientRect();
+                    return { x: rect.x + rect.width / 2, y: rect.y + rect.height / 2 };
+                }
+            }
+            return null;
+        })()
+    "#;
+
+    let result = evaluate(page, "get_folder_root_position", script).await?;
     let pos: Option<serde_json::Value> = result.into_value().ok();
 
     Ok(pos.and_then(|p| {
@@ -236,7 +459,7 @@ pub async fn wait_for_message(page: &Page, id: &str) -> Result<bool> {
         r#"
         (async () => {{
             for (let i = 0; i < 50; i++) {{
-                const item = document.querySelector('[data-convid="{}"]');
+                const item = document.querySelector({});
                 if (item && item.getBoundingClientRect().height > 0) {{
                     return true;
                 }}
@@ -245,10 +468,10 @@ pub async fn wait_for_message(page: &Page, id: &str) -> Result<bool> {
             return false;
         }})()
         "#,
-        id
+        serde_json::to_string(&crate::browser::message_selector(id))?
     );
 
-    let result = page.evaluate(script).await?;
+    let result = evaluate(page, "wait_for_message", script).await?;
     Ok(result.into_value::<bool>().unwrap_or(false))
 }
 
@@ -273,39 +496,35 @@ pub async fn extract_categories_from_dialog(page: &Page) -> Result<Vec<String>>
         })()
     "#;
 
-    let result = page.evaluate(script).await?;
-    let categories_json = result.into_value::<String>().unwrap_or_default();
-    let categories: Vec<String> = serde_json::from_str(&categories_json).unwrap_or_default();
-    Ok(categories)
+    evaluate_json(page, "extract_categories_from_dialog", script).await
 }
 
 /// Extract category names from the submenu (fallback if dialog extraction fails)
 pub async fn extract_categories_from_submenu(page: &Page) -> Result<Vec<String>> {
-    let script = r#"
-        (() => {
+    let skip = serde_json::to_string(&[localize("clear"), localize("all categor")])?;
+    let script = format!(
+        r#"
+        (() => {{
             const categories = [];
             const items = document.querySelectorAll('[role="menuitemcheckbox"]');
-            for (const item of items) {
+            for (const item of items) {{
                 let text = item.textContent?.trim() || '';
                 // Remove icon prefix (categories often start with a colored icon)
                 const parts = text.split(/[\s\u00A0]/);
-                if (parts.length > 1) {
+                if (parts.length > 1) {{
                     text = parts.slice(1).join(' ').trim();
-                }
+                }}
                 if (text && text.length > 0 && text.length < 50 &&
-                    !text.toLowerCase().includes('clear') &&
-                    !text.toLowerCase().includes('all categor')) {
+                    !{skip}.some(s => text.toLowerCase().includes(s))) {{
                     categories.push(text);
-                }
-            }
+                }}
+            }}
             return JSON.stringify(categories);
-        })()
-    "#;
+        }})()
+    "#
+    );
 
-    let result = page.evaluate(script).await?;
-    let categories_json = result.into_value::<String>().unwrap_or_default();
-    let categories: Vec<String> = serde_json::from_str(&categories_json).unwrap_or_default();
-    Ok(categories)
+    evaluate_json(page, "extract_categories_from_submenu", &script).await
 }
 
 /// List all visible menu items (for debugging)
@@ -321,9 +540,7 @@ pub async fn list_menu_items(page: &Page) -> Result<Vec<String>> {
         })()
     "#;
 
-    let result = page.evaluate(script).await?;
-    let json = result.into_value::<String>().unwrap_or_default();
-    Ok(serde_json::from_str(&json).unwrap_or_default())
+    evaluate_json(page, "list_menu_items", script).await
 }
 
 /// Click on "Manage Categories" to open the categories dialog
@@ -340,7 +557,7 @@ pub async fn create_category(page: &Page, name: &str) -> Result<()> {
     let mut dialog_opened = false;
     for _ in 0..10 {
         let script = r#"document.querySelector('[role="dialog"]') !== null"#;
-        let result = page.evaluate(script).await?;
+        let result = evaluate(page, "create_category", script).await?;
         if result.into_value::<bool>().unwrap_or(false) {
             dialog_opened = true;
             break;
@@ -365,7 +582,7 @@ pub async fn create_category(page: &Page, name: &str) -> Result<()> {
         })()
     "#;
 
-    let result = page.evaluate(focus_script).await?;
+    let result = evaluate(page, "create_category:focus", focus_script).await?;
     if !result.into_value::<bool>().unwrap_or(false) {
         anyhow::bail!("Could not find category name input");
     }
@@ -395,7 +612,7 @@ pub async fn create_category(page: &Page, name: &str) -> Result<()> {
         })()
     "#;
 
-    let result = page.evaluate(save_script).await?;
+    let result = evaluate(page, "create_category:save", save_script).await?;
     if !result.into_value::<bool>().unwrap_or(false) {
         anyhow::bail!("Could not find Save button in category dialog");
     }
@@ -406,6 +623,69 @@ pub async fn create_category(page: &Page, name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Wait for a dialog and click the first button whose text contains one of `labels`
+/// (partial match, case-insensitive, tried in order)
+pub async fn click_dialog_button(
+    page: &Page,
+    labels: &[&str],
+    sleep_ms: Option<u64>,
+) -> Result<()> {
+    let labels_json =
+        serde_json::to_string(&labels.iter().map(|l| localize(l)).collect::<Vec<_>>())?;
+    let script = format!(
+        r#"
+        (async () => {{
+            const labels = {labels_json};
+            for (let i = 0; i < 20; i++) {{
+                const dialog = document.querySelector('[role="dialog"], [role="alertdialog"]');
+                if (dialog) {{
+                    const buttons = Array.from(dialog.querySelectorAll('button'));
+                    for (const label of labels) {{
+                        const btn = buttons.find(b => (b.textContent?.toLowerCase() || '').includes(label));
+                        if (btn) {{
+                            btn.click();
+                            return true;
+                        }}
+                    }}
+                }}
+                await new Promise(r => setTimeout(r, 100));
+            }}
+            return false;
+        }})()
+        "#
+    );
+
+    let result = evaluate(page, "click_dialog_button", script).await?;
+    if !result.into_value::<bool>().unwrap_or(false) {
+        anyhow::bail!("Dialog button not found: {}", labels.join(" / "));
+    }
+
+    let ms = sleep_ms.unwrap_or(500);
+    tokio::time::sleep(tokio::time::Duration::from_millis(ms)).await;
+
+    Ok(())
+}
+
+/// Close the open dialog by clicking its X button or the backdrop
+pub async fn close_dialog(page: &Page) -> Result<()> {
+    let close_script = r#"
+        (() => {
+            // Try close button first
+            const closeBtn = document.querySelector('[role="dialog"] button[aria-label*="Close"], [role="dialog"] button[aria-label*="close"]');
+            if (closeBtn) { closeBtn.click(); return true; }
+            // Try clicking backdrop/overlay
+            const backdrop = document.querySelector('[class*="overlay"], [class*="backdrop"]');
+            if (backdrop) { backdrop.click(); return true; }
+            // Click outside dialog
+            document.body.click();
+            return false;
+        })()
+    "#;
+    evaluate(page, "close_dialog", close_script).await?;
+    tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+    Ok(())
+}
+
 /// Click on the "Categorize" menu item to open the submenu
 pub async fn click_categorize(page: &Page, sleep_ms: Option<u64>) -> Result<()> {
     click_menu_item(page, "categorize", sleep_ms).await
@@ -413,37 +693,67 @@ pub async fn click_categorize(page: &Page, sleep_ms: Option<u64>) -> Result<()>
 
 /// Click on a specific category in the submenu
 /// Assumes the category submenu is already open
-pub async fn click_category(page: &Page, label: &str, sleep_ms: Option<u64>) -> Result<()> {
+/// Returns the category's full name, which may differ from `label` when matched by prefix
+pub async fn click_category(
+    page: &Page,
+    label: &str,
+    prefix: bool,
+    sleep_ms: Option<u64>,
+) -> Result<String> {
     // Small delay to ensure submenu is fully rendered
     tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
 
-    // Click the specific category only if not already checked
+    // Click the matching category only if not already checked
     let click_script = format!(
         r#"
-        (() => {{
-            const items = document.querySelectorAll('[role="menuitemcheckbox"], [role="menuitem"]');
-            for (const item of items) {{
-                const text = item.textContent?.trim() || '';
-                if (text.endsWith('{label}')) {{
-                    // Check if already applied (aria-checked="true")
-                    if (item.getAttribute('aria-checked') === 'true') {{
-                        return 'already_set';
-                    }}
-                    item.click();
-                    return 'clicked';
-                }}
+        (label, prefix) => {{
+            {find_category}
+            const match = findCategory(label, prefix);
+            if (match.status !== 'found') {{
+                return JSON.stringify({{ status: match.status, candidates: match.candidates }});
             }}
-            return 'not_found';
-        }})()
+            // Check if already applied (aria-checked="true")
+            if (match.item.getAttribute('aria-checked') === 'true') {{
+                return JSON.stringify({{ status: 'already_set', candidates: match.candidates }});
+            }}
+            match.item.click();
+            return JSON.stringify({{ status: 'clicked', candidates: match.candidates }});
+        }}
         "#,
-        label = label
+        find_category = FIND_CATEGORY_JS
     );
 
-    let result = page.evaluate(click_script.as_str()).await?;
-    let status = result.into_value::<String>().unwrap_or_default();
-
-    if status == "not_found" {
-        anyhow::bail!("Category not found: {}", label);
+    let result = evaluate_with_args(
+        page,
+        "click_category:click",
+        &click_script,
+        &[label.into(), prefix.into()],
+    )
+    .await?;
+    // Not retried: the script may already have clicked
+    let json = result
+        .into_value::<String>()
+        .context("click_category did not return JSON")?;
+    let outcome: serde_json::Value = serde_json::from_str(&json)
+        .with_context(|| format!("click_category returned unexpected JSON: {}", json))?;
+    let status = outcome["status"].as_str().unwrap_or("not_found");
+    let candidates: Vec<&str> = outcome["candidates"]
+        .as_array()
+        .map(|c| c.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+
+    crate::debug!("click_category {:?}: {} {:?}", label, status, candidates);
+    match status {
+        "not_found" => anyhow::bail!("Category not found: {}", label),
+        "ambiguous" => {
+            close_menus(page).await?;
+            anyhow::bail!(
+                "Category {:?} is ambiguous, matches: {}",
+                label,
+                candidates.join(", ")
+            );
+        }
+        _ => {}
     }
     // If "already_set" or "clicked", we're good
 
@@ -453,5 +763,5 @@ pub async fn click_category(page: &Page, label: &str, sleep_ms: Option<u64>) ->
     // Close menu after clicking category using close_menus for consistent cleanup
     close_menus(page).await?;
 
-    Ok(())
+    Ok(candidates.first().map_or(label, |c| c).to_string())
 }
@@ -0,0 +1,144 @@
+use crate::api::{Client, InboxView};
+use crate::stats::Stats;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Render inbox stats in the Prometheus text exposition format
+pub fn to_prometheus(stats: &Stats) -> String {
+    let mut out = String::new();
+    out.push_str("# HELP outlook_inbox_total Messages listed in the inbox.\n");
+    out.push_str("# TYPE outlook_inbox_total gauge\n");
+    out.push_str(&format!("outlook_inbox_total {}\n", stats.total));
+    out.push_str("# HELP outlook_inbox_unread Unread messages listed in the inbox.\n");
+    out.push_str("# TYPE outlook_inbox_unread gauge\n");
+    out.push_str(&format!("outlook_inbox_unread {}\n", stats.unread));
+    out.push_str("# HELP outlook_inbox_label_messages Inbox messages carrying each label.\n");
+    out.push_str("# TYPE outlook_inbox_label_messages gauge\n");
+    for (label, count) in &stats.labels {
+        out.push_str(&format!(
+            "outlook_inbox_label_messages{{label=\"{}\"}} {}\n",
+            escape_label(label),
+            count
+        ));
+    }
+    out
+}
+
+/// Escape a label value as the exposition format requires
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// How long a client gets to send its request before the connection is dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Last scrape and when it was taken, shared by the connection tasks
+type Cache = tokio::sync::Mutex<Option<(Instant, String)>>;
+
+/// Serve `/metrics` on `addr`, scraping at most `max` messages of the `view` inbox pivot
+/// A scrape is reused for `interval`, so frequent Prometheus polls don't drive the browser.
+/// Each connection is handled in its own task; a slow or broken client only loses its
+/// own request.
+pub async fn serve(
+    client: Client,
+    view: InboxView,
+    addr: &str,
+    max: u32,
+    interval: Duration,
+) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    crate::browser::keep_connection();
+    eprintln!(
+        "Serving metrics on http://{}/metrics",
+        listener.local_addr()?
+    );
+
+    let client = Arc::new(client);
+    let cache: Arc<Cache> = Arc::default();
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                crate::debug!("metrics: accept failed: {}", e);
+                continue;
+            }
+        };
+        let client = client.clone();
+        let cache = cache.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, &client, &cache, view, max, interval).await {
+                crate::debug!("metrics: connection from {} failed: {:#}", peer, e);
+            }
+        });
+    }
+}
+
+/// Answer one HTTP request
+async fn handle(
+    mut stream: tokio::net::TcpStream,
+    client: &Client,
+    cache: &Cache,
+    view: InboxView,
+    max: u32,
+    interval: Duration,
+) -> Result<()> {
+    // Only the request line matters; headers are read so the client sees a clean close
+    let request_line = tokio::time::timeout(REQUEST_TIMEOUT, async {
+        let mut reader = BufReader::new(&mut stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).await?;
+        let mut header = String::new();
+        while reader.read_line(&mut header).await? > 2 {
+            header.clear();
+        }
+        anyhow::Ok(request_line)
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("no request within {:?}", REQUEST_TIMEOUT))??;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+    let (status, body) = if path == "/metrics" || path.starts_with("/metrics?") {
+        // Held across the scrape, so concurrent polls wait for one scrape instead of each
+        // driving the browser
+        let mut cached = cache.lock().await;
+        let fresh = cached
+            .as_ref()
+            .filter(|(at, _)| at.elapsed() < interval)
+            .map(|(_, body)| body.clone());
+        match fresh {
+            Some(body) => ("200 OK", body),
+            None => match scrape(client, view, max).await {
+                Ok(body) => {
+                    *cached = Some((Instant::now(), body.clone()));
+                    ("200 OK", body)
+                }
+                Err(e) => (
+                    "500 Internal Server Error",
+                    format!("scrape failed: {:#}\n", e),
+                ),
+            },
+        }
+    } else {
+        ("404 Not Found", "Try /metrics\n".to_string())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+        Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+async fn scrape(client: &Client, view: InboxView, max: u32) -> Result<String> {
+    let messages = client.list_messages(max, view).await?;
+    Ok(to_prometheus(&crate::stats::summarize(&messages, 0)))
+}
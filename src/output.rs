@@ -0,0 +1,738 @@
+use crate::api::Message;
+use anyhow::Result;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Set by `--no-color`
+static COLOR_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turn colors off for the rest of the run, regardless of the terminal
+pub fn disable_color() {
+    COLOR_DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Set by `--quiet`: success messages are dropped, requested data is still printed
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Drop success messages for the rest of the run
+pub fn set_quiet() {
+    QUIET.store(true, Ordering::Relaxed);
+}
+
+/// Whether `--quiet` was given
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Whether to emit ANSI colors: stdout is a TTY, NO_COLOR is unset and --no-color wasn't passed
+pub fn use_color() -> bool {
+    !COLOR_DISABLED.load(Ordering::Relaxed)
+        && std::env::var_os("NO_COLOR").is_none()
+        && std::io::stdout().is_terminal()
+}
+
+/// Wrap a message's row in its colors: bold when unread, yellow when flagged, dim otherwise
+fn paint(line: &str, msg: &Message) -> String {
+    let mut style = String::new();
+    if msg.is_unread {
+        style.push_str(BOLD);
+    }
+    if msg.is_flagged {
+        style.push_str(YELLOW);
+    }
+    if style.is_empty() {
+        style.push_str(DIM);
+    }
+    format!("{}{}{}", style, line, RESET)
+}
+
+/// Format a message as one `*id | from | subject (count) [labels]` line, colored when `color` is set
+/// Unread messages are marked with `*`, so the plain form carries the same information.
+pub fn format_line(msg: &Message, color: bool) -> String {
+    let from = msg.from.as_deref().unwrap_or("Unknown");
+    let subject = msg.subject.as_deref().unwrap_or("(no subject)");
+    let unread = if msg.is_unread { "*" } else { " " };
+    let count = msg
+        .message_count
+        .map(|n| format!(" ({})", n))
+        .unwrap_or_default();
+    let labels = if msg.labels.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", msg.labels.join(", "))
+    };
+    let line = format!(
+        "{}{} | {} | {}{}{}",
+        unread, msg.id, from, subject, count, labels
+    );
+    if color { paint(&line, msg) } else { line }
+}
+
+/// Width of the terminal in columns, from COLUMNS, or 120 when that isn't set
+pub fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .filter(|&c: &usize| c > 0)
+        .unwrap_or(120)
+}
+
+/// Shorten `text` to at most `width` characters, ending in an ellipsis when cut
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut out: String = text.chars().take(width - 1).collect();
+    out.push('…');
+    out
+}
+
+fn pad(text: &str, width: usize) -> String {
+    let len = text.chars().count();
+    format!("{}{}", text, " ".repeat(width.saturating_sub(len)))
+}
+
+/// Format messages as an aligned table fitting `width` columns
+/// Sender and subject are truncated to fit; the ID is never truncated so it can be copied.
+/// Rows are colored as in `format_line` when `color` is set.
+pub fn format_table(messages: &[Message], width: usize, color: bool) -> String {
+    const SEP: &str = "  ";
+
+    let rows: Vec<[String; 5]> = messages
+        .iter()
+        .map(|msg| {
+            [
+                msg.from.clone().unwrap_or_else(|| "Unknown".to_string()),
+                msg.subject
+                    .clone()
+                    .unwrap_or_else(|| "(no subject)".to_string()),
+                msg.labels.join(", "),
+                msg.folder.clone().unwrap_or_default(),
+                msg.id.clone(),
+            ]
+        })
+        .collect();
+
+    let col_width = |i: usize, header: &str| {
+        rows.iter()
+            .map(|r| r[i].chars().count())
+            .chain(std::iter::once(header.len()))
+            .max()
+            .unwrap_or(0)
+    };
+    // Optional columns are dropped (width 0) when no row has a value
+    let optional_width = |i: usize, header: &str| {
+        if rows.iter().any(|r| !r[i].is_empty()) {
+            col_width(i, header)
+        } else {
+            0
+        }
+    };
+    let labels_w = optional_width(2, "LABELS");
+    let folder_w = optional_width(3, "FOLDER");
+    let id_w = col_width(4, "ID");
+
+    // Marker column, separators, labels, folder and ID are fixed; sender and subject share the rest
+    let optional_sep = |w: usize| if w > 0 { SEP.len() } else { 0 };
+    let fixed = 1
+        + SEP.len() * 3
+        + labels_w
+        + optional_sep(labels_w)
+        + folder_w
+        + optional_sep(folder_w)
+        + id_w;
+    let flexible = width.saturating_sub(fixed).max(20);
+    let from_w = col_width(0, "FROM").min(flexible / 3).max(4);
+    let subject_w = col_width(1, "SUBJECT").min(flexible - from_w).max(7);
+
+    let format_row = |marker: &str, cols: [&str; 5]| {
+        let mut line = format!(
+            "{}{}{}{}{}",
+            marker,
+            SEP,
+            pad(&truncate(cols[0], from_w), from_w),
+            SEP,
+            pad(&truncate(cols[1], subject_w), subject_w)
+        );
+        if labels_w > 0 {
+            line.push_str(SEP);
+            line.push_str(&pad(cols[2], labels_w));
+        }
+        if folder_w > 0 {
+            line.push_str(SEP);
+            line.push_str(&pad(cols[3], folder_w));
+        }
+        line.push_str(SEP);
+        line.push_str(cols[4]);
+        line
+    };
+
+    let mut out = format_row(" ", ["FROM", "SUBJECT", "LABELS", "FOLDER", "ID"]);
+    out.push('\n');
+    for (msg, row) in messages.iter().zip(&rows) {
+        let marker = if msg.is_unread { "*" } else { " " };
+        let line = format_row(marker, [&row[0], &row[1], &row[2], &row[3], &row[4]]);
+        if color {
+            out.push_str(&paint(&line, msg));
+        } else {
+            out.push_str(&line);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Field to order a message list by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortField {
+    /// Newest first
+    Date,
+    /// Sender, alphabetically
+    From,
+    /// Subject, alphabetically
+    Subject,
+    /// Unread messages first
+    Unread,
+}
+
+/// Parse an Outlook timestamp into a sortable (year, month, day, hour, minute) key
+/// Accepts the list's title format, e.g. `Tue 10/14/2025 3:42 PM` or `Tue 2025-10-14 15:42`,
+/// with or without the weekday and time. Two-digit years are taken as 20xx.
+/// Whether a slash date is month or day first depends on the locale, so it is worked out
+/// from a part above 12 or from the weekday; a date that could be either is None.
+fn date_key(date: &str) -> Option<(u32, u32, u32, u32, u32)> {
+    let weekday_name = date
+        .split_whitespace()
+        .next()
+        .filter(|w| w.starts_with(|c: char| c.is_alphabetic()))
+        .map(|w| w.to_lowercase());
+    let mut parts = date
+        .split_whitespace()
+        .skip_while(|p| !p.starts_with(|c: char| c.is_ascii_digit()));
+
+    let day_part = parts.next()?;
+    let nums: Vec<u32> = day_part
+        .split(['/', '-', '.'])
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    let (year, month, day) = match nums[..] {
+        [y, m, d] if y > 31 => (y, m, d),
+        [a, b, y] => {
+            let year = if y < 100 { 2000 + y } else { y };
+            let on_weekday = |month: u32, day: u32| {
+                weekday_name
+                    .as_deref()
+                    .is_some_and(|name| name.starts_with(&weekday(year, month, day).to_lowercase()))
+            };
+            match (a > 12, b > 12) {
+                (false, true) => (year, a, b),
+                (true, false) => (year, b, a),
+                (false, false) if a == b => (year, a, b),
+                (false, false) => match (on_weekday(a, b), on_weekday(b, a)) {
+                    (true, false) => (year, a, b),
+                    (false, true) => (year, b, a),
+                    _ => return None,
+                },
+                (true, true) => return None,
+            }
+        }
+        _ => return None,
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let (mut hour, minute) = match parts.next() {
+        Some(time) => {
+            let (h, m) = time.split_once(':')?;
+            (h.parse().ok()?, m.parse().ok()?)
+        }
+        None => (0, 0),
+    };
+    match parts.next().map(str::to_ascii_uppercase).as_deref() {
+        Some("PM") if hour < 12 => hour += 12,
+        Some("AM") if hour == 12 => hour = 0,
+        _ => {}
+    }
+    Some((year, month, day, hour, minute))
+}
+
+/// Whole days between an Outlook timestamp and now, or None if it can't be parsed
+/// Counted in UTC calendar days, so a message from late yesterday is one day old.
+pub fn days_old(date: &str) -> Option<i64> {
+    let (year, month, day, _, _) = date_key(date)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs() as i64;
+    Some(now / 86_400 - days_from_civil(year as i64, month as i64, day as i64))
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Order `a` and `b` (or the reverse), keeping missing values after present ones
+fn cmp_present<T: Ord>(a: Option<T>, b: Option<T>, reverse: bool) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    match (a, b) {
+        (Some(a), Some(b)) if reverse => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Sort messages in place by `field`, reversed with `reverse`
+/// The sort is stable, and messages without the field (or with a date that can't be
+/// parsed) stay at the end in page order either way.
+pub fn sort_messages(messages: &mut [Message], field: SortField, reverse: bool) {
+    use std::cmp::Reverse;
+
+    let text = |s: &Option<String>| s.as_deref().map(str::to_lowercase);
+
+    match field {
+        SortField::Date => messages.sort_by(|a, b| {
+            let key = |m: &Message| m.date.as_deref().and_then(date_key).map(Reverse);
+            cmp_present(key(a), key(b), reverse)
+        }),
+        SortField::From => {
+            messages.sort_by(|a, b| cmp_present(text(&a.from), text(&b.from), reverse))
+        }
+        SortField::Subject => {
+            messages.sort_by(|a, b| cmp_present(text(&a.subject), text(&b.subject), reverse))
+        }
+        SortField::Unread => messages.sort_by(|a, b| {
+            cmp_present(
+                Some(Reverse(a.is_unread)),
+                Some(Reverse(b.is_unread)),
+                reverse,
+            )
+        }),
+    }
+}
+
+/// Field to bucket a message list by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// One group per sender
+    Sender,
+    /// One group per label; a message with several labels appears in each
+    Label,
+}
+
+/// Bucket messages by `by`, largest group first
+/// Messages keep their order within a group, so sort before grouping. Groups of the same
+/// size stay in order of first appearance.
+pub fn group_messages(messages: &[Message], by: GroupBy) -> Vec<(String, Vec<Message>)> {
+    let mut groups: Vec<(String, Vec<Message>)> = Vec::new();
+    let mut add = |key: &str, msg: &Message| match groups.iter_mut().find(|(k, _)| k == key) {
+        Some((_, list)) => list.push(msg.clone()),
+        None => groups.push((key.to_string(), vec![msg.clone()])),
+    };
+
+    for msg in messages {
+        match by {
+            GroupBy::Sender => add(msg.from.as_deref().unwrap_or("Unknown"), msg),
+            GroupBy::Label if msg.labels.is_empty() => add("(no label)", msg),
+            GroupBy::Label => {
+                for label in &msg.labels {
+                    add(label, msg);
+                }
+            }
+        }
+    }
+
+    groups.sort_by_key(|(_, list)| std::cmp::Reverse(list.len()));
+    groups
+}
+
+/// Field names a message serializes with, as accepted by `--fields`
+pub const MESSAGE_FIELDS: &[&str] = &[
+    "id",
+    "immutableId",
+    "subject",
+    "from",
+    "to",
+    "cc",
+    "date",
+    "body",
+    "bodyHtml",
+    "preview",
+    "labels",
+    "labelColors",
+    "isUnread",
+    "isPinned",
+    "isFlagged",
+    "messageCount",
+    "folder",
+];
+
+/// Check requested field names against `MESSAGE_FIELDS`
+pub fn validate_fields(fields: &[String]) -> Result<()> {
+    let unknown: Vec<&str> = fields
+        .iter()
+        .map(String::as_str)
+        .filter(|f| !MESSAGE_FIELDS.contains(f))
+        .collect();
+    if !unknown.is_empty() {
+        anyhow::bail!(
+            "Unknown field(s): {} (known: {})",
+            unknown.join(", "),
+            MESSAGE_FIELDS.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Serialize a message keeping only `fields` (all fields when empty)
+/// Fields the message omits (e.g. empty `labelColors`) come out as null.
+pub fn project(msg: &Message, fields: &[String]) -> Result<serde_json::Value> {
+    let value = serde_json::to_value(msg)?;
+    if fields.is_empty() {
+        return Ok(value);
+    }
+    let object = fields
+        .iter()
+        .map(|f| (f.clone(), value.get(f).cloned().unwrap_or_default()))
+        .collect::<serde_json::Map<_, _>>();
+    Ok(object.into())
+}
+
+/// Serialize messages as CSV with a header row
+/// Columns: id, from, subject, date, unread, labels (joined by `;`)
+pub fn to_csv(messages: &[Message]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["id", "from", "subject", "date", "unread", "labels"])?;
+    for msg in messages {
+        writer.write_record([
+            msg.id.as_str(),
+            msg.from.as_deref().unwrap_or(""),
+            msg.subject.as_deref().unwrap_or(""),
+            msg.date.as_deref().unwrap_or(""),
+            if msg.is_unread { "true" } else { "false" },
+            &msg.labels.join(";"),
+        ])?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// Encode a header value as an RFC 2047 encoded-word if it isn't plain ASCII
+fn encode_header(value: &str) -> String {
+    use base64::Engine;
+
+    if value.is_ascii() {
+        return value.replace(['\r', '\n'], " ");
+    }
+    format!(
+        "=?UTF-8?B?{}?=",
+        base64::engine::general_purpose::STANDARD.encode(value)
+    )
+}
+
+/// Build a minimal RFC 822 message from extracted fields
+/// This is a reconstruction, not the original MIME: Bcc, headers other than
+/// From/To/Cc/Subject/Date, attachments, and inline images are not included.
+pub fn to_eml(msg: &Message) -> String {
+    let mut out = String::new();
+    out.push_str(&format!(
+        "From: {}\r\n",
+        encode_header(msg.from.as_deref().unwrap_or("Unknown"))
+    ));
+    if !msg.to.is_empty() {
+        out.push_str(&format!("To: {}\r\n", encode_header(&msg.to.join(", "))));
+    }
+    if !msg.cc.is_empty() {
+        out.push_str(&format!("Cc: {}\r\n", encode_header(&msg.cc.join(", "))));
+    }
+    out.push_str(&format!(
+        "Subject: {}\r\n",
+        encode_header(msg.subject.as_deref().unwrap_or(""))
+    ));
+    if let Some(date) = msg.date.as_deref().and_then(rfc5322_date) {
+        out.push_str(&format!("Date: {}\r\n", date));
+    }
+    out.push_str("MIME-Version: 1.0\r\n");
+
+    match (&msg.body_html, &msg.body) {
+        // HTML goes out with a plain-text alternative for clients that won't render it
+        (Some(html), _) => {
+            const BOUNDARY: &str = "outlook-web-alternative";
+            out.push_str(&format!(
+                "Content-Type: multipart/alternative; boundary=\"{}\"\r\n\r\n",
+                BOUNDARY
+            ));
+            for (content_type, body) in [
+                ("text/plain", crate::html::to_text(html)),
+                ("text/html", html.clone()),
+            ] {
+                out.push_str(&format!("--{}\r\n", BOUNDARY));
+                push_base64_part(&mut out, content_type, &body);
+            }
+            out.push_str(&format!("--{}--\r\n", BOUNDARY));
+        }
+        (None, text) => push_base64_part(&mut out, "text/plain", text.as_deref().unwrap_or("")),
+    }
+    out
+}
+
+/// Header recording a message's ID in an mbox export, so a rerun can skip it
+const MBOX_ID_HEADER: &str = "X-Outlook-Web-Id: ";
+
+/// One mbox (mboxrd) entry: a `From ` separator, the `to_eml` reconstruction with the
+/// message ID as an extra header, and a blank line
+/// Lines become LF-terminated, and lines starting with `From ` after any `>` get one more `>`.
+pub fn to_mbox(msg: &Message) -> String {
+    let eml = format!("{}{}\r\n{}", MBOX_ID_HEADER, msg.id, to_eml(msg));
+    mbox_entry(
+        &format!("From {} {}", mbox_sender(msg), mbox_date(msg)),
+        &eml,
+    )
+}
+
+/// `separator`, then the lines of `eml` with LF endings, quoting any that read as a separator
+fn mbox_entry(separator: &str, eml: &str) -> String {
+    let mut out = format!("{}\n", separator);
+    for line in eml.split("\r\n") {
+        if line.trim_start_matches('>').starts_with("From ") {
+            out.push('>');
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// IDs of the messages already in an mbox export
+/// Only the header right after each `From ` separator counts, so a body line that happens
+/// to look like the ID header is ignored.
+pub fn mbox_ids(mbox: &str) -> std::collections::HashSet<String> {
+    let mut lines = mbox.lines().peekable();
+    let mut ids = std::collections::HashSet::new();
+    while let Some(line) = lines.next() {
+        if line.starts_with("From ")
+            && let Some(id) = lines.peek().and_then(|l| l.strip_prefix(MBOX_ID_HEADER))
+        {
+            ids.insert(id.trim().to_string());
+        }
+    }
+    ids
+}
+
+/// Address for the `From ` separator: the sender's address when it shows one
+fn mbox_sender(msg: &Message) -> String {
+    let from = msg.from.as_deref().unwrap_or("");
+    let address = match (from.rfind('<'), from.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &from[start + 1..end],
+        _ => from,
+    };
+    if address.contains('@') && !address.contains(char::is_whitespace) {
+        address.to_string()
+    } else {
+        "MAILER-DAEMON".to_string()
+    }
+}
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Abbreviated weekday name for a date `date_key` accepted
+fn weekday(year: u32, month: u32, day: u32) -> &'static str {
+    WEEKDAYS[days_from_civil(year as i64, month as i64, day as i64).rem_euclid(7) as usize]
+}
+
+/// An Outlook timestamp as an RFC 5322 `Date:` value, or None if it can't be parsed
+/// Outlook shows local time without saying which zone, so the offset is `-0000`
+/// ("zone unknown").
+fn rfc5322_date(date: &str) -> Option<String> {
+    let (year, month, day, hour, minute) = date_key(date)?;
+    Some(format!(
+        "{}, {} {} {} {:02}:{:02}:00 -0000",
+        weekday(year, month, day),
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        hour,
+        minute
+    ))
+}
+
+/// The message date in the `asctime` form the `From ` separator uses
+/// Falls back to the epoch when the date can't be parsed.
+fn mbox_date(msg: &Message) -> String {
+    let (year, month, day, hour, minute) = msg
+        .date
+        .as_deref()
+        .and_then(date_key)
+        .unwrap_or((1970, 1, 1, 0, 0));
+    format!(
+        "{} {} {:>2} {:02}:{:02}:00 {}",
+        weekday(year, month, day),
+        MONTHS[month as usize - 1],
+        day,
+        hour,
+        minute,
+        year
+    )
+}
+
+/// Append a part's Content-Type and encoding headers followed by `body` in base64
+fn push_base64_part(out: &mut String, content_type: &str, body: &str) {
+    use base64::Engine;
+
+    out.push_str(&format!(
+        "Content-Type: {}; charset=utf-8\r\n",
+        content_type
+    ));
+    out.push_str("Content-Transfer-Encoding: base64\r\n\r\n");
+
+    // Base64 keeps long HTML lines within the 998-character limit
+    let encoded = base64::engine::general_purpose::STANDARD.encode(body);
+    for chunk in encoded.as_bytes().chunks(76) {
+        out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        out.push_str("\r\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str) -> Message {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "subject": "Hello",
+            "from": "Ann <ann@example.com>",
+            "date": "Tue 10/14/2025 3:42 PM",
+            "body": "From the start\nX-Outlook-Web-Id: fake",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn mbox_quotes_from_lines() {
+        let entry = mbox_entry(
+            "From a@b Thu Jan  1 00:00:00 1970",
+            "Subject: x\r\n\r\nFrom here\r\n>From there\r\nFromage",
+        );
+        let lines: Vec<&str> = entry.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "From a@b Thu Jan  1 00:00:00 1970",
+                "Subject: x",
+                "",
+                ">From here",
+                ">>From there",
+                "Fromage",
+            ]
+        );
+    }
+
+    #[test]
+    fn mbox_separator_and_id_header() {
+        let mbox = to_mbox(&message("abc"));
+        let mut lines = mbox.lines();
+        assert_eq!(
+            lines.next(),
+            Some("From ann@example.com Tue Oct 14 15:42:00 2025")
+        );
+        assert_eq!(lines.next(), Some("X-Outlook-Web-Id: abc"));
+        assert_eq!(mbox.lines().filter(|l| l.starts_with("From ")).count(), 1);
+    }
+
+    #[test]
+    fn mbox_ids_ignore_body_lines() {
+        let mut mbox = to_mbox(&message("one"));
+        mbox.push_str(&to_mbox(&message("two")));
+        mbox.push_str("From x Thu Jan  1 00:00:00 1970\nSubject: y\n\nX-Outlook-Web-Id: body\n");
+        let ids = mbox_ids(&mbox);
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains("one") && ids.contains("two"));
+    }
+
+    #[test]
+    fn eml_date_is_rfc5322() {
+        let eml = to_eml(&message("abc"));
+        assert!(eml.contains("\r\nDate: Tue, 14 Oct 2025 15:42:00 -0000\r\n"));
+        assert_eq!(rfc5322_date("yesterday"), None);
+    }
+
+    #[test]
+    fn civil_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(
+            days_from_civil(2024, 2, 29) + 1,
+            days_from_civil(2024, 3, 1)
+        );
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+    }
+
+    #[test]
+    fn dates_parse_or_reject() {
+        assert_eq!(
+            date_key("Tue 10/14/2025 3:42 PM"),
+            Some((2025, 10, 14, 15, 42))
+        );
+        assert_eq!(date_key("2025-10-14 00:05"), Some((2025, 10, 14, 0, 5)));
+        assert_eq!(
+            date_key("Mon 12/1/2025 12:00 AM"),
+            Some((2025, 12, 1, 0, 0))
+        );
+        assert_eq!(date_key("14/10/2025"), Some((2025, 10, 14, 0, 0)));
+        assert_eq!(date_key("2025-13-01"), None);
+        assert_eq!(date_key("13/13/2025"), None);
+    }
+
+    #[test]
+    fn two_digit_years() {
+        assert_eq!(
+            date_key("Tue 10/14/25 3:42 PM"),
+            Some((2025, 10, 14, 15, 42))
+        );
+        assert!(days_old("10/14/25").is_some_and(|age| age < 36_500));
+        assert!(
+            to_mbox(&Message {
+                date: Some("10/14/25 3:42 PM".into()),
+                ..message("abc")
+            })
+            .starts_with("From ann@example.com Tue Oct 14 15:42:00 2025")
+        );
+    }
+
+    #[test]
+    fn ambiguous_day_and_month() {
+        // 3 April and 4 March 2025 are both possible without a weekday to check against
+        assert_eq!(date_key("03/04/2025"), None);
+        assert_eq!(days_old("03/04/2025"), None);
+        // 4 March 2025 was a Tuesday, 3 April a Thursday
+        assert_eq!(date_key("Tue 03/04/2025"), Some((2025, 3, 4, 0, 0)));
+        assert_eq!(date_key("Thu 03/04/2025"), Some((2025, 4, 3, 0, 0)));
+        assert_eq!(date_key("Wed 03/04/2025"), None);
+        let today = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+            / 86_400;
+        assert_eq!(days_old("1/1/1970"), Some(today));
+    }
+}
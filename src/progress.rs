@@ -0,0 +1,77 @@
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by `--json` and `--ndjson`, whose output is meant for programs, and by `--quiet`
+/// Also silences `notice`.
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turn progress counters off for the rest of the run
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Print a one-line notice on stderr, unless the output is meant for programs or --quiet
+pub fn notice(message: std::fmt::Arguments) {
+    if !DISABLED.load(Ordering::Relaxed) {
+        eprintln!("{}", message);
+    }
+}
+
+/// A "label 37/100" counter redrawn in place on stderr
+/// Only drawn when stderr is a terminal and nothing else writes there (no -v logging),
+/// and only for more than one step. The line is cleared when the counter is dropped.
+pub struct Progress {
+    label: String,
+    total: usize,
+    done: usize,
+    visible: bool,
+}
+
+impl Progress {
+    pub fn new(label: &str, total: usize) -> Self {
+        let progress = Progress {
+            label: label.to_string(),
+            total,
+            done: 0,
+            visible: total > 1
+                && !DISABLED.load(Ordering::Relaxed)
+                && !crate::log::enabled(1)
+                && std::io::stderr().is_terminal(),
+        };
+        progress.draw();
+        progress
+    }
+
+    /// Count one more step as done
+    pub fn inc(&mut self) {
+        self.set(self.done + 1);
+    }
+
+    /// Count `done` steps as done in all
+    pub fn set(&mut self, done: usize) {
+        self.done = done.min(self.total);
+        self.draw();
+    }
+
+    fn draw(&self) {
+        if self.visible {
+            let mut stderr = std::io::stderr();
+            let _ = write!(
+                stderr,
+                "\r\x1b[2K{} {}/{}",
+                self.label, self.done, self.total
+            );
+            let _ = stderr.flush();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.visible {
+            let mut stderr = std::io::stderr();
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// When the last paced operation finished
+/// Shared by every client, since they all drive the same Outlook session.
+static LAST_ACTION: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Marks the end of a paced operation when dropped
+pub struct Paced;
+
+impl Drop for Paced {
+    fn drop(&mut self) {
+        *LAST_ACTION.lock().unwrap() = Some(Instant::now());
+    }
+}
+
+/// Wait until the configured pace has passed since the previous mutating operation finished
+/// Hold the returned guard for the whole operation. The gap spans whole operations
+/// (a batch step, a `serve` request), not the individual clicks inside one.
+pub async fn pace() -> Paced {
+    let pace = crate::settings::current().pace;
+    let last = *LAST_ACTION.lock().unwrap();
+    if let Some(wait) = last.and_then(|last| pace.checked_sub(last.elapsed()))
+        && !wait.is_zero()
+    {
+        crate::debug!("pacing: waiting {}ms", wait.as_millis());
+        tokio::time::sleep(wait).await;
+    }
+    Paced
+}
+
+/// Run `f` up to `attempts` times, doubling the delay after each failure
+/// Returns the last error if every attempt fails
+pub async fn retry<T, F, Fut>(attempts: u32, base_delay: Duration, mut f: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let attempts = attempts.max(1);
+    let mut delay = base_delay;
+
+    for attempt in 1..=attempts {
+        match f().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt == attempts => return Err(e),
+            Err(e) => {
+                crate::debug!("attempt {}/{} failed: {:#}", attempt, attempts, e);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+        }
+    }
+
+    unreachable!()
+}
+
+/// `retry` with the configured attempt count and backoff
+pub async fn with_retry<T, F, Fut>(f: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let settings = crate::settings::current();
+    retry(settings.retry_attempts, settings.retry_delay, f).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn pace_spaces_operations() {
+        let mut paced = crate::settings::current().as_ref().clone();
+        paced.pace = Duration::from_millis(500);
+        crate::settings::scope(std::sync::Arc::new(paced), async {
+            let start = Instant::now();
+            drop(pace().await);
+            drop(pace().await);
+            assert_eq!(start.elapsed(), Duration::from_millis(500));
+
+            // The gap counts from when the previous operation finished
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            let before = Instant::now();
+            drop(pace().await);
+            assert_eq!(before.elapsed(), Duration::from_millis(300));
+        })
+        .await;
+
+        // Outside the scope the default config applies, which doesn't pace
+        let before = Instant::now();
+        drop(pace().await);
+        assert_eq!(before.elapsed(), Duration::ZERO);
+    }
+}
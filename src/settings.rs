@@ -0,0 +1,64 @@
+use crate::config::Config;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+/// The parts of a `Config` read deep inside the page helpers, which are not handed a config
+/// Each `Client` keeps its own and installs it around every call (see `scope`), so two
+/// clients with different configs don't affect each other.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// How many times `retry::with_retry` tries a flaky operation
+    pub retry_attempts: u32,
+    /// Initial delay between those tries
+    pub retry_delay: Duration,
+    /// Minimum gap between mutating operations (see `retry::pace`)
+    pub pace: Duration,
+    /// Scrolls made per listing before giving up on reaching `max`
+    pub max_scroll: u32,
+    /// Localized UI text by lowercased English text (see `strings::localize`)
+    pub strings: BTreeMap<String, String>,
+    /// CSS selector for a row in the message list
+    pub message_item: String,
+    /// Account or profile the caches belong to (see `cache`)
+    pub cache_scope: Option<String>,
+}
+
+impl Settings {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            retry_attempts: config.retry_attempts().max(1),
+            retry_delay: config.retry_delay(),
+            pace: config.pace(),
+            max_scroll: config.max_scroll(),
+            strings: config
+                .strings
+                .iter()
+                .map(|(k, v)| (k.to_lowercase(), v.to_lowercase()))
+                .collect(),
+            message_item: config.selectors.message_item().to_string(),
+            cache_scope: config.cache_scope().map(str::to_string),
+        }
+    }
+}
+
+tokio::task_local! {
+    static CURRENT: Arc<Settings>;
+}
+
+/// Settings of a default config, for code running outside any `scope`
+static DEFAULT: LazyLock<Arc<Settings>> =
+    LazyLock::new(|| Arc::new(Settings::new(&Config::default())));
+
+/// Run `f` with `settings` as the ones `current` returns
+pub async fn scope<F: Future>(settings: Arc<Settings>, f: F) -> F::Output {
+    CURRENT.scope(settings, f).await
+}
+
+/// The settings of the innermost enclosing `scope`, or the defaults outside one
+pub fn current() -> Arc<Settings> {
+    CURRENT
+        .try_with(Arc::clone)
+        .unwrap_or_else(|_| DEFAULT.clone())
+}
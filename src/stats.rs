@@ -0,0 +1,90 @@
+use crate::api::Message;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A sender and how many of the listed messages came from them
+#[derive(Debug, Clone, Serialize)]
+pub struct SenderCount {
+    pub from: String,
+    pub count: usize,
+}
+
+/// Aggregates over a listed set of messages
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+    pub total: usize,
+    pub unread: usize,
+    #[serde(rename = "topSenders")]
+    pub top_senders: Vec<SenderCount>,
+    /// Messages per label; a message with several labels counts once under each
+    pub labels: BTreeMap<String, usize>,
+}
+
+/// Summarize `messages`, keeping the `top` most frequent senders
+/// Senders tie-break alphabetically so the report is stable between runs.
+pub fn summarize(messages: &[Message], top: usize) -> Stats {
+    let mut senders: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut labels: BTreeMap<String, usize> = BTreeMap::new();
+
+    for msg in messages {
+        *senders
+            .entry(msg.from.as_deref().unwrap_or("Unknown"))
+            .or_default() += 1;
+        for label in &msg.labels {
+            *labels.entry(label.clone()).or_default() += 1;
+        }
+    }
+
+    let mut top_senders: Vec<SenderCount> = senders
+        .into_iter()
+        .map(|(from, count)| SenderCount {
+            from: from.to_string(),
+            count,
+        })
+        .collect();
+    // Stable sort keeps the alphabetical order from the BTreeMap within equal counts
+    top_senders.sort_by_key(|s| std::cmp::Reverse(s.count));
+    top_senders.truncate(top);
+
+    Stats {
+        total: messages.len(),
+        unread: messages.iter().filter(|m| m.is_unread).count(),
+        top_senders,
+        labels,
+    }
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Messages: {}", self.total)?;
+        write!(f, "Unread:   {}", self.unread)?;
+
+        if !self.top_senders.is_empty() {
+            write!(f, "\n\nTop senders:")?;
+            let width = self
+                .top_senders
+                .iter()
+                .map(|s| s.count.to_string().len())
+                .max()
+                .unwrap_or(1);
+            for sender in &self.top_senders {
+                write!(f, "\n  {:>width$}  {}", sender.count, sender.from)?;
+            }
+        }
+
+        if !self.labels.is_empty() {
+            write!(f, "\n\nLabels:")?;
+            let mut labels: Vec<(&String, &usize)> = self.labels.iter().collect();
+            labels.sort_by_key(|(_, n)| std::cmp::Reverse(**n));
+            let width = labels
+                .iter()
+                .map(|(_, n)| n.to_string().len())
+                .max()
+                .unwrap_or(1);
+            for (label, count) in labels {
+                write!(f, "\n  {:>width$}  {}", count, label)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+/// The text to look for in place of the English `text`, lowercased
+/// Uses the config's `strings` map (see `settings::Settings`), matched case-insensitively,
+/// and falls back to `text` itself when there is no override.
+pub fn localize(text: &str) -> String {
+    let key = text.to_lowercase();
+    crate::settings::current()
+        .strings
+        .get(&key)
+        .cloned()
+        .unwrap_or(key)
+}
+
+/// CSS attribute selector matching the localized `text`, e.g. `[aria-label="discard" i]`
+/// Quoted for CSS and matched ignoring case, since `localize` lowercases.
+pub fn attr_selector(attr: &str, text: &str) -> String {
+    let value = serde_json::to_string(&localize(text)).unwrap_or_default();
+    format!("[{}={} i]", attr, value)
+}
@@ -0,0 +1,53 @@
+use crate::config::Config;
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+/// Look up a canned response by name in the config's `templates` map
+pub fn load<'a>(config: &'a Config, name: &str) -> Result<&'a str> {
+    config
+        .templates
+        .get(name)
+        .map(String::as_str)
+        .ok_or_else(|| {
+            let known: Vec<&str> = config.templates.keys().map(String::as_str).collect();
+            if known.is_empty() {
+                anyhow::anyhow!("Unknown template '{}' (no templates configured)", name)
+            } else {
+                anyhow::anyhow!("Unknown template '{}' (known: {})", name, known.join(", "))
+            }
+        })
+}
+
+/// Replace each `{{key}}` in `template` with its value from `vars`
+/// Whitespace inside the braces is ignored. Errors listing every placeholder without a value.
+pub fn render(template: &str, vars: &BTreeMap<String, String>) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut missing: Vec<&str> = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let key = rest[start + 2..start + 2 + len].trim();
+        match vars.get(key) {
+            Some(value) => out.push_str(value),
+            None => {
+                if !missing.contains(&key) {
+                    missing.push(key);
+                }
+            }
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "Missing template value(s): {} (pass --var key=value)",
+            missing.join(", ")
+        );
+    }
+    Ok(out)
+}